@@ -0,0 +1,187 @@
+//! Opt-in encryption of asset files at rest
+//!
+//! This module provides:
+//! - A session passphrase, held in memory only, that switches new asset
+//!   writes to encrypted mode (plaintext stays the default)
+//! - ChaCha20-Poly1305 sealing with an Argon2id key derived per file
+//! - A small header identifying encrypted files, and decryption on read
+
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::{AssetError, FileResult};
+
+/// Identifies an encrypted asset; the last byte is the format version
+const MAGIC: &[u8; 8] = b"DCENC\0\0\x01";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+lazy_static::lazy_static! {
+    /// Passphrase for this session; None means assets are written in plaintext
+    static ref PASSPHRASE: Mutex<Option<String>> = Mutex::new(None);
+}
+
+fn crypto_error(message: impl Into<String>) -> AssetError {
+    AssetError {
+        code: "CRYPTO_ERROR".to_string(),
+        message: message.into(),
+    }
+}
+
+fn session_passphrase() -> Option<String> {
+    PASSPHRASE.lock().ok().and_then(|p| p.clone())
+}
+
+/// Whether new asset writes are currently encrypted
+pub fn encryption_enabled() -> bool {
+    PASSPHRASE.lock().is_ok_and(|p| p.is_some())
+}
+
+/// Whether `data` starts with the encrypted-asset header
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.len() >= HEADER_LEN && data.starts_with(MAGIC)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, AssetError> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| crypto_error(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+/// Seal `plaintext` as `MAGIC | salt | nonce | ciphertext+tag`
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, AssetError> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| crypto_error("Encryption failed"))?;
+
+    let mut out = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Open data produced by `encrypt`. Fails on a wrong passphrase or any tampering.
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, AssetError> {
+    if !is_encrypted(data) {
+        return Err(crypto_error("Not an encrypted asset"));
+    }
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = Nonce::from_slice(&data[MAGIC.len() + SALT_LEN..HEADER_LEN]);
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, salt)?);
+    cipher.decrypt(nonce, &data[HEADER_LEN..]).map_err(|_| AssetError {
+        code: "DECRYPT_FAILED".to_string(),
+        message: "Wrong passphrase or corrupted asset".to_string(),
+    })
+}
+
+/// Encrypt `bytes` with the session passphrase, or return them unchanged
+/// when encryption is off
+pub fn seal_if_enabled(bytes: Vec<u8>) -> Result<Vec<u8>, AssetError> {
+    match session_passphrase() {
+        Some(passphrase) => encrypt(&bytes, &passphrase),
+        None => Ok(bytes),
+    }
+}
+
+/// Read an asset, decrypting it with the session passphrase if it's encrypted
+pub fn read_decrypted(path: &Path) -> Result<Vec<u8>, AssetError> {
+    let data = fs::read(path).map_err(|e| AssetError::io("Failed to read file", e))?;
+    if !is_encrypted(&data) {
+        return Ok(data);
+    }
+    let passphrase = session_passphrase().ok_or_else(|| AssetError {
+        code: "PASSPHRASE_REQUIRED".to_string(),
+        message: "Asset is encrypted; set the passphrase first".to_string(),
+    })?;
+    decrypt(&data, &passphrase)
+}
+
+// ============================================
+// TAURI COMMANDS
+// ============================================
+
+/// Resolve a path from the frontend, refusing anything outside the asset store
+fn asset_path(path: &str) -> Result<PathBuf, AssetError> {
+    crate::managed_asset_path(path)?.map_err(|rejection| AssetError {
+        code: rejection.code.unwrap_or_default(),
+        message: rejection.error.unwrap_or_default(),
+    })
+}
+
+/// Tauri command to turn encryption on for new assets (Some) or back off (None).
+/// The passphrase is never written to disk.
+#[tauri::command]
+pub fn set_asset_passphrase(passphrase: Option<String>) -> Result<FileResult, AssetError> {
+    if passphrase.as_deref().is_some_and(str::is_empty) {
+        return Err(crypto_error("Passphrase must not be empty"));
+    }
+    *PASSPHRASE.lock().map_err(|e| crypto_error(e.to_string()))? = passphrase;
+    Ok(FileResult {
+        success: true,
+        path: None,
+        error: None,
+        code: None,
+    })
+}
+
+/// Tauri command reporting whether a file is an encrypted asset
+#[tauri::command]
+pub async fn is_asset_encrypted(path: String) -> Result<bool, AssetError> {
+    let path = asset_path(&path)?;
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    let file = fs::File::open(&path).map_err(|e| AssetError::io("Failed to open file", e))?;
+    file.take(HEADER_LEN as u64)
+        .read_to_end(&mut header)
+        .map_err(|e| AssetError::io("Failed to read file", e))?;
+    Ok(is_encrypted(&header))
+}
+
+/// Tauri command to read an asset as base64, decrypting it if needed
+#[tauri::command]
+pub async fn read_asset_decrypted(path: String) -> Result<String, AssetError> {
+    let path = asset_path(&path)?;
+    let bytes = tauri::async_runtime::spawn_blocking(move || read_decrypted(&path))
+        .await
+        .map_err(|e| AssetError {
+            code: "TASK_JOIN_ERROR".to_string(),
+            message: format!("Task join error: {}", e),
+        })??;
+    Ok(BASE64.encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_wrong_passphrase() {
+        let sealed = encrypt(b"confidential footage", "correct horse").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!is_encrypted(b"confidential footage"));
+        assert_eq!(decrypt(&sealed, "correct horse").unwrap(), b"confidential footage");
+        assert_eq!(decrypt(&sealed, "wrong").unwrap_err().code, "DECRYPT_FAILED");
+
+        // Fresh salt and nonce every time
+        assert_ne!(sealed, encrypt(b"confidential footage", "correct horse").unwrap());
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&tampered, "correct horse").is_err());
+    }
+}
@@ -0,0 +1,415 @@
+//! EXIF metadata for still image assets
+//!
+//! This module provides:
+//! - Reading common EXIF fields (orientation, capture date, camera, exposure)
+//! - Rewriting a small subset of fields in JPEG files
+//! - Orientation-aware image thumbnails, so portrait photos aren't sideways
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use exif::experimental::Writer;
+use exif::{Field, In, Tag, Value};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+
+use crate::AssetError;
+
+// ============================================
+// TYPES
+// ============================================
+
+/// EXIF fields surfaced in the asset inspector
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ImageMetadata {
+    /// EXIF orientation 1-8 (1 = upright)
+    pub orientation: Option<u16>,
+    /// DateTimeOriginal, "YYYY:MM:DD HH:MM:SS"
+    pub capture_date: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_model: Option<String>,
+    pub description: Option<String>,
+    pub artist: Option<String>,
+    pub copyright: Option<String>,
+    pub iso: Option<u32>,
+    /// Seconds
+    pub exposure_time: Option<f64>,
+    pub f_number: Option<f64>,
+    pub focal_length_mm: Option<f64>,
+}
+
+/// Fields `write_metadata` can change; None leaves a field as is
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ImageMetadataUpdate {
+    pub orientation: Option<u16>,
+    pub capture_date: Option<String>,
+    pub description: Option<String>,
+    pub artist: Option<String>,
+    pub copyright: Option<String>,
+}
+
+fn metadata_error(code: &str, message: String) -> AssetError {
+    AssetError {
+        code: code.to_string(),
+        message,
+    }
+}
+
+// ============================================
+// READING
+// ============================================
+
+/// Parse the EXIF block of an image, or None if it has none
+fn read_exif(path: &Path) -> Result<Option<exif::Exif>, AssetError> {
+    let file = fs::File::open(path).map_err(|e| AssetError::io("Failed to open image", e))?;
+    match exif::Reader::new().read_from_container(&mut BufReader::new(file)) {
+        Ok(exif) => Ok(Some(exif)),
+        Err(exif::Error::NotFound(_)) | Err(exif::Error::BlankValue(_)) => Ok(None),
+        Err(e) => Err(metadata_error(
+            "EXIF_ERROR",
+            format!("Invalid EXIF in '{}': {}", path.display(), e),
+        )),
+    }
+}
+
+fn ascii_field(exif: &exif::Exif, tag: Tag) -> Option<String> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(parts) => {
+            let text = String::from_utf8_lossy(parts.first()?)
+                .trim_end_matches('\0')
+                .trim()
+                .to_string();
+            (!text.is_empty()).then_some(text)
+        }
+        _ => None,
+    }
+}
+
+fn rational_field(exif: &exif::Exif, tag: Tag) -> Option<f64> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(values) => values.first().map(|r| r.to_f64()),
+        Value::SRational(values) => values.first().map(|r| r.to_f64()),
+        _ => None,
+    }
+}
+
+fn uint_field(exif: &exif::Exif, tag: Tag) -> Option<u32> {
+    exif.get_field(tag, In::PRIMARY)?.value.get_uint(0)
+}
+
+/// EXIF fields of an image (all None when it carries no EXIF)
+pub fn read_metadata(path: &Path) -> Result<ImageMetadata, AssetError> {
+    let Some(exif) = read_exif(path)? else {
+        return Ok(ImageMetadata::default());
+    };
+    Ok(ImageMetadata {
+        orientation: uint_field(&exif, Tag::Orientation).map(|o| o as u16),
+        capture_date: ascii_field(&exif, Tag::DateTimeOriginal)
+            .or_else(|| ascii_field(&exif, Tag::DateTime)),
+        camera_make: ascii_field(&exif, Tag::Make),
+        camera_model: ascii_field(&exif, Tag::Model),
+        lens_model: ascii_field(&exif, Tag::LensModel),
+        description: ascii_field(&exif, Tag::ImageDescription),
+        artist: ascii_field(&exif, Tag::Artist),
+        copyright: ascii_field(&exif, Tag::Copyright),
+        iso: uint_field(&exif, Tag::PhotographicSensitivity),
+        exposure_time: rational_field(&exif, Tag::ExposureTime),
+        f_number: rational_field(&exif, Tag::FNumber),
+        focal_length_mm: rational_field(&exif, Tag::FocalLength),
+    })
+}
+
+/// EXIF orientation of an image, 1 (upright) when absent or unreadable
+pub fn image_orientation(path: &Path) -> u16 {
+    read_exif(path)
+        .ok()
+        .flatten()
+        .and_then(|exif| uint_field(&exif, Tag::Orientation))
+        .filter(|o| (1..=8).contains(o))
+        .map_or(1, |o| o as u16)
+}
+
+/// Rotate/flip decoded pixels so they display upright for `orientation`
+pub fn apply_orientation(img: image::DynamicImage, orientation: u16) -> image::DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+/// Whether `orientation` swaps width and height when displayed
+pub fn orientation_swaps_axes(orientation: u16) -> bool {
+    (5..=8).contains(&orientation)
+}
+
+// ============================================
+// WRITING
+// ============================================
+
+/// Tags the writer derives itself, or whose data we can't carry over
+/// (pointer offsets, maker notes with internal offsets)
+const UNCOPIED_TAGS: [Tag; 4] = [
+    Tag::ExifIFDPointer,
+    Tag::GPSInfoIFDPointer,
+    Tag::InteropIFDPointer,
+    Tag::MakerNote,
+];
+
+fn ascii_value(text: &str) -> Value {
+    Value::Ascii(vec![text.as_bytes().to_vec()])
+}
+
+/// Replace the EXIF APP1 segment of a JPEG (or insert one after SOI/APP0)
+fn splice_jpeg_exif(jpeg: &[u8], tiff: &[u8]) -> Result<Vec<u8>, String> {
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        return Err("not a JPEG file".to_string());
+    }
+    let payload_len = 2 + 6 + tiff.len();
+    if payload_len > u16::MAX as usize {
+        return Err("EXIF block is too large for a JPEG APP1 segment".to_string());
+    }
+    let mut app1 = vec![0xFF, 0xE1];
+    app1.extend_from_slice(&(payload_len as u16).to_be_bytes());
+    app1.extend_from_slice(b"Exif\0\0");
+    app1.extend_from_slice(tiff);
+
+    let mut out = jpeg[..2].to_vec();
+    let mut inserted = false;
+    let mut pos = 2;
+    // Walk marker segments up to start-of-scan; everything after is copied as is
+    while pos + 4 <= jpeg.len() && jpeg[pos] == 0xFF {
+        let marker = jpeg[pos + 1];
+        if marker == 0xDA {
+            break;
+        }
+        let len = u16::from_be_bytes([jpeg[pos + 2], jpeg[pos + 3]]) as usize;
+        let end = pos + 2 + len;
+        if len < 2 || end > jpeg.len() {
+            return Err("truncated JPEG segment".to_string());
+        }
+        let segment = &jpeg[pos..end];
+        let is_exif = marker == 0xE1 && segment[4..].starts_with(b"Exif\0\0");
+        if !inserted && marker != 0xE0 {
+            out.extend_from_slice(&app1);
+            inserted = true;
+        }
+        if !is_exif {
+            out.extend_from_slice(segment);
+        }
+        pos = end;
+    }
+    if !inserted {
+        out.extend_from_slice(&app1);
+    }
+    out.extend_from_slice(&jpeg[pos..]);
+    Ok(out)
+}
+
+/// Apply `update` to a JPEG's EXIF, keeping its other primary and Exif IFD
+/// fields. Maker notes and the embedded EXIF thumbnail are dropped.
+pub fn write_metadata(path: &Path, update: &ImageMetadataUpdate) -> Result<(), AssetError> {
+    if let Some(orientation) = update.orientation {
+        if !(1..=8).contains(&orientation) {
+            return Err(metadata_error(
+                "INVALID_METADATA",
+                format!("EXIF orientation must be 1-8 (got {})", orientation),
+            ));
+        }
+    }
+
+    let existing = read_exif(path)?;
+    let mut fields: Vec<Field> = existing
+        .as_ref()
+        .map(|exif| {
+            exif.fields()
+                .filter(|f| f.ifd_num == In::PRIMARY && !UNCOPIED_TAGS.contains(&f.tag))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut set = |tag: Tag, value: Value| {
+        fields.retain(|f| f.tag != tag);
+        fields.push(Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value,
+        });
+    };
+    if let Some(orientation) = update.orientation {
+        set(Tag::Orientation, Value::Short(vec![orientation]));
+    }
+    if let Some(date) = &update.capture_date {
+        set(Tag::DateTimeOriginal, ascii_value(date));
+    }
+    if let Some(text) = &update.description {
+        set(Tag::ImageDescription, ascii_value(text));
+    }
+    if let Some(text) = &update.artist {
+        set(Tag::Artist, ascii_value(text));
+    }
+    if let Some(text) = &update.copyright {
+        set(Tag::Copyright, ascii_value(text));
+    }
+
+    let little_endian = existing.as_ref().is_some_and(|exif| exif.little_endian());
+    let mut writer = Writer::new();
+    for field in &fields {
+        writer.push_field(field);
+    }
+    let mut tiff = Cursor::new(Vec::new());
+    writer
+        .write(&mut tiff, little_endian)
+        .map_err(|e| metadata_error("EXIF_ERROR", format!("Failed to encode EXIF: {}", e)))?;
+
+    let jpeg = fs::read(path).map_err(|e| AssetError::io("Failed to read image", e))?;
+    let updated = splice_jpeg_exif(&jpeg, tiff.get_ref()).map_err(|e| {
+        metadata_error(
+            "UNSUPPORTED_FORMAT",
+            format!(
+                "Can only write EXIF to JPEG files ('{}': {})",
+                path.display(),
+                e
+            ),
+        )
+    })?;
+
+    // Write then rename so a failure never corrupts the original
+    let temp = path.with_extension(format!("exif-{}", uuid::Uuid::new_v4()));
+    fs::write(&temp, updated).map_err(|e| AssetError::io("Failed to write image", e))?;
+    fs::rename(&temp, path).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        AssetError::io("Failed to replace image", e)
+    })
+}
+
+// ============================================
+// THUMBNAILS
+// ============================================
+
+/// Base64 JPEG thumbnail of a still image, at most `max_size` on its long
+/// edge and rotated upright per its EXIF orientation
+pub fn image_thumbnail(path: &Path, max_size: u32) -> Result<String, AssetError> {
+    let img = image::open(path).map_err(|e| {
+        metadata_error(
+            "DECODE_ERROR",
+            format!("Failed to decode image '{}': {}", path.display(), e),
+        )
+    })?;
+    let img = apply_orientation(img, image_orientation(path));
+    let thumb = img.thumbnail(max_size, max_size).to_rgb8();
+
+    let mut buffer = Vec::new();
+    thumb
+        .write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(
+            &mut buffer,
+            80,
+        ))
+        .map_err(|e| {
+            metadata_error(
+                "JPEG_ENCODE_ERROR",
+                format!("Failed to encode thumbnail: {}", e),
+            )
+        })?;
+    Ok(BASE64.encode(buffer))
+}
+
+// ============================================
+// TAURI COMMANDS
+// ============================================
+
+/// Resolve a path from the frontend, refusing anything outside the asset store
+fn asset_path(path: &str) -> Result<PathBuf, AssetError> {
+    crate::managed_asset_path(path)?.map_err(|rejection| AssetError {
+        code: rejection.code.unwrap_or_default(),
+        message: rejection.error.unwrap_or_default(),
+    })
+}
+
+fn join_error(e: tauri::Error) -> AssetError {
+    AssetError {
+        code: "TASK_JOIN_ERROR".to_string(),
+        message: format!("Task join error: {}", e),
+    }
+}
+
+/// Tauri command to read an image's EXIF fields
+#[tauri::command]
+pub async fn read_image_metadata(path: String) -> Result<ImageMetadata, AssetError> {
+    let path = asset_path(&path)?;
+    tauri::async_runtime::spawn_blocking(move || read_metadata(&path))
+        .await
+        .map_err(join_error)?
+}
+
+/// Tauri command to update the supported EXIF fields of a JPEG
+#[tauri::command]
+pub async fn write_image_metadata(
+    path: String,
+    update: ImageMetadataUpdate,
+) -> Result<(), AssetError> {
+    let path = asset_path(&path)?;
+    tauri::async_runtime::spawn_blocking(move || write_metadata(&path, &update))
+        .await
+        .map_err(join_error)?
+}
+
+/// Tauri command for an upright thumbnail of a still image (default 320 px)
+#[tauri::command]
+pub async fn get_image_thumbnail(
+    path: String,
+    max_size: Option<u32>,
+) -> Result<String, AssetError> {
+    let path = asset_path(&path)?;
+    let max_size = max_size.unwrap_or(320).max(1);
+    tauri::async_runtime::spawn_blocking(move || image_thumbnail(&path, max_size))
+        .await
+        .map_err(join_error)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xFF, marker];
+        out.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn test_splice_replaces_exif_after_jfif() {
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend(segment(0xE0, b"JFIF\0"));
+        jpeg.extend(segment(0xE1, b"Exif\0\0old"));
+        jpeg.extend(segment(0xDB, &[0; 4]));
+        jpeg.extend([0xFF, 0xDA, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xD9]);
+
+        let out = splice_jpeg_exif(&jpeg, b"new").unwrap();
+        let mut expected = vec![0xFF, 0xD8];
+        expected.extend(segment(0xE0, b"JFIF\0"));
+        expected.extend(segment(0xE1, b"Exif\0\0new"));
+        expected.extend(segment(0xDB, &[0; 4]));
+        expected.extend([0xFF, 0xDA, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xD9]);
+        assert_eq!(out, expected);
+
+        assert!(splice_jpeg_exif(b"\x89PNG", b"new").is_err());
+    }
+
+    #[test]
+    fn test_orientation_rotates_pixels() {
+        let img = image::DynamicImage::ImageRgb8(image::RgbImage::new(4, 2));
+        let upright = apply_orientation(img.clone(), 6);
+        assert_eq!((upright.width(), upright.height()), (2, 4));
+        assert!(orientation_swaps_axes(6) && !orientation_swaps_axes(3));
+        assert_eq!(apply_orientation(img, 1).width(), 4);
+    }
+}
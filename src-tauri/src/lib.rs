@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::collections::HashMap;
 use std::fs;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::PathBuf;
-use tauri::Manager;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{Emitter, Manager};
 use uuid::Uuid;
 
 mod video_decoder;
@@ -11,14 +15,58 @@ use video_decoder::*;
 mod melt_runner;
 use melt_runner::*;
 
+mod mlt_builder;
+mod mlt_parser;
+
+mod asset_crypto;
+mod image_metadata;
+mod loudness;
+mod lut;
+mod stream_copy;
+mod thumb_cache;
+
 /// Result of a file operation
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct FileResult {
     pub success: bool,
     pub path: Option<String>,
     pub error: Option<String>,
+    /// Machine-readable failure reason, e.g. "TIMEOUT"
+    #[serde(default)]
+    pub code: Option<String>,
+}
+
+/// Error returned by the asset commands; `code` is stable for the UI to branch on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetError {
+    pub code: String,
+    pub message: String,
+}
+
+impl AssetError {
+    fn io(context: &str, err: impl std::fmt::Display) -> Self {
+        AssetError {
+            code: "IO_ERROR".to_string(),
+            message: format!("{}: {}", context, err),
+        }
+    }
+
+    fn network(context: &str, err: impl std::fmt::Display) -> Self {
+        AssetError {
+            code: "NETWORK_ERROR".to_string(),
+            message: format!("{}: {}", context, err),
+        }
+    }
 }
 
+impl std::fmt::Display for AssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for AssetError {}
+
 /// Asset metadata for file operations
 #[derive(Serialize, Deserialize)]
 pub struct AssetInfo {
@@ -27,34 +75,474 @@ pub struct AssetInfo {
     pub extension: String,   // jpg, png, mp4, mp3, etc.
 }
 
-/// Get the app's asset storage directory
-fn get_asset_dir() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or("Could not find home directory")?;
-    let asset_dir = home.join(".dreamcloud").join("assets");
+/// Human-facing details stored in a `{id}.meta.json` sidecar next to the asset
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct AssetMetadata {
+    pub id: String,
+    pub title: Option<String>,
+    pub source_url: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Unix seconds; filled in on first write when missing
+    pub imported_at: Option<u64>,
+    /// ETag from the last download, sent as If-None-Match to revalidate
+    #[serde(default)]
+    pub etag: Option<String>,
+    /// Hex SHA-256 of the file as written, checked by `verify_asset_integrity`
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+/// Size, kind and media details of a local asset in one call
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AssetMediaInfo {
+    pub path: String,
+    pub size_bytes: u64,
+    /// "image", "video", "audio" or "unknown"
+    pub kind: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub duration_secs: Option<f64>,
+    pub codec: Option<String>,
+}
+
+/// A local asset and its sidecar metadata, if any
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AssetEntry {
+    pub path: String,
+    pub metadata: Option<AssetMetadata>,
+}
+
+/// `~/.dreamcloud`, home of the default asset store and app config
+fn dreamcloud_dir() -> Result<PathBuf, AssetError> {
+    let home = dirs::home_dir().ok_or_else(|| AssetError {
+        code: "HOME_NOT_FOUND".to_string(),
+        message: "Could not find home directory".to_string(),
+    })?;
+    Ok(home.join(".dreamcloud"))
+}
+
+/// Asset storage roots persisted in `~/.dreamcloud/config.json`. The default
+/// `~/.dreamcloud/assets` root is always registered implicitly.
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct StorageConfig {
+    #[serde(default)]
+    roots: Vec<String>,
+    /// Root new assets are written to; None means the default root
+    #[serde(default)]
+    primary_root: Option<String>,
+}
+
+/// A registered asset storage root
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StorageRoot {
+    pub path: String,
+    pub primary: bool,
+    /// False when the directory is missing, e.g. an unplugged external drive
+    pub available: bool,
+}
+
+fn storage_config_path() -> Result<PathBuf, AssetError> {
+    Ok(dreamcloud_dir()?.join("config.json"))
+}
+
+fn load_storage_config() -> StorageConfig {
+    storage_config_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_storage_config(config: &StorageConfig) -> Result<(), AssetError> {
+    let path = storage_config_path()?;
+    // config.json may hold other settings; only replace the storage keys
+    let mut json: serde_json::Map<String, serde_json::Value> = fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+    if let Ok(serde_json::Value::Object(storage)) = serde_json::to_value(config) {
+        json.extend(storage);
+    }
+    let text = serde_json::to_string_pretty(&json).map_err(|e| AssetError {
+        code: "SERIALIZE_ERROR".to_string(),
+        message: format!("Failed to serialize config: {}", e),
+    })?;
+    fs::create_dir_all(dreamcloud_dir()?).map_err(|e| AssetError::io("Failed to create config directory", e))?;
+    fs::write(&path, text).map_err(|e| AssetError::io("Failed to write config", e))
+}
+
+fn default_asset_dir() -> Result<PathBuf, AssetError> {
+    Ok(dreamcloud_dir()?.join("assets"))
+}
+
+/// `path` with symlinks and `..` resolved as far as it exists, so a
+/// directory compares equal however it was spelled (and while unplugged)
+fn canonical_root(path: &std::path::Path) -> PathBuf {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return missing.iter().rev().fold(canonical, |dir, name| dir.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// Every registered root, default first, whether or not it is available.
+/// Roots after the default are canonical.
+fn registered_storage_roots() -> Result<Vec<PathBuf>, AssetError> {
+    let default_dir = default_asset_dir()?;
+    let mut seen = vec![canonical_root(&default_dir)];
+    let mut roots = vec![default_dir];
+    for root in load_storage_config().roots {
+        // Older configs may name one directory in several ways
+        let root = canonical_root(std::path::Path::new(&root));
+        if !seen.contains(&root) {
+            seen.push(root.clone());
+            roots.push(root);
+        }
+    }
+    Ok(roots)
+}
+
+/// Check that `root` (canonical) can be added beside the `registered` ones
+/// (canonical): it must be a dedicated asset directory, so not a filesystem
+/// root, the home directory or an ancestor of it, nor a folder of other
+/// files, and it must neither contain nor sit inside another root
+fn check_storage_root(root: &std::path::Path, registered: &[PathBuf]) -> Result<(), AssetError> {
+    let not_dedicated = |reason: &str| AssetError {
+        code: "NOT_DEDICATED_DIRECTORY".to_string(),
+        message: format!(
+            "{} {}; choose an empty folder for assets",
+            root.display(),
+            reason
+        ),
+    };
+    if root.parent().is_none() {
+        return Err(not_dedicated("is a filesystem root"));
+    }
+    if dirs::home_dir().is_some_and(|home| canonical_root(&home).starts_with(root)) {
+        return Err(not_dedicated("is or contains the home directory"));
+    }
+    if let Ok(entries) = fs::read_dir(root) {
+        let foreign = entries.flatten().any(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            !name.starts_with('.') && !ASSET_TYPES.contains(&name.as_str())
+        });
+        if foreign {
+            return Err(not_dedicated("already holds files that aren't assets"));
+        }
+    }
+
+    for existing in registered {
+        if root.starts_with(existing) || existing.starts_with(root) {
+            return Err(AssetError {
+                code: "OVERLAPPING_STORAGE_ROOT".to_string(),
+                message: format!(
+                    "{} overlaps the storage root {}",
+                    root.display(),
+                    existing.display()
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Roots that currently exist, for listing, search and usage
+fn storage_roots() -> Result<Vec<PathBuf>, AssetError> {
+    let default_dir = get_asset_dir_at(default_asset_dir()?)?;
+    let mut roots = vec![default_dir];
+    roots.extend(
+        registered_storage_roots()?
+            .into_iter()
+            .skip(1)
+            .filter(|root| root.is_dir()),
+    );
+    Ok(roots)
+}
 
-    // Create directory if it doesn't exist
-    if !asset_dir.exists() {
-        fs::create_dir_all(&asset_dir).map_err(|e| format!("Failed to create asset directory: {}", e))?;
+/// Create `dir` if needed and return it
+fn get_asset_dir_at(dir: PathBuf) -> Result<PathBuf, AssetError> {
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(|e| AssetError::io("Failed to create asset directory", e))?;
     }
+    Ok(dir)
+}
 
-    Ok(asset_dir)
+/// Get the app's asset storage directory: the primary root new assets are
+/// written to. Falls back to the default root when the primary is
+/// unavailable (e.g. its drive is unplugged).
+fn get_asset_dir() -> Result<PathBuf, AssetError> {
+    if let Some(primary) = load_storage_config().primary_root.map(PathBuf::from) {
+        if primary.is_dir() {
+            return Ok(primary);
+        }
+        eprintln!(
+            "Warning: primary storage root {} is unavailable, using the default",
+            primary.display()
+        );
+    }
+    get_asset_dir_at(default_asset_dir()?)
 }
 
+/// Asset type subdirectories the store knows about; `asset_type` becomes a
+/// path component, so anything else is rejected
+const ASSET_TYPES: [&str; 4] = ["image", "video", "audio", "animation"];
+
 /// Get the path for a specific asset type subdirectory
-fn get_asset_type_dir(asset_type: &str) -> Result<PathBuf, String> {
+fn get_asset_type_dir(asset_type: &str) -> Result<PathBuf, AssetError> {
+    if !ASSET_TYPES.contains(&asset_type) {
+        return Err(AssetError {
+            code: "INVALID_ASSET_TYPE".to_string(),
+            message: format!(
+                "Unknown asset type '{}'. Expected one of: {}",
+                asset_type,
+                ASSET_TYPES.join(", ")
+            ),
+        });
+    }
+
     let base_dir = get_asset_dir()?;
     let type_dir = base_dir.join(asset_type);
 
     if !type_dir.exists() {
-        fs::create_dir_all(&type_dir).map_err(|e| format!("Failed to create {} directory: {}", asset_type, e))?;
+        fs::create_dir_all(&type_dir).map_err(|e| AssetError::io(&format!("Failed to create {} directory", asset_type), e))?;
     }
 
     Ok(type_dir)
 }
 
-/// Download a file from a URL and save it locally
-#[tauri::command]
-async fn download_asset(url: String, asset_info: AssetInfo) -> Result<FileResult, String> {
+/// Emit batch download progress at most once per this many bytes
+const DOWNLOAD_PROGRESS_STEP_BYTES: u64 = 256 * 1024;
+
+/// Default number of simultaneous downloads in a batch
+const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 4;
+
+/// Progress for one item of a batch download
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DownloadProgressEvent {
+    pub index: usize,
+    pub url: String,
+    pub bytes_downloaded: u64,
+    pub total_bytes: Option<u64>,
+}
+
+/// Completion of one item of a batch download
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DownloadCompleteEvent {
+    pub index: usize,
+    pub url: String,
+    pub result: FileResult,
+}
+
+/// Network options for asset downloads
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct DownloadOptions {
+    /// Connect and read timeout; None waits indefinitely
+    pub timeout_secs: Option<u64>,
+    /// Extra request headers, e.g. Authorization for token-auth CDNs
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Retries for connection errors and 5xx/429 responses (0 = no retry)
+    #[serde(default)]
+    pub max_retries: u32,
+    /// Send the stored ETag as If-None-Match when the asset already exists;
+    /// a 304 keeps the cached file and reports code "NOT_MODIFIED"
+    #[serde(default)]
+    pub revalidate: bool,
+    /// Cap on transfer speed per download; None (or 0) means unthrottled
+    #[serde(default)]
+    pub max_bytes_per_sec: Option<u64>,
+}
+
+/// Emitted before each download retry
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DownloadRetryEvent {
+    pub url: String,
+    /// 1-based retry number
+    pub attempt: u32,
+    pub max_retries: u32,
+    pub delay_ms: u64,
+    pub reason: String,
+}
+
+/// First retry waits about this long; each later retry doubles it
+const RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// Upper bound for backoff and for server-supplied Retry-After
+const RETRY_MAX_DELAY_MS: u64 = 30_000;
+
+/// Exponential backoff with jitter: half the step is fixed, half random
+fn retry_backoff(attempt: u32) -> std::time::Duration {
+    let step = RETRY_BASE_DELAY_MS
+        .saturating_mul(1u64 << attempt.min(16))
+        .min(RETRY_MAX_DELAY_MS);
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let jitter = nanos as f64 / 1_000_000_000.0;
+    std::time::Duration::from_millis(step / 2 + (step as f64 / 2.0 * jitter) as u64)
+}
+
+/// Delay requested by a Retry-After header (seconds form only)
+fn retry_after(response: &reqwest::Response) -> Option<std::time::Duration> {
+    let secs: u64 = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(std::time::Duration::from_millis(
+        secs.saturating_mul(1000).min(RETRY_MAX_DELAY_MS),
+    ))
+}
+
+/// 5xx and 429 are worth retrying; other 4xx (404, 403, ...) are not
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Token bucket pacing a download to a byte rate. Allows up to one second
+/// of burst; a chunk larger than the balance is paid off by sleeping.
+struct RateLimiter {
+    bytes_per_sec: f64,
+    tokens: f64,
+    last_refill: std::time::Instant,
+}
+
+impl RateLimiter {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec as f64,
+            tokens: bytes_per_sec as f64,
+            last_refill: std::time::Instant::now(),
+        }
+    }
+
+    /// Take `bytes` from the bucket, waiting until the rate allows them
+    async fn consume(&mut self, bytes: u64) {
+        let now = std::time::Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.bytes_per_sec).min(self.bytes_per_sec);
+        self.last_refill = now;
+
+        self.tokens -= bytes as f64;
+        if self.tokens < 0.0 {
+            let wait = std::time::Duration::from_secs_f64(-self.tokens / self.bytes_per_sec);
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Why a download stopped part-way
+enum TransferError {
+    Timeout,
+    Failed(AssetError),
+}
+
+/// FileResult for a download that hit the configured timeout
+fn timeout_result(url: &str, options: &DownloadOptions) -> FileResult {
+    FileResult {
+        success: false,
+        path: None,
+        error: Some(format!(
+            "Download of {} timed out after {} s",
+            url,
+            options.timeout_secs.unwrap_or(0)
+        )),
+        code: Some("TIMEOUT".to_string()),
+    }
+}
+
+/// Where downloaded bytes go: straight into the asset file, or into memory
+/// when they have to be encrypted before touching the disk
+enum DownloadSink {
+    File(fs::File),
+    Sealed(Vec<u8>),
+}
+
+/// Largest asset that can be encrypted at rest. Sealing is a single pass
+/// over the whole file, so the plaintext and the sealed copy are both held
+/// in memory while it's written.
+const MAX_SEALED_ASSET_BYTES: u64 = 1024 * 1024 * 1024;
+
+fn too_large_to_seal() -> AssetError {
+    AssetError {
+        code: "TOO_LARGE_TO_ENCRYPT".to_string(),
+        message: format!(
+            "Assets over {} MB can't be encrypted at rest; turn encryption off to store this one",
+            MAX_SEALED_ASSET_BYTES / (1024 * 1024)
+        ),
+    }
+}
+
+/// Write an asset file, sealed when encryption is on, returning the hex
+/// SHA-256 of the bytes on disk. A failed write leaves no file behind.
+fn write_asset_bytes(dest: &std::path::Path, bytes: Vec<u8>) -> Result<String, AssetError> {
+    let bytes = asset_crypto::seal_if_enabled(bytes)?;
+    if let Err(e) = fs::write(dest, &bytes) {
+        let _ = fs::remove_file(dest);
+        return Err(AssetError::io("Failed to write file", e));
+    }
+    Ok(hex_digest(sha2::Sha256::new_with_prefix(&bytes)))
+}
+
+/// `write_asset_bytes` for a stream. Plaintext assets are copied in chunks;
+/// encrypted ones are buffered first, up to `MAX_SEALED_ASSET_BYTES`.
+fn write_asset_stream(dest: &std::path::Path, reader: &mut dyn Read) -> Result<String, AssetError> {
+    if asset_crypto::encryption_enabled() {
+        let mut plaintext = Vec::new();
+        (&mut *reader)
+            .take(MAX_SEALED_ASSET_BYTES + 1)
+            .read_to_end(&mut plaintext)
+            .map_err(|e| AssetError::io("Failed to read asset", e))?;
+        if plaintext.len() as u64 > MAX_SEALED_ASSET_BYTES {
+            return Err(too_large_to_seal());
+        }
+        return write_asset_bytes(dest, plaintext);
+    }
+
+    let mut out = fs::File::create(dest).map_err(|e| AssetError::io("Failed to create file", e))?;
+    let mut hasher = sha2::Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let copied = (|| -> std::io::Result<()> {
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                return Ok(());
+            }
+            out.write_all(&buffer[..read])?;
+            hasher.update(&buffer[..read]);
+        }
+    })();
+    if let Err(e) = copied {
+        drop(out);
+        let _ = fs::remove_file(dest);
+        return Err(AssetError::io("Failed to write file", e));
+    }
+    Ok(hex_digest(hasher))
+}
+
+/// Stream a URL into the asset directory, reporting bytes written so far.
+/// A partially written file is removed if the transfer fails.
+async fn download_to_file<F: FnMut(u64, Option<u64>)>(
+    app: &tauri::AppHandle,
+    url: &str,
+    asset_info: &AssetInfo,
+    options: &DownloadOptions,
+    mut on_progress: F,
+) -> Result<FileResult, AssetError> {
     // Get the appropriate directory for this asset type
     let type_dir = get_asset_type_dir(&asset_info.asset_type)?;
 
@@ -62,62 +550,309 @@ async fn download_asset(url: String, asset_info: AssetInfo) -> Result<FileResult
     let filename = format!("{}.{}", asset_info.id, asset_info.extension);
     let file_path = type_dir.join(&filename);
 
-    // Download the file
-    let response = reqwest::get(&url)
-        .await
-        .map_err(|e| format!("Failed to download file: {}", e))?;
+    let mut client = reqwest::Client::builder();
+    if let Some(secs) = options.timeout_secs {
+        let timeout = std::time::Duration::from_secs(secs);
+        client = client.connect_timeout(timeout).read_timeout(timeout);
+    }
+    let client = client
+        .build()
+        .map_err(|e| AssetError::network("Failed to create HTTP client", e))?;
+
+    let cached_etag = if options.revalidate && file_path.is_file() {
+        read_sidecar(&file_path).and_then(|metadata| metadata.etag)
+    } else {
+        None
+    };
+
+    // Download the file, retrying transient failures
+    let mut attempt = 0u32;
+    let mut response = loop {
+        let mut request = client.get(url);
+        for (name, value) in &options.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(etag) = &cached_etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag.as_str());
+        }
+
+        let can_retry = attempt < options.max_retries;
+        let (reason, delay) = match request.send().await {
+            Ok(response) if can_retry && is_retryable_status(response.status()) => (
+                format!("HTTP error: {}", response.status()),
+                retry_after(&response).unwrap_or_else(|| retry_backoff(attempt)),
+            ),
+            Ok(response) => break response,
+            Err(e) if can_retry && (e.is_connect() || e.is_timeout()) => {
+                (e.to_string(), retry_backoff(attempt))
+            }
+            Err(e) if e.is_timeout() => return Ok(timeout_result(url, options)),
+            Err(e) => return Err(AssetError::network("Failed to download file", e)),
+        };
+
+        attempt += 1;
+        let _ = app.emit(
+            "download-retry",
+            DownloadRetryEvent {
+                url: url.to_string(),
+                attempt,
+                max_retries: options.max_retries,
+                delay_ms: delay.as_millis() as u64,
+                reason,
+            },
+        );
+        tokio::time::sleep(delay).await;
+    };
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED && cached_etag.is_some() {
+        return Ok(FileResult {
+            success: true,
+            path: Some(file_path.to_string_lossy().to_string()),
+            error: None,
+            code: Some("NOT_MODIFIED".to_string()),
+        });
+    }
 
     if !response.status().is_success() {
         return Ok(FileResult {
             success: false,
             path: None,
             error: Some(format!("HTTP error: {}", response.status())),
+            code: None,
         });
     }
 
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let total_bytes = response.content_length();
+
+    // With encryption on, the plaintext stays in memory (up to
+    // MAX_SEALED_ASSET_BYTES) and only the sealed bytes are written to disk
+    let mut sink = if asset_crypto::encryption_enabled() {
+        if total_bytes.is_some_and(|total| total > MAX_SEALED_ASSET_BYTES) {
+            return Err(too_large_to_seal());
+        }
+        DownloadSink::Sealed(Vec::new())
+    } else {
+        DownloadSink::File(
+            fs::File::create(&file_path).map_err(|e| AssetError::io("Failed to create file", e))?,
+        )
+    };
+
+    let mut downloaded = 0u64;
+    let mut hasher = sha2::Sha256::new();
+    let mut limiter = options.max_bytes_per_sec.filter(|&rate| rate > 0).map(RateLimiter::new);
+    let transfer: Result<(), TransferError> = async {
+        loop {
+            let chunk = match response.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => return Ok(()),
+                Err(e) if e.is_timeout() => return Err(TransferError::Timeout),
+                Err(e) => {
+                    return Err(TransferError::Failed(AssetError::network("Failed to read response", e)))
+                }
+            };
+            let written = match &mut sink {
+                DownloadSink::File(file) => file.write_all(&chunk),
+                DownloadSink::Sealed(buffer) => {
+                    // Content-Length may be missing or wrong
+                    if (buffer.len() + chunk.len()) as u64 > MAX_SEALED_ASSET_BYTES {
+                        return Err(TransferError::Failed(too_large_to_seal()));
+                    }
+                    buffer.write_all(&chunk)
+                }
+            };
+            written.map_err(|e| TransferError::Failed(AssetError::io("Failed to write file", e)))?;
+            hasher.update(&chunk);
+            downloaded += chunk.len() as u64;
+            on_progress(downloaded, total_bytes);
+            if let Some(limiter) = limiter.as_mut() {
+                limiter.consume(chunk.len() as u64).await;
+            }
+        }
+    }
+    .await;
+
+    if let Err(e) = transfer {
+        if let DownloadSink::File(file) = sink {
+            drop(file);
+            let _ = fs::remove_file(&file_path);
+        }
+        return match e {
+            TransferError::Timeout => Ok(timeout_result(url, options)),
+            TransferError::Failed(message) => Err(message),
+        };
+    }
 
-    // Write to file
-    let mut file = fs::File::create(&file_path)
-        .map_err(|e| format!("Failed to create file: {}", e))?;
+    let sha256 = match sink {
+        DownloadSink::File(file) => {
+            drop(file);
+            hex_digest(hasher)
+        }
+        // The hash covers the bytes on disk, i.e. the sealed ones
+        DownloadSink::Sealed(plaintext) => write_asset_bytes(&file_path, plaintext)?,
+    };
 
-    file.write_all(&bytes)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
+    // Remember the ETag for revalidation (a stale one is dropped on refetch)
+    // and the content hash for integrity checks
+    let mut metadata = read_sidecar(&file_path).unwrap_or_else(|| AssetMetadata {
+        id: asset_info.id.clone(),
+        source_url: Some(url.to_string()),
+        imported_at: unix_now(),
+        ..Default::default()
+    });
+    metadata.etag = etag;
+    metadata.sha256 = Some(sha256);
+    write_sidecar(&file_path, &metadata)?;
 
     Ok(FileResult {
         success: true,
         path: Some(file_path.to_string_lossy().to_string()),
         error: None,
+        code: None,
     })
 }
 
+/// Download a file from a URL and save it locally.
+/// With encryption on, downloads are capped at `MAX_SEALED_ASSET_BYTES`.
+/// Emits "download-retry" before each retry when `max_retries` > 0.
+/// With `revalidate`, an unchanged asset (HTTP 304) is not re-downloaded.
+/// `max_bytes_per_sec` caps the transfer speed (unthrottled by default).
+#[tauri::command]
+async fn download_asset(
+    app: tauri::AppHandle,
+    url: String,
+    asset_info: AssetInfo,
+    timeout_secs: Option<u64>,
+    headers: Option<HashMap<String, String>>,
+    max_retries: Option<u32>,
+    revalidate: Option<bool>,
+    max_bytes_per_sec: Option<u64>,
+) -> Result<FileResult, AssetError> {
+    let options = DownloadOptions {
+        timeout_secs,
+        headers: headers.unwrap_or_default(),
+        max_retries: max_retries.unwrap_or(0),
+        revalidate: revalidate.unwrap_or(false),
+        max_bytes_per_sec,
+    };
+    let result = download_to_file(&app, &url, &asset_info, &options, |_, _| {}).await;
+    spawn_storage_watch_check(&app);
+    result
+}
+
+/// Download many assets concurrently (at most `max_concurrent` at once).
+///
+/// Emits "asset-download-progress" and "asset-download-complete" events per
+/// item. Results are returned in request order; a failed item does not abort
+/// the others.
+#[tauri::command]
+async fn download_assets_batch(
+    app: tauri::AppHandle,
+    items: Vec<(String, AssetInfo)>,
+    max_concurrent: Option<usize>,
+    options: Option<DownloadOptions>,
+) -> Result<Vec<FileResult>, AssetError> {
+    let limit = max_concurrent.unwrap_or(DEFAULT_DOWNLOAD_CONCURRENCY).max(1);
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(limit));
+    let options = Arc::new(options.unwrap_or_default());
+
+    let mut handles = Vec::with_capacity(items.len());
+    for (index, (url, asset_info)) in items.into_iter().enumerate() {
+        let semaphore = semaphore.clone();
+        let options = options.clone();
+        let app = app.clone();
+
+        handles.push(tauri::async_runtime::spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+
+            let mut last_emitted = 0u64;
+            let result = download_to_file(&app, &url, &asset_info, &options, |bytes_downloaded, total_bytes| {
+                let finished = Some(bytes_downloaded) == total_bytes;
+                if finished || bytes_downloaded - last_emitted >= DOWNLOAD_PROGRESS_STEP_BYTES {
+                    last_emitted = bytes_downloaded;
+                    let _ = app.emit(
+                        "asset-download-progress",
+                        DownloadProgressEvent {
+                            index,
+                            url: url.clone(),
+                            bytes_downloaded,
+                            total_bytes,
+                        },
+                    );
+                }
+            })
+            .await
+            .unwrap_or_else(|error| FileResult {
+                success: false,
+                path: None,
+                error: Some(error.message),
+                code: Some(error.code),
+            });
+
+            let _ = app.emit(
+                "asset-download-complete",
+                DownloadCompleteEvent {
+                    index,
+                    url: url.clone(),
+                    result: result.clone(),
+                },
+            );
+            result
+        }));
+    }
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await.unwrap_or_else(|e| FileResult {
+            success: false,
+            path: None,
+            error: Some(format!("Task join error: {}", e)),
+            code: Some("TASK_JOIN_ERROR".to_string()),
+        }));
+    }
+
+    spawn_storage_watch_check(&app);
+    Ok(results)
+}
+
 /// Save raw bytes as a local asset
 #[tauri::command]
-async fn save_asset_bytes(bytes: Vec<u8>, asset_info: AssetInfo) -> Result<FileResult, String> {
+async fn save_asset_bytes(
+    app: tauri::AppHandle,
+    bytes: Vec<u8>,
+    asset_info: AssetInfo,
+) -> Result<FileResult, AssetError> {
     let type_dir = get_asset_type_dir(&asset_info.asset_type)?;
 
     let filename = format!("{}.{}", asset_info.id, asset_info.extension);
     let file_path = type_dir.join(&filename);
+    let sha256 = write_asset_bytes(&file_path, bytes)?;
 
-    let mut file = fs::File::create(&file_path)
-        .map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut metadata = read_sidecar(&file_path).unwrap_or_else(|| AssetMetadata {
+        id: asset_info.id.clone(),
+        imported_at: unix_now(),
+        ..Default::default()
+    });
+    metadata.sha256 = Some(sha256);
+    write_sidecar(&file_path, &metadata)?;
 
-    file.write_all(&bytes)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
+    spawn_storage_watch_check(&app);
 
     Ok(FileResult {
         success: true,
         path: Some(file_path.to_string_lossy().to_string()),
         error: None,
+        code: None,
     })
 }
 
 /// Delete a local asset file
 #[tauri::command]
-async fn delete_asset(local_path: String) -> Result<FileResult, String> {
+async fn delete_asset(local_path: String) -> Result<FileResult, AssetError> {
     let path = PathBuf::from(&local_path);
 
     if !path.exists() {
@@ -125,28 +860,31 @@ async fn delete_asset(local_path: String) -> Result<FileResult, String> {
             success: true,
             path: None,
             error: None,
+            code: None,
         });
     }
 
     fs::remove_file(&path)
-        .map_err(|e| format!("Failed to delete file: {}", e))?;
+        .map_err(|e| AssetError::io("Failed to delete file", e))?;
+    let _ = fs::remove_file(sidecar_path(&path));
 
     Ok(FileResult {
         success: true,
         path: None,
         error: None,
+        code: None,
     })
 }
 
 /// Check if a local asset exists
 #[tauri::command]
-async fn asset_exists(local_path: String) -> Result<bool, String> {
+async fn asset_exists(local_path: String) -> Result<bool, AssetError> {
     Ok(PathBuf::from(&local_path).exists())
 }
 
 /// Get the file size of a local asset
 #[tauri::command]
-async fn get_asset_size(local_path: String) -> Result<Option<u64>, String> {
+async fn get_asset_size(local_path: String) -> Result<Option<u64>, AssetError> {
     let path = PathBuf::from(&local_path);
 
     if !path.exists() {
@@ -154,37 +892,182 @@ async fn get_asset_size(local_path: String) -> Result<Option<u64>, String> {
     }
 
     let metadata = fs::metadata(&path)
-        .map_err(|e| format!("Failed to get file metadata: {}", e))?;
+        .map_err(|e| AssetError::io("Failed to get file metadata", e))?;
 
     Ok(Some(metadata.len()))
 }
 
+/// Detect a file's media kind and read its dimensions/duration/codec.
+/// Files neither the image crate nor FFmpeg understand come back as "unknown".
+fn probe_asset(path: &str, size_bytes: u64) -> AssetMediaInfo {
+    let mut info = AssetMediaInfo {
+        path: path.to_string(),
+        size_bytes,
+        kind: "unknown".to_string(),
+        width: None,
+        height: None,
+        duration_secs: None,
+        codec: None,
+    };
+
+    // Stills first: FFmpeg would also open them (as single-frame video)
+    if let Ok(format) = image::ImageFormat::from_path(path) {
+        info.kind = "image".to_string();
+        info.codec = Some(format!("{:?}", format).to_lowercase());
+        if let Ok((width, height)) = image::image_dimensions(path) {
+            // Report display dimensions: portrait photos are often stored sideways
+            let rotated = image_metadata::orientation_swaps_axes(image_metadata::image_orientation(
+                std::path::Path::new(path),
+            ));
+            let (width, height) = if rotated { (height, width) } else { (width, height) };
+            info.width = Some(width);
+            info.height = Some(height);
+        }
+        return info;
+    }
+
+    let Ok(streams) = video_decoder::probe_streams(path) else {
+        return info;
+    };
+
+    if streams.iter().any(|s| s.media_type == "video") {
+        if let Ok(video) = video_decoder::get_video_info(path) {
+            info.kind = "video".to_string();
+            info.width = Some(video.display_width);
+            info.height = Some(video.display_height);
+            info.duration_secs = Some(video.duration_secs);
+            info.codec = Some(video.codec);
+            return info;
+        }
+    }
+
+    if let Some(audio) = streams.iter().find(|s| s.media_type == "audio") {
+        info.kind = "audio".to_string();
+        info.duration_secs = audio.duration_secs;
+        info.codec = Some(audio.codec.clone());
+    }
+
+    info
+}
+
+/// Size, media kind and dimensions/duration/codec of a local asset
+#[tauri::command]
+async fn get_asset_info(local_path: String) -> Result<AssetMediaInfo, AssetError> {
+    let metadata = match fs::metadata(&local_path) {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => {
+            return Err(AssetError {
+                code: "FILE_NOT_FOUND".to_string(),
+                message: format!("Asset not found: {}", local_path),
+            })
+        }
+    };
+
+    let size_bytes = metadata.len();
+    tauri::async_runtime::spawn_blocking(move || probe_asset(&local_path, size_bytes))
+        .await
+        .map_err(|e| AssetError {
+            code: "TASK_JOIN_ERROR".to_string(),
+            message: format!("Task join error: {}", e),
+        })
+}
+
 /// Get the base asset directory path
 #[tauri::command]
-async fn get_asset_directory() -> Result<String, String> {
+async fn get_asset_directory() -> Result<String, AssetError> {
     let dir = get_asset_dir()?;
     Ok(dir.to_string_lossy().to_string())
 }
 
+fn storage_root_list() -> Result<Vec<StorageRoot>, AssetError> {
+    let primary = canonical_root(&get_asset_dir()?);
+    Ok(registered_storage_roots()?
+        .into_iter()
+        .map(|root| StorageRoot {
+            path: root.to_string_lossy().to_string(),
+            primary: canonical_root(&root) == primary,
+            available: root.is_dir(),
+        })
+        .collect())
+}
+
+/// Register another directory (e.g. on an external SSD) as an asset root.
+/// Its assets are listed, searched and counted alongside the default root.
+#[tauri::command]
+async fn add_storage_root(path: String) -> Result<Vec<StorageRoot>, AssetError> {
+    if !std::path::Path::new(&path).is_absolute() {
+        return Err(AssetError {
+            code: "INVALID_PATH".to_string(),
+            message: format!("Storage root must be an absolute path: {}", path),
+        });
+    }
+    let root = canonical_root(std::path::Path::new(&path));
+    let registered: Vec<PathBuf> = registered_storage_roots()?
+        .iter()
+        .map(|root| canonical_root(root))
+        .collect();
+    if registered.contains(&root) {
+        return storage_root_list();
+    }
+    check_storage_root(&root, &registered)?;
+    fs::create_dir_all(&root).map_err(|e| AssetError::io("Failed to create storage root", e))?;
+
+    let mut config = load_storage_config();
+    config.roots.push(root.to_string_lossy().to_string());
+    save_storage_config(&config)?;
+    storage_root_list()
+}
+
+/// List registered storage roots, marking the primary and unavailable ones
+#[tauri::command]
+async fn list_storage_roots() -> Result<Vec<StorageRoot>, AssetError> {
+    storage_root_list()
+}
+
+/// Make a registered root the one new assets are written to
+#[tauri::command]
+async fn set_primary_root(path: String) -> Result<Vec<StorageRoot>, AssetError> {
+    let root = canonical_root(std::path::Path::new(&path));
+    if !registered_storage_roots()?.iter().any(|registered| canonical_root(registered) == root) {
+        return Err(AssetError {
+            code: "UNKNOWN_STORAGE_ROOT".to_string(),
+            message: format!("Not a registered storage root: {}", path),
+        });
+    }
+    if !root.is_dir() {
+        return Err(AssetError {
+            code: "STORAGE_ROOT_UNAVAILABLE".to_string(),
+            message: format!("Storage root is not available: {}", path),
+        });
+    }
+
+    let mut config = load_storage_config();
+    config.primary_root =
+        (root != canonical_root(&default_asset_dir()?)).then(|| root.to_string_lossy().to_string());
+    save_storage_config(&config)?;
+    storage_root_list()
+}
+
 /// Generate a new UUID for an asset
 #[tauri::command]
 fn generate_asset_id() -> String {
     Uuid::new_v4().to_string()
 }
 
-/// List all assets in a directory by type
+/// List all assets of a type across every available storage root
 #[tauri::command]
-async fn list_local_assets(asset_type: String) -> Result<Vec<String>, String> {
-    let type_dir = get_asset_type_dir(&asset_type)?;
-
-    let entries = fs::read_dir(&type_dir)
-        .map_err(|e| format!("Failed to read directory: {}", e))?;
+async fn list_local_assets(asset_type: String) -> Result<Vec<String>, AssetError> {
+    // Validates the type and makes sure the primary root has the directory
+    get_asset_type_dir(&asset_type)?;
 
     let mut files = Vec::new();
-    for entry in entries {
-        if let Ok(entry) = entry {
+    for root in storage_roots()? {
+        let Ok(entries) = fs::read_dir(root.join(&asset_type)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
             let path = entry.path();
-            if path.is_file() {
+            if path.is_file() && !is_sidecar(&path) {
                 files.push(path.to_string_lossy().to_string());
             }
         }
@@ -193,105 +1076,1773 @@ async fn list_local_assets(asset_type: String) -> Result<Vec<String>, String> {
     Ok(files)
 }
 
-/// Copy an asset to a new location (for export/sharing)
-#[tauri::command]
-async fn copy_asset(source_path: String, destination_path: String) -> Result<FileResult, String> {
-    let source = PathBuf::from(&source_path);
-    let dest = PathBuf::from(&destination_path);
+/// Sidecar file name suffix. Asset ids can't contain '.', so no asset file
+/// ends in it, while JSON assets (e.g. Lottie animations) stay assets.
+const SIDECAR_SUFFIX: &str = ".meta.json";
 
-    if !source.exists() {
-        return Ok(FileResult {
-            success: false,
-            path: None,
-            error: Some("Source file does not exist".to_string()),
-        });
+fn is_sidecar(path: &std::path::Path) -> bool {
+    path.file_name().is_some_and(|name| name.to_string_lossy().ends_with(SIDECAR_SUFFIX))
+}
+
+/// `{id}.meta.json` next to an asset file
+fn sidecar_path(asset_path: &std::path::Path) -> PathBuf {
+    let stem = asset_path.file_stem().unwrap_or_default().to_string_lossy();
+    asset_path.with_file_name(format!("{}{}", stem, SIDECAR_SUFFIX))
+}
+
+/// Rename sidecars written under the old `{id}.json` naming in every type
+/// directory of every root. Returns how many were renamed.
+fn migrate_legacy_sidecars() -> Result<usize, AssetError> {
+    let mut migrated = 0;
+    for root in storage_roots()? {
+        for asset_type in ASSET_TYPES {
+            let Ok(entries) = fs::read_dir(root.join(asset_type)) else {
+                continue;
+            };
+            let files: Vec<PathBuf> = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .collect();
+            migrated += migrate_legacy_sidecars_in(&files);
+        }
     }
+    Ok(migrated)
+}
 
-    // Create parent directory if needed
-    if let Some(parent) = dest.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create destination directory: {}", e))?;
+/// A `{id}.json` among `files` is a legacy sidecar only when another asset
+/// file shares its id; a lone one is a JSON asset
+fn migrate_legacy_sidecars_in(files: &[PathBuf]) -> usize {
+    let is_json = |path: &PathBuf| path.extension().is_some_and(|ext| ext == "json");
+    let asset_stems: std::collections::HashSet<_> = files
+        .iter()
+        .filter(|path| !is_json(path))
+        .filter_map(|path| path.file_stem())
+        .collect();
+
+    let mut migrated = 0;
+    for legacy in files.iter().filter(|path| is_json(path) && !is_sidecar(path)) {
+        let target = sidecar_path(legacy);
+        if legacy.file_stem().is_some_and(|stem| asset_stems.contains(stem))
+            && !target.exists()
+            && fs::rename(legacy, &target).is_ok()
+        {
+            migrated += 1;
+        }
+    }
+    migrated
+}
+
+/// Find the asset file named `{id}.*` in any type directory of any root
+fn find_asset_by_id(id: &str) -> Result<Option<PathBuf>, AssetError> {
+    let mut type_dirs = Vec::new();
+    for root in storage_roots()? {
+        let entries = fs::read_dir(&root).map_err(|e| AssetError::io("Failed to read directory", e))?;
+        type_dirs.extend(entries.flatten());
+    }
+
+    for type_dir in type_dirs {
+        let Ok(entries) = fs::read_dir(type_dir.path()) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file()
+                && !is_sidecar(&path)
+                && path.file_stem().is_some_and(|stem| stem == id)
+            {
+                return Ok(Some(path));
+            }
         }
     }
 
-    fs::copy(&source, &dest)
-        .map_err(|e| format!("Failed to copy file: {}", e))?;
+    Ok(None)
+}
+
+/// Read the sidecar for an asset file; assets without one (or with an
+/// unreadable one) simply have no metadata
+fn read_sidecar(asset_path: &std::path::Path) -> Option<AssetMetadata> {
+    let json = fs::read_to_string(sidecar_path(asset_path)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn write_sidecar(asset_path: &std::path::Path, metadata: &AssetMetadata) -> Result<PathBuf, AssetError> {
+    let json = serde_json::to_string_pretty(metadata).map_err(|e| AssetError {
+        code: "SERIALIZE_ERROR".to_string(),
+        message: format!("Failed to serialize metadata: {}", e),
+    })?;
+    let path = sidecar_path(asset_path);
+    fs::write(&path, json).map_err(|e| AssetError::io("Failed to write metadata", e))?;
+    Ok(path)
+}
+
+fn unix_now() -> Option<u64> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+/// Store metadata in a `{id}.meta.json` sidecar next to the asset
+#[tauri::command]
+async fn write_asset_metadata(id: String, metadata: AssetMetadata) -> Result<FileResult, AssetError> {
+    let Some(asset_path) = find_asset_by_id(&id)? else {
+        return Ok(rejected("FILE_NOT_FOUND", format!("No asset with id {}", id)));
+    };
+
+    let mut metadata = metadata;
+    metadata.id = id;
+    let existing = read_sidecar(&asset_path);
+    if metadata.imported_at.is_none() {
+        metadata.imported_at = existing
+            .as_ref()
+            .and_then(|existing| existing.imported_at)
+            .or_else(unix_now);
+    }
+    // The content hash is recorded by the backend, never by the frontend
+    metadata.sha256 = existing.and_then(|existing| existing.sha256);
+
+    let path = write_sidecar(&asset_path, &metadata)?;
 
     Ok(FileResult {
         success: true,
-        path: Some(dest.to_string_lossy().to_string()),
+        path: Some(path.to_string_lossy().to_string()),
         error: None,
+        code: None,
     })
 }
 
-/// Get total storage used by local assets
+/// Read an asset's sidecar metadata (None when the asset has none)
 #[tauri::command]
-async fn get_storage_usage() -> Result<u64, String> {
-    let asset_dir = get_asset_dir()?;
+async fn read_asset_metadata(id: String) -> Result<Option<AssetMetadata>, AssetError> {
+    Ok(find_asset_by_id(&id)?.and_then(|path| read_sidecar(&path)))
+}
 
-    fn dir_size(path: &PathBuf) -> std::io::Result<u64> {
-        let mut size = 0;
-        if path.is_dir() {
-            for entry in fs::read_dir(path)? {
-                let entry = entry?;
-                let path = entry.path();
-                if path.is_dir() {
-                    size += dir_size(&path)?;
-                } else {
-                    size += entry.metadata()?.len();
-                }
-            }
-        }
-        Ok(size)
-    }
+/// Lowercase hex of a finished SHA-256
+fn hex_digest(hasher: sha2::Sha256) -> String {
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
 
-    dir_size(&asset_dir).map_err(|e| format!("Failed to calculate storage: {}", e))
+/// Hex SHA-256 of a file's contents
+fn sha256_file(path: &std::path::Path) -> std::io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex_digest(hasher))
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    // Initialize FFmpeg
-    if let Err(e) = video_decoder::init_ffmpeg() {
+/// Check whether a local asset matches `expected`, either the ETag stored
+/// from its last download or the SHA-256 of its contents (hex)
+#[tauri::command]
+async fn asset_is_current(local_path: String, expected: String) -> Result<bool, AssetError> {
+    let path = match managed_asset_path(&local_path)? {
+        Ok(path) => path,
+        Err(rejection) => {
+            return Err(AssetError {
+                code: rejection.code.unwrap_or_default(),
+                message: rejection.error.unwrap_or_default(),
+            })
+        }
+    };
+
+    // Weak validators (W/"...") compare by the quoted value
+    let normalize = |tag: &str| tag.trim().trim_start_matches("W/").trim_matches('"').to_string();
+    let expected = normalize(&expected);
+    if read_sidecar(&path)
+        .and_then(|metadata| metadata.etag)
+        .is_some_and(|etag| normalize(&etag) == expected)
+    {
+        return Ok(true);
+    }
+
+    let is_sha256 = expected.len() == 64 && expected.chars().all(|c| c.is_ascii_hexdigit());
+    if !is_sha256 {
+        return Ok(false);
+    }
+    tauri::async_runtime::spawn_blocking(move || sha256_file(&path))
+        .await
+        .map_err(|e| AssetError {
+            code: "TASK_JOIN_ERROR".to_string(),
+            message: format!("Task join error: {}", e),
+        })?
+        .map(|hash| hash.eq_ignore_ascii_case(&expected))
+        .map_err(|e| AssetError::io("Failed to read asset", e))
+}
+
+/// Outcome of an asset integrity check
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityStatus {
+    /// Contents match the recorded hash
+    Ok,
+    /// Contents changed since the hash was recorded (bit rot, truncation)
+    Corrupt,
+    /// The sidecar has no hash to compare against
+    NoHashRecorded,
+}
+
+/// Integrity of one local asset
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AssetIntegrity {
+    pub id: String,
+    pub path: String,
+    pub status: IntegrityStatus,
+    pub expected_sha256: Option<String>,
+    pub actual_sha256: Option<String>,
+}
+
+/// Emitted after each asset checked by `verify_all_assets`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct VerifyProgressEvent {
+    pub checked: usize,
+    pub total: usize,
+    pub id: String,
+    pub status: IntegrityStatus,
+}
+
+/// Every asset file (sidecars excluded) in every type directory of every root
+fn all_asset_files() -> Result<Vec<PathBuf>, AssetError> {
+    let mut paths = Vec::new();
+    for root in storage_roots()? {
+        for asset_type in ASSET_TYPES {
+            let Ok(entries) = fs::read_dir(root.join(asset_type)) else {
+                continue;
+            };
+            paths.extend(
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_file() && !is_sidecar(path)),
+            );
+        }
+    }
+    Ok(paths)
+}
+
+/// Rehash an asset and compare it to the hash in its sidecar
+fn check_asset_integrity(path: &std::path::Path) -> Result<AssetIntegrity, AssetError> {
+    let expected = read_sidecar(path).and_then(|metadata| metadata.sha256);
+    let actual = match &expected {
+        Some(_) => Some(sha256_file(path).map_err(|e| AssetError::io("Failed to read asset", e))?),
+        None => None,
+    };
+    let status = match (&expected, &actual) {
+        (None, _) => IntegrityStatus::NoHashRecorded,
+        (Some(expected), Some(actual)) if expected.eq_ignore_ascii_case(actual) => {
+            IntegrityStatus::Ok
+        }
+        _ => IntegrityStatus::Corrupt,
+    };
+    Ok(AssetIntegrity {
+        id: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+        path: path.to_string_lossy().to_string(),
+        status,
+        expected_sha256: expected,
+        actual_sha256: actual,
+    })
+}
+
+/// Recompute an asset's content hash and compare it to the one recorded
+/// when it was downloaded or saved
+#[tauri::command]
+async fn verify_asset_integrity(id: String) -> Result<AssetIntegrity, AssetError> {
+    let Some(path) = find_asset_by_id(&id)? else {
+        return Err(AssetError {
+            code: "FILE_NOT_FOUND".to_string(),
+            message: format!("No asset with id {}", id),
+        });
+    };
+    tauri::async_runtime::spawn_blocking(move || check_asset_integrity(&path))
+        .await
+        .map_err(|e| AssetError {
+            code: "TASK_JOIN_ERROR".to_string(),
+            message: format!("Task join error: {}", e),
+        })?
+}
+
+/// Check every local asset, emitting "asset-verify-progress" after each.
+/// An unreadable asset is reported as corrupt rather than failing the batch.
+#[tauri::command]
+async fn verify_all_assets(app: tauri::AppHandle) -> Result<Vec<AssetIntegrity>, AssetError> {
+    let paths = all_asset_files()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let total = paths.len();
+        let mut results = Vec::with_capacity(total);
+        for (index, path) in paths.into_iter().enumerate() {
+            let result = check_asset_integrity(&path).unwrap_or_else(|_| AssetIntegrity {
+                id: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+                path: path.to_string_lossy().to_string(),
+                status: IntegrityStatus::Corrupt,
+                expected_sha256: read_sidecar(&path).and_then(|metadata| metadata.sha256),
+                actual_sha256: None,
+            });
+            let _ = app.emit(
+                "asset-verify-progress",
+                VerifyProgressEvent {
+                    checked: index + 1,
+                    total,
+                    id: result.id.clone(),
+                    status: result.status,
+                },
+            );
+            results.push(result);
+        }
+        results
+    })
+    .await
+    .map_err(|e| AssetError {
+        code: "TASK_JOIN_ERROR".to_string(),
+        message: format!("Task join error: {}", e),
+    })
+}
+
+/// Assets removed (or, for a dry run, that would be removed) by
+/// `gc_unreferenced_assets`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AssetGcResult {
+    pub dry_run: bool,
+    /// Asset files, sidecars included in `freed_bytes` but not listed
+    pub paths: Vec<String>,
+    pub freed_bytes: u64,
+    /// Assets that could not be deleted, with the error
+    pub failed: Vec<FileResult>,
+}
+
+/// Assets modified more recently than this are never collected: they may
+/// still be downloading or not yet saved into a project
+const GC_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// Collect the `files` whose id isn't referenced and that are older than
+/// `grace`, deleting them and their sidecars unless `dry_run`
+fn collect_unreferenced(
+    files: Vec<PathBuf>,
+    referenced: &std::collections::HashSet<String>,
+    dry_run: bool,
+    grace: std::time::Duration,
+) -> AssetGcResult {
+    let mut result = AssetGcResult {
+        dry_run,
+        paths: Vec::new(),
+        freed_bytes: 0,
+        failed: Vec::new(),
+    };
+
+    for path in files {
+        let id = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        if referenced.contains(&id) {
+            continue;
+        }
+        let recent = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_none_or(|age| age < grace);
+        if recent {
+            continue;
+        }
+        let sidecar = sidecar_path(&path);
+        let size = |p: &std::path::Path| fs::metadata(p).map(|m| m.len()).unwrap_or(0);
+        let bytes = size(&path) + size(&sidecar);
+
+        if !dry_run {
+            if let Err(e) = fs::remove_file(&path) {
+                result.failed.push(FileResult {
+                    success: false,
+                    path: Some(path.to_string_lossy().to_string()),
+                    error: Some(format!("Failed to delete asset: {}", e)),
+                    code: Some("IO_ERROR".to_string()),
+                });
+                continue;
+            }
+            let _ = fs::remove_file(&sidecar);
+        }
+        result.freed_bytes += bytes;
+        result.paths.push(path.to_string_lossy().to_string());
+    }
+
+    result
+}
+
+/// Delete every asset whose id isn't in `referenced_ids` (the union of ids
+/// used by all known projects), along with its sidecar. With `dry_run`,
+/// only report what would be deleted so the UI can confirm first.
+///
+/// An empty `referenced_ids` (e.g. before any project has loaded) would
+/// delete everything, so it is rejected unless `allow_all` is set. Assets
+/// modified in the last ten minutes are always kept.
+#[tauri::command]
+async fn gc_unreferenced_assets(
+    referenced_ids: Vec<String>,
+    dry_run: bool,
+    allow_all: Option<bool>,
+) -> Result<AssetGcResult, AssetError> {
+    if referenced_ids.is_empty() && !allow_all.unwrap_or(false) {
+        return Err(AssetError {
+            code: "EMPTY_REFERENCE_SET".to_string(),
+            message: "No referenced assets given; pass allow_all to collect every asset".to_string(),
+        });
+    }
+    let referenced: std::collections::HashSet<String> = referenced_ids.into_iter().collect();
+    Ok(collect_unreferenced(all_asset_files()?, &referenced, dry_run, GC_GRACE_PERIOD))
+}
+
+/// List assets of a type together with their sidecar metadata
+#[tauri::command]
+async fn list_assets_with_metadata(asset_type: String) -> Result<Vec<AssetEntry>, AssetError> {
+    let paths = list_local_assets(asset_type).await?;
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let metadata = read_sidecar(std::path::Path::new(&path));
+            AssetEntry { path, metadata }
+        })
+        .collect())
+}
+
+/// Sort order for `search_assets`
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetSortKey {
+    #[default]
+    Name,
+    Size,
+    Modified,
+    /// Sidecar `imported_at`; assets without one sort first
+    Imported,
+}
+
+/// Filters for `search_assets`; unset fields match everything
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct AssetQuery {
+    /// One of the asset types; None searches all of them
+    pub asset_type: Option<String>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Unix seconds, inclusive
+    pub modified_after: Option<u64>,
+    /// Unix seconds, inclusive
+    pub modified_before: Option<u64>,
+    /// Case-insensitive substring of the file name, sidecar title or a tag
+    pub text: Option<String>,
+    #[serde(default)]
+    pub sort_by: AssetSortKey,
+    #[serde(default)]
+    pub descending: bool,
+    pub limit: Option<usize>,
+}
+
+/// Whether the text filter matches the file name or sidecar title/tags
+fn asset_text_matches(needle: &str, file_name: &str, metadata: Option<&AssetMetadata>) -> bool {
+    let contains = |haystack: &str| haystack.to_lowercase().contains(needle);
+    contains(file_name)
+        || metadata.is_some_and(|m| {
+            m.title.as_deref().is_some_and(contains) || m.tags.iter().any(|tag| contains(tag))
+        })
+}
+
+/// Search assets across every storage root by type, size, modified date
+/// and name/title/tag text, sorted by `sort_by`
+#[tauri::command]
+async fn search_assets(query: AssetQuery) -> Result<Vec<AssetEntry>, AssetError> {
+    let types: Vec<String> = match &query.asset_type {
+        Some(asset_type) => {
+            get_asset_type_dir(asset_type)?;
+            vec![asset_type.clone()]
+        }
+        None => ASSET_TYPES.iter().map(|t| t.to_string()).collect(),
+    };
+    let needle = query
+        .text
+        .as_deref()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_lowercase);
+
+    // (entry, name, size, modified) so sorting doesn't re-stat
+    let mut matches: Vec<(AssetEntry, String, u64, u64)> = Vec::new();
+    for root in storage_roots()? {
+        for asset_type in &types {
+            let Ok(entries) = fs::read_dir(root.join(asset_type)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                if !meta.is_file() || is_sidecar(&path) {
+                    continue;
+                }
+
+                let size = meta.len();
+                let modified = meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map_or(0, |d| d.as_secs());
+                if query.min_size.is_some_and(|min| size < min)
+                    || query.max_size.is_some_and(|max| size > max)
+                    || query.modified_after.is_some_and(|after| modified < after)
+                    || query.modified_before.is_some_and(|before| modified > before)
+                {
+                    continue;
+                }
+
+                let name = entry.file_name().to_string_lossy().to_string();
+                let metadata = read_sidecar(&path);
+                if let Some(needle) = &needle {
+                    if !asset_text_matches(needle, &name, metadata.as_ref()) {
+                        continue;
+                    }
+                }
+
+                let entry = AssetEntry {
+                    path: path.to_string_lossy().to_string(),
+                    metadata,
+                };
+                matches.push((entry, name.to_lowercase(), size, modified));
+            }
+        }
+    }
+
+    matches.sort_by(|a, b| {
+        let ordering = match query.sort_by {
+            AssetSortKey::Name => a.1.cmp(&b.1),
+            AssetSortKey::Size => a.2.cmp(&b.2),
+            AssetSortKey::Modified => a.3.cmp(&b.3),
+            AssetSortKey::Imported => {
+                let imported = |entry: &AssetEntry| entry.metadata.as_ref().and_then(|m| m.imported_at);
+                imported(&a.0).cmp(&imported(&b.0))
+            }
+        };
+        if query.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+
+    Ok(matches
+        .into_iter()
+        .take(query.limit.unwrap_or(usize::MAX))
+        .map(|(entry, ..)| entry)
+        .collect())
+}
+
+/// Copy an asset to a new location (for export/sharing)
+#[tauri::command]
+async fn copy_asset(source_path: String, destination_path: String) -> Result<FileResult, AssetError> {
+    let source = PathBuf::from(&source_path);
+    let dest = PathBuf::from(&destination_path);
+
+    if !source.exists() {
+        return Ok(FileResult {
+            success: false,
+            path: None,
+            error: Some("Source file does not exist".to_string()),
+            code: Some("FILE_NOT_FOUND".to_string()),
+        });
+    }
+
+    // Create parent directory if needed
+    if let Some(parent) = dest.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AssetError::io("Failed to create destination directory", e))?;
+        }
+    }
+
+    fs::copy(&source, &dest)
+        .map_err(|e| AssetError::io("Failed to copy file", e))?;
+
+    Ok(FileResult {
+        success: true,
+        path: Some(dest.to_string_lossy().to_string()),
+        error: None,
+        code: None,
+    })
+}
+
+/// Failed `FileResult` for a request that was rejected rather than erroring
+fn rejected(code: &str, error: impl Into<String>) -> FileResult {
+    FileResult {
+        success: false,
+        path: None,
+        error: Some(error.into()),
+        code: Some(code.to_string()),
+    }
+}
+
+/// Asset ids and type names become path components, so keep them to a safe charset
+fn is_valid_asset_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 128
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Resolve `path` and check it is a file inside the managed asset store.
+/// The inner `Err` is a rejection to hand straight back to the frontend.
+fn managed_asset_path(path: &str) -> Result<Result<PathBuf, FileResult>, AssetError> {
+    let path = PathBuf::from(path);
+    if !path.is_file() {
+        return Ok(Err(rejected("FILE_NOT_FOUND", "Source file does not exist")));
+    }
+    let mut roots = Vec::new();
+    for root in storage_roots()? {
+        roots.push(
+            root.canonicalize()
+                .map_err(|e| AssetError::io("Failed to resolve asset directory", e))?,
+        );
+    }
+    let resolved = path
+        .canonicalize()
+        .map_err(|e| AssetError::io("Failed to resolve asset path", e))?;
+    if !roots.iter().any(|root| resolved.starts_with(root)) {
+        return Ok(Err(rejected("OUTSIDE_ASSET_STORE", "Path is not inside the asset directory")));
+    }
+    Ok(Ok(resolved))
+}
+
+/// Storage root whose type directory directly holds `path`
+fn type_dir_root(path: &std::path::Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    let type_dir = path.parent()?;
+    let root = type_dir.parent()?;
+    let is_type_dir = type_dir
+        .file_name()
+        .is_some_and(|name| ASSET_TYPES.iter().any(|t| name == *t));
+    (is_type_dir && roots.iter().any(|r| r == root)).then(|| root.to_path_buf())
+}
+
+/// Rename an asset within its type directory, keeping the extension
+#[tauri::command]
+async fn rename_asset(old_path: String, new_id: String) -> Result<FileResult, AssetError> {
+    if !is_valid_asset_name(&new_id) {
+        return Ok(rejected("INVALID_ID", format!("Invalid asset id: {}", new_id)));
+    }
+    let source = match managed_asset_path(&old_path)? {
+        Ok(path) => path,
+        Err(result) => return Ok(result),
+    };
+
+    let filename = match source.extension() {
+        Some(ext) => format!("{}.{}", new_id, ext.to_string_lossy()),
+        None => new_id.clone(),
+    };
+    let dest = source.with_file_name(filename);
+    if dest.exists() {
+        return Ok(rejected("ALREADY_EXISTS", "An asset with that id already exists"));
+    }
+
+    fs::rename(&source, &dest).map_err(|e| AssetError::io("Failed to rename asset", e))?;
+    if let Some(mut metadata) = read_sidecar(&source) {
+        metadata.id = new_id;
+        if let Ok(json) = serde_json::to_string_pretty(&metadata) {
+            let _ = fs::write(sidecar_path(&dest), json);
+        }
+        let _ = fs::remove_file(sidecar_path(&source));
+    }
+
+    Ok(FileResult {
+        success: true,
+        path: Some(dest.to_string_lossy().to_string()),
+        error: None,
+        code: None,
+    })
+}
+
+/// Move an asset into another type subdirectory, keeping its filename
+#[tauri::command]
+async fn move_asset(source_path: String, dest_asset_type: String) -> Result<FileResult, AssetError> {
+    let source = match managed_asset_path(&source_path)? {
+        Ok(path) => path,
+        Err(result) => return Ok(result),
+    };
+
+    // Stay within the source's storage root so the move never crosses drives
+    get_asset_type_dir(&dest_asset_type)?;
+    let roots: Vec<PathBuf> = storage_roots()?.iter().map(|root| canonical_root(root)).collect();
+    let Some(root) = type_dir_root(&source, &roots) else {
+        return Ok(rejected("INVALID_PATH", "Asset is not inside a type directory"));
+    };
+    let type_dir = root.join(&dest_asset_type);
+    fs::create_dir_all(&type_dir).map_err(|e| AssetError::io("Failed to create directory", e))?;
+    let dest = type_dir.join(source.file_name().unwrap_or_default());
+    if dest.exists() {
+        return Ok(rejected("ALREADY_EXISTS", "An asset with that name already exists"));
+    }
+
+    // Same filesystem, so this is an atomic rename rather than copy + delete
+    fs::rename(&source, &dest).map_err(|e| AssetError::io("Failed to move asset", e))?;
+    let _ = fs::rename(sidecar_path(&source), sidecar_path(&dest));
+
+    Ok(FileResult {
+        success: true,
+        path: Some(dest.to_string_lossy().to_string()),
+        error: None,
+        code: None,
+    })
+}
+
+/// `file://` URI for an absolute path, percent-encoding anything unusual
+fn file_uri(path: &std::path::Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Open the platform file manager with `path` selected. A path that no
+/// longer exists falls back to opening its parent directory.
+#[tauri::command]
+async fn reveal_in_file_manager(path: String) -> Result<FileResult, AssetError> {
+    use std::process::Command;
+
+    let target = PathBuf::from(&path);
+    let opened = if target.exists() {
+        target.clone()
+    } else {
+        match target.parent().filter(|p| p.is_dir()) {
+            Some(parent) => parent.to_path_buf(),
+            None => return Ok(rejected("FILE_NOT_FOUND", format!("{} no longer exists", path))),
+        }
+    };
+    let select = opened == target && target.is_file();
+
+    let status = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("explorer");
+        if select {
+            cmd.arg(format!("/select,{}", target.display()));
+        } else {
+            cmd.arg(&opened);
+        }
+        // explorer exits non-zero even on success, so only spawn failures count
+        cmd.status().map(|_| true)
+    } else if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("open");
+        if select {
+            cmd.arg("-R");
+        }
+        cmd.arg(&opened).status().map(|s| s.success())
+    } else {
+        // freedesktop FileManager1.ShowItems selects the file; not every
+        // desktop implements it, so fall back to opening the folder
+        let shown = select
+            && Command::new("dbus-send")
+                .args([
+                    "--session",
+                    "--dest=org.freedesktop.FileManager1",
+                    "--type=method_call",
+                    "/org/freedesktop/FileManager1",
+                    "org.freedesktop.FileManager1.ShowItems",
+                ])
+                .arg(format!("array:string:{}", file_uri(&opened)))
+                .arg("string:")
+                .status()
+                .is_ok_and(|s| s.success());
+        if shown {
+            Ok(true)
+        } else {
+            let folder = if select { opened.parent().unwrap_or(&opened) } else { &opened };
+            Command::new("xdg-open").arg(folder).status().map(|s| s.success())
+        }
+    };
+
+    match status {
+        Ok(true) => Ok(FileResult {
+            success: true,
+            path: Some(opened.to_string_lossy().to_string()),
+            error: None,
+            code: None,
+        }),
+        Ok(false) => Ok(rejected("OPEN_FAILED", "The file manager could not be opened")),
+        Err(e) => Err(AssetError::io("Failed to launch file manager", e)),
+    }
+}
+
+/// Running totals of a storage walk
+#[derive(Default)]
+struct ScanTotals {
+    files: u64,
+    bytes: u64,
+}
+
+/// Add the size of everything under `path` to `totals`, calling `on_file`
+/// after each file. Returns `ErrorKind::Interrupted` once `cancelled` is set.
+fn dir_size(
+    path: &std::path::Path,
+    totals: &mut ScanTotals,
+    cancelled: &AtomicBool,
+    on_file: &mut dyn FnMut(&ScanTotals),
+) -> std::io::Result<()> {
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            if cancelled.load(Ordering::SeqCst) {
+                return Err(std::io::ErrorKind::Interrupted.into());
+            }
+            let entry = entry?;
+            let path = entry.path();
+            if path.is_dir() {
+                dir_size(&path, totals, cancelled, on_file)?;
+            } else {
+                totals.files += 1;
+                totals.bytes += entry.metadata()?.len();
+                on_file(totals);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Total bytes used by local assets across every available root
+fn scan_storage_usage(
+    cancelled: &AtomicBool,
+    on_file: &mut dyn FnMut(&ScanTotals),
+) -> Result<u64, AssetError> {
+    let mut totals = ScanTotals::default();
+    for root in storage_roots()? {
+        dir_size(&root, &mut totals, cancelled, on_file).map_err(|e| match e.kind() {
+            std::io::ErrorKind::Interrupted => AssetError {
+                code: "CANCELLED".to_string(),
+                message: "Storage scan cancelled".to_string(),
+            },
+            _ => AssetError::io("Failed to calculate storage", e),
+        })?;
+    }
+    Ok(totals.bytes)
+}
+
+/// Total bytes used by local assets across every available root
+fn compute_storage_usage() -> Result<u64, AssetError> {
+    scan_storage_usage(&AtomicBool::new(false), &mut |_| {})
+}
+
+/// An asset extracted from a project bundle
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ImportedAsset {
+    pub id: String,
+    pub asset_type: String,
+    pub path: String,
+}
+
+/// A bundle entry that was not imported, and why
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SkippedBundleEntry {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Manifest of a project bundle import
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BundleImportResult {
+    pub imported: Vec<ImportedAsset>,
+    pub skipped: Vec<SkippedBundleEntry>,
+}
+
+/// Where a bundle entry named `{asset_type}/{id}.{ext}` belongs. `Err(None)`
+/// marks a path that escapes the bundle (zip-slip); `Err(Some(reason))` an
+/// entry that is merely not an asset.
+fn bundle_entry_target(name: &str) -> Result<(String, String), Option<String>> {
+    use std::path::Component;
+
+    let mut parts = Vec::new();
+    for component in std::path::Path::new(name).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::CurDir => {}
+            _ => return Err(None),
+        }
+    }
+    // Backslash separators are literal on Unix but traversal on Windows
+    if parts.iter().any(|part| part.contains('\\')) {
+        return Err(None);
+    }
+
+    let [asset_type, filename] = parts.as_slice() else {
+        return Err(Some("not in an asset type directory".to_string()));
+    };
+    if !ASSET_TYPES.contains(&asset_type.as_str()) {
+        return Err(Some(format!("unknown asset type '{}'", asset_type)));
+    }
+    let stem = match filename.strip_suffix(SIDECAR_SUFFIX) {
+        Some(id) => id.into(),
+        None => std::path::Path::new(filename)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy(),
+    };
+    if !is_valid_asset_name(&stem) {
+        return Err(Some("invalid asset id".to_string()));
+    }
+    Ok((asset_type.clone(), filename.clone()))
+}
+
+/// Bundled sidecars are small JSON; anything larger is not one of ours
+const MAX_BUNDLED_SIDECAR_BYTES: u64 = 1024 * 1024;
+
+fn import_bundle(zip_path: &str) -> Result<BundleImportResult, AssetError> {
+    let file = fs::File::open(zip_path).map_err(|e| AssetError::io("Failed to open bundle", e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| AssetError {
+        code: "INVALID_BUNDLE".to_string(),
+        message: format!("Failed to read bundle: {}", e),
+    })?;
+
+    // Check every name before writing anything so a malicious bundle
+    // leaves no partial import behind
+    for name in archive.file_names() {
+        if let Err(None) = bundle_entry_target(name) {
+            return Err(AssetError {
+                code: "UNSAFE_BUNDLE_ENTRY".to_string(),
+                message: format!("Bundle entry escapes the asset directory: {}", name),
+            });
+        }
+    }
+
+    let mut result = BundleImportResult {
+        imported: Vec::new(),
+        skipped: Vec::new(),
+    };
+    // Bundled sidecars are applied once their asset is written, keeping only
+    // the descriptive fields; the hash is taken from what reached the disk
+    let mut sidecars: HashMap<(String, String), (String, AssetMetadata)> = HashMap::new();
+    let mut hashes = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| AssetError {
+            code: "INVALID_BUNDLE".to_string(),
+            message: format!("Failed to read bundle entry: {}", e),
+        })?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let (asset_type, filename) = match bundle_entry_target(&name) {
+            Ok(target) => target,
+            Err(reason) => {
+                result.skipped.push(SkippedBundleEntry {
+                    name,
+                    reason: reason.unwrap_or_default(),
+                });
+                continue;
+            }
+        };
+
+        let dest = get_asset_type_dir(&asset_type)?.join(&filename);
+        if dest.exists() {
+            result.skipped.push(SkippedBundleEntry {
+                name,
+                reason: "an asset with this name already exists".to_string(),
+            });
+            continue;
+        }
+
+        if let Some(id) = filename.strip_suffix(SIDECAR_SUFFIX) {
+            let mut json = String::new();
+            let metadata = (&mut entry)
+                .take(MAX_BUNDLED_SIDECAR_BYTES)
+                .read_to_string(&mut json)
+                .ok()
+                .and_then(|_| serde_json::from_str::<AssetMetadata>(&json).ok());
+            match metadata {
+                Some(metadata) => {
+                    sidecars.insert((asset_type, id.to_string()), (name, metadata));
+                }
+                None => result.skipped.push(SkippedBundleEntry {
+                    name,
+                    reason: "unreadable sidecar".to_string(),
+                }),
+            }
+            continue;
+        }
+
+        // Same seal-and-hash path as save_asset_bytes; a failed copy leaves
+        // no truncated asset
+        let sha256 = write_asset_stream(&dest, &mut entry).map_err(|e| AssetError {
+            message: format!("Failed to extract {}: {}", name, e.message),
+            ..e
+        })?;
+        hashes.push(sha256);
+        result.imported.push(ImportedAsset {
+            id: dest.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+            asset_type,
+            path: dest.to_string_lossy().to_string(),
+        });
+    }
+
+    for (asset, sha256) in result.imported.iter().zip(hashes) {
+        let metadata = match sidecars.remove(&(asset.asset_type.clone(), asset.id.clone())) {
+            Some((_, bundled)) => AssetMetadata {
+                id: asset.id.clone(),
+                etag: None,
+                sha256: Some(sha256),
+                ..bundled
+            },
+            None => AssetMetadata {
+                id: asset.id.clone(),
+                imported_at: unix_now(),
+                sha256: Some(sha256),
+                ..Default::default()
+            },
+        };
+        write_sidecar(std::path::Path::new(&asset.path), &metadata)?;
+    }
+    for (name, _) in sidecars.into_values() {
+        result.skipped.push(SkippedBundleEntry {
+            name,
+            reason: "sidecar without its asset".to_string(),
+        });
+    }
+
+    Ok(result)
+}
+
+/// Import a project bundle zip laid out as `{asset_type}/{id}.{ext}` (with
+/// optional `{id}.meta.json` sidecars). Entries are streamed to disk one at
+/// a time, sealed and hashed like saved assets; existing assets and
+/// unrecognised files are skipped and listed, and paths that would escape
+/// the asset directory reject the whole bundle.
+#[tauri::command]
+async fn import_project_bundle(
+    app: tauri::AppHandle,
+    zip_path: String,
+) -> Result<BundleImportResult, AssetError> {
+    let result = tauri::async_runtime::spawn_blocking(move || import_bundle(&zip_path))
+        .await
+        .map_err(|e| AssetError {
+            code: "TASK_JOIN_ERROR".to_string(),
+            message: format!("Task join error: {}", e),
+        })?;
+    spawn_storage_watch_check(&app);
+    result
+}
+
+/// Emitted after each entry written by `export_assets_zip`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ZipProgressEvent {
+    pub export_id: Option<String>,
+    pub files_done: u64,
+    pub total_files: u64,
+    pub current_entry: String,
+}
+
+/// Cancellation flags of running zip exports, by export id
+#[derive(Default)]
+pub struct ZipExportState {
+    exports: std::sync::Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+/// Id of the asset a file in a type directory belongs to; a sidecar
+/// (`{id}.meta.json`, or the legacy `{id}.json`) belongs to its asset
+fn owning_asset_id(path: &std::path::Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    match name.strip_suffix(SIDECAR_SUFFIX) {
+        Some(id) => id.to_string(),
+        None => path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+    }
+}
+
+/// Bundle entries (`{asset_type}/{filename}`, source path) for the assets
+/// with the given ids, or every asset when `ids` is None. Sidecars travel
+/// with their asset.
+fn collect_bundle_entries(ids: Option<&[String]>) -> Result<Vec<(String, PathBuf)>, AssetError> {
+    Ok(collect_bundle_entries_in(&storage_roots()?, ids))
+}
+
+fn collect_bundle_entries_in(roots: &[PathBuf], ids: Option<&[String]>) -> Vec<(String, PathBuf)> {
+    let mut entries = Vec::new();
+    for root in roots {
+        for asset_type in ASSET_TYPES {
+            let Ok(dir) = fs::read_dir(root.join(asset_type)) else {
+                continue;
+            };
+            for entry in dir.flatten() {
+                let path = entry.path();
+                if !path.is_file() {
+                    continue;
+                }
+                if ids.is_some_and(|ids| !ids.contains(&owning_asset_id(&path))) {
+                    continue;
+                }
+                let name = format!("{}/{}", asset_type, entry.file_name().to_string_lossy());
+                // The same asset in two roots would collide inside the zip
+                if !entries.iter().any(|(existing, _)| *existing == name) {
+                    entries.push((name, path));
+                }
+            }
+        }
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+fn zip_error(context: &str, err: zip::result::ZipError) -> AssetError {
+    AssetError {
+        code: "ZIP_ERROR".to_string(),
+        message: format!("{}: {}", context, err),
+    }
+}
+
+/// Copy each of `entries` into `zip`, calling `on_entry` after each
+fn write_zip_entries(
+    zip: &mut zip::ZipWriter<fs::File>,
+    entries: &[(String, PathBuf)],
+    cancelled: &AtomicBool,
+    on_entry: &mut dyn FnMut(u64, &str),
+) -> Result<(), AssetError> {
+    for (done, (name, path)) in entries.iter().enumerate() {
+        if cancelled.load(Ordering::SeqCst) {
+            return Err(AssetError {
+                code: "CANCELLED".to_string(),
+                message: "Zip export cancelled".to_string(),
+            });
+        }
+        // Media is already compressed; deflating it only costs time
+        let method = if is_sidecar(path) {
+            zip::CompressionMethod::Deflated
+        } else {
+            zip::CompressionMethod::Stored
+        };
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(method)
+            .large_file(true);
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| zip_error(&format!("Failed to add {}", name), e))?;
+        let mut source = fs::File::open(path)
+            .map_err(|e| AssetError::io(&format!("Failed to read {}", name), e))?;
+        std::io::copy(&mut source, zip)
+            .map_err(|e| AssetError::io(&format!("Failed to write {}", name), e))?;
+        on_entry(done as u64 + 1, name);
+    }
+    Ok(())
+}
+
+/// Write `entries` into a new zip at `zip_path`. A failed or cancelled
+/// export removes the partial zip.
+fn write_assets_zip(
+    zip_path: &str,
+    entries: &[(String, PathBuf)],
+    cancelled: &AtomicBool,
+    on_entry: &mut dyn FnMut(u64, &str),
+) -> Result<u64, AssetError> {
+    let file = fs::File::create(zip_path).map_err(|e| AssetError::io("Failed to create zip", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+
+    let written = write_zip_entries(&mut zip, entries, cancelled, on_entry)
+        .and_then(|()| zip.finish().map_err(|e| zip_error("Failed to finish zip", e)));
+    if let Err(e) = written {
+        let _ = fs::remove_file(zip_path);
+        return Err(e);
+    }
+    Ok(entries.len() as u64)
+}
+
+/// Export assets (all, or those in `asset_ids`) with their sidecars to a zip
+/// in the layout `import_project_bundle` reads. Emits "zip-progress" after
+/// each entry. Pass `export_id` to allow `cancel_zip_export`; a cancelled
+/// export deletes the partial zip and fails with code "CANCELLED". Returns
+/// the number of files written.
+#[tauri::command]
+async fn export_assets_zip(
+    app: tauri::AppHandle,
+    zip_path: String,
+    asset_ids: Option<Vec<String>>,
+    export_id: Option<String>,
+    state: tauri::State<'_, ZipExportState>,
+) -> Result<u64, AssetError> {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    if let (Some(export_id), Ok(mut exports)) = (&export_id, state.exports.lock()) {
+        exports.insert(export_id.clone(), cancelled.clone());
+    }
+
+    let progress_export_id = export_id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let entries = collect_bundle_entries(asset_ids.as_deref())?;
+        let total_files = entries.len() as u64;
+        write_assets_zip(&zip_path, &entries, &cancelled, &mut |files_done, name| {
+            let _ = app.emit(
+                "zip-progress",
+                ZipProgressEvent {
+                    export_id: progress_export_id.clone(),
+                    files_done,
+                    total_files,
+                    current_entry: name.to_string(),
+                },
+            );
+        })
+    })
+    .await
+    .map_err(|e| AssetError {
+        code: "TASK_JOIN_ERROR".to_string(),
+        message: format!("Task join error: {}", e),
+    });
+
+    if let (Some(export_id), Ok(mut exports)) = (&export_id, state.exports.lock()) {
+        exports.remove(export_id);
+    }
+    result?
+}
+
+/// Cancel a running `export_assets_zip`. Returns false if no export with
+/// that id is running.
+#[tauri::command]
+fn cancel_zip_export(export_id: String, state: tauri::State<'_, ZipExportState>) -> bool {
+    match state.exports.lock().ok().and_then(|exports| exports.get(&export_id).cloned()) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Emit storage scan progress at most this often
+const STORAGE_SCAN_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Emitted periodically while `get_storage_usage` walks the asset roots
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StorageScanProgress {
+    pub scan_id: Option<String>,
+    pub files_counted: u64,
+    pub bytes_counted: u64,
+}
+
+/// Cancellation flags of running storage scans, by scan id
+#[derive(Default)]
+pub struct StorageScanState {
+    scans: std::sync::Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+/// Get total storage used by local assets, emitting "storage-scan-progress"
+/// while walking. Pass `scan_id` to allow `cancel_storage_scan`; a cancelled
+/// scan fails with code "CANCELLED".
+#[tauri::command]
+async fn get_storage_usage(
+    app: tauri::AppHandle,
+    scan_id: Option<String>,
+    state: tauri::State<'_, StorageScanState>,
+) -> Result<u64, AssetError> {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    if let (Some(scan_id), Ok(mut scans)) = (&scan_id, state.scans.lock()) {
+        scans.insert(scan_id.clone(), cancelled.clone());
+    }
+
+    let walk_scan_id = scan_id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let mut last_emitted = std::time::Instant::now();
+        scan_storage_usage(&cancelled, &mut |totals| {
+            if last_emitted.elapsed() >= STORAGE_SCAN_PROGRESS_INTERVAL {
+                last_emitted = std::time::Instant::now();
+                let _ = app.emit(
+                    "storage-scan-progress",
+                    StorageScanProgress {
+                        scan_id: walk_scan_id.clone(),
+                        files_counted: totals.files,
+                        bytes_counted: totals.bytes,
+                    },
+                );
+            }
+        })
+    })
+    .await
+    .map_err(|e| AssetError {
+        code: "TASK_JOIN_ERROR".to_string(),
+        message: format!("Task join error: {}", e),
+    });
+
+    if let (Some(scan_id), Ok(mut scans)) = (&scan_id, state.scans.lock()) {
+        scans.remove(scan_id);
+    }
+    result?
+}
+
+/// Cancel a running `get_storage_usage` scan. Returns false if no scan
+/// with that id is running.
+#[tauri::command]
+fn cancel_storage_scan(scan_id: String, state: tauri::State<'_, StorageScanState>) -> bool {
+    match state.scans.lock().ok().and_then(|scans| scans.get(&scan_id).cloned()) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+/// How often the storage watch re-checks usage in the background
+const STORAGE_WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// While usage stays above the threshold, re-emit at most this often
+const STORAGE_WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(30 * 60);
+
+/// Emitted when asset storage crosses the registered threshold
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StorageThresholdEvent {
+    pub usage_bytes: u64,
+    pub warn_bytes: u64,
+}
+
+/// Registered storage threshold and debounce bookkeeping
+#[derive(Default)]
+struct StorageWatch {
+    warn_bytes: Option<u64>,
+    last_emitted: Option<std::time::Instant>,
+    background_started: bool,
+}
+
+/// Managed state for `register_storage_watch`
+#[derive(Default)]
+pub struct StorageWatchState {
+    watch: std::sync::Mutex<StorageWatch>,
+}
+
+/// Run `check_storage_watch` on the blocking pool without waiting; it walks
+/// the whole storage tree
+fn spawn_storage_watch_check(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || check_storage_watch(&app));
+}
+
+/// Compare usage against the registered threshold and emit
+/// "storage-threshold-exceeded" when crossed (debounced). Cheap no-op when
+/// no watch is registered.
+fn check_storage_watch(app: &tauri::AppHandle) {
+    let state = app.state::<StorageWatchState>();
+    let warn_bytes = match state.watch.lock().ok().and_then(|watch| watch.warn_bytes) {
+        Some(warn_bytes) => warn_bytes,
+        None => return,
+    };
+
+    let usage_bytes = match compute_storage_usage() {
+        Ok(usage) => usage,
+        Err(e) => {
+            eprintln!("Warning: storage watch failed: {}", e);
+            return;
+        }
+    };
+
+    let Ok(mut watch) = state.watch.lock() else {
+        return;
+    };
+    if usage_bytes < warn_bytes {
+        // Dropped back below: the next crossing should notify immediately
+        watch.last_emitted = None;
+        return;
+    }
+    if watch
+        .last_emitted
+        .is_some_and(|at| at.elapsed() < STORAGE_WATCH_DEBOUNCE)
+    {
+        return;
+    }
+    watch.last_emitted = Some(std::time::Instant::now());
+    drop(watch);
+
+    let _ = app.emit(
+        "storage-threshold-exceeded",
+        StorageThresholdEvent {
+            usage_bytes,
+            warn_bytes,
+        },
+    );
+}
+
+/// Watch asset storage and emit "storage-threshold-exceeded" once usage
+/// reaches `warn_bytes`. Checked after saves/downloads and periodically.
+#[tauri::command]
+async fn register_storage_watch(
+    app: tauri::AppHandle,
+    warn_bytes: u64,
+    state: tauri::State<'_, StorageWatchState>,
+) -> Result<(), AssetError> {
+    let start_background = {
+        let mut watch = state.watch.lock().map_err(|e| AssetError {
+            code: "STATE_ERROR".to_string(),
+            message: e.to_string(),
+        })?;
+        watch.warn_bytes = Some(warn_bytes);
+        watch.last_emitted = None;
+        !std::mem::replace(&mut watch.background_started, true)
+    };
+
+    if start_background {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(STORAGE_WATCH_INTERVAL).await;
+                let app = app.clone();
+                let _ = tauri::async_runtime::spawn_blocking(move || check_storage_watch(&app)).await;
+            }
+        });
+    }
+
+    let _ = tauri::async_runtime::spawn_blocking(move || check_storage_watch(&app)).await;
+    Ok(())
+}
+
+/// Stop watching storage usage
+#[tauri::command]
+async fn clear_storage_watch(state: tauri::State<'_, StorageWatchState>) -> Result<(), AssetError> {
+    let mut watch = state.watch.lock().map_err(|e| AssetError {
+        code: "STATE_ERROR".to_string(),
+        message: e.to_string(),
+    })?;
+    watch.warn_bytes = None;
+    watch.last_emitted = None;
+    Ok(())
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    // Initialize FFmpeg
+    if let Err(e) = video_decoder::init_ffmpeg() {
         eprintln!("Warning: Failed to initialize FFmpeg: {}", e);
     }
 
+    // Evict video handles the frontend forgot to close
+    video_decoder::start_handle_sweeper();
+
+    // Sidecars used to be `{id}.json`; rename them before anything lists assets
+    if let Err(e) = migrate_legacy_sidecars() {
+        eprintln!("Warning: Failed to migrate asset sidecars: {}", e);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
         .manage(melt_runner::MeltState::new())
+        .manage(StorageWatchState::default())
+        .manage(StorageScanState::default())
+        .manage(ZipExportState::default())
+        .setup(|app| {
+            // Let find_melt prefer a melt shipped with the app
+            if let Ok(resource_dir) = app.path().resource_dir() {
+                melt_runner::set_resource_dir(&app.state::<melt_runner::MeltState>(), resource_dir);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Asset management commands
             download_asset,
+            download_assets_batch,
             save_asset_bytes,
             delete_asset,
             asset_exists,
             get_asset_size,
+            get_asset_info,
             get_asset_directory,
+            add_storage_root,
+            list_storage_roots,
+            set_primary_root,
             generate_asset_id,
             list_local_assets,
             copy_asset,
+            rename_asset,
+            move_asset,
+            reveal_in_file_manager,
+            write_asset_metadata,
+            read_asset_metadata,
+            asset_is_current,
+            verify_asset_integrity,
+            verify_all_assets,
+            gc_unreferenced_assets,
+            list_assets_with_metadata,
+            search_assets,
+            import_project_bundle,
             get_storage_usage,
+            cancel_storage_scan,
+            export_assets_zip,
+            cancel_zip_export,
+            register_storage_watch,
+            clear_storage_watch,
             // Video decoder commands
             cmd_get_video_info,
+            cmd_probe_streams,
+            cmd_register_image_sequence,
             cmd_open_video,
             cmd_close_video,
+            cmd_get_video_handle_info,
+            cmd_get_frame_from_handle,
+            cmd_list_open_videos,
+            cmd_prune_video_handles,
+            cmd_check_video_integrity,
+            cmd_can_decode,
             cmd_get_frame_at_time,
+            cmd_get_frame_with_dimensions,
+            cmd_get_frame_data_url,
+            cmd_get_nearest_keyframe_frame,
             cmd_get_frame_at_time_with_quality,
+            cmd_get_frame_from_url,
+            cmd_get_frame_at_index,
+            cmd_extract_frame_to_file,
+            cmd_get_keyframe_timestamps,
+            cmd_get_gop_structure,
+            cmd_generate_preview_loop,
+            cmd_detect_scene_cuts,
+            cmd_compute_video_phash,
+            cmd_phash_distance,
+            cmd_get_cover_art,
             cmd_generate_thumbnails,
             cmd_generate_thumbnails_with_options,
+            cmd_generate_thumbnails_with_timestamps,
+            cmd_generate_smart_filmstrip,
+            cmd_cancel_thumbnail_job,
+            cmd_generate_thumbnails_count,
             cmd_get_first_frame,
+            cmd_get_fast_poster,
+            cmd_get_interpolated_frame,
+            cmd_get_smart_poster_frame,
             cmd_get_thumbnail_at_percent,
+            cmd_extract_frames_to_dir,
+            cmd_cancel_extract_frames,
+            loudness::cmd_measure_loudness,
+            thumb_cache::cmd_thumbnail_cache_size,
+            thumb_cache::cmd_clear_thumbnail_cache,
+            stream_copy::cmd_lossless_trim,
+            stream_copy::cmd_concat_clips,
+            stream_copy::cmd_remux,
+            asset_crypto::set_asset_passphrase,
+            asset_crypto::is_asset_encrypted,
+            asset_crypto::read_asset_decrypted,
+            image_metadata::read_image_metadata,
+            image_metadata::write_image_metadata,
+            image_metadata::get_image_thumbnail,
             // MLT/melt render commands
             melt_runner::check_melt,
+            melt_runner::set_melt_path,
+            melt_runner::melt_capabilities,
             melt_runner::run_melt_render,
+            melt_runner::render_to_fifo,
+            melt_runner::build_and_render,
+            melt_runner::render_preview,
+            melt_runner::estimate_render,
             melt_runner::cancel_melt_render,
+            melt_runner::enqueue_render,
+            melt_runner::list_render_jobs,
+            melt_runner::list_active_renders,
+            melt_runner::cancel_all_renders,
+            melt_runner::list_render_history,
+            melt_runner::clear_render_history,
+            melt_runner::set_max_concurrent_renders,
             melt_runner::get_mlt_temp_dir,
             melt_runner::cleanup_mlt_temp_files,
+            melt_runner::cleanup_mlt_temp_files_older_than,
             melt_runner::run_melt_raw,
+            melt_runner::run_melt_raw_streaming,
             melt_runner::validate_mlt_xml,
+            melt_runner::preflight_mlt,
+            melt_runner::validate_render_options,
+            melt_runner::list_render_presets,
+            melt_runner::render_preset,
+            melt_runner::verify_render_output,
+            melt_runner::generate_proxy,
+            melt_runner::list_proxies,
+            melt_runner::substitute_proxies,
+            melt_runner::extract_audio,
+            mlt_parser::parse_mlt_xml,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Don't leave melt encoding in the background after quitting
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                melt_runner::shutdown(&app.state::<MeltState>());
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    /// Temp dir holding `keep.png` and `orphan.png`, each with a sidecar
+    fn gc_fixture() -> (PathBuf, Vec<PathBuf>) {
+        let dir = std::env::temp_dir().join(format!("gc-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut files = Vec::new();
+        for (name, len) in [("keep", 10), ("orphan", 100)] {
+            let path = dir.join(format!("{}.png", name));
+            fs::write(&path, vec![0u8; len]).unwrap();
+            fs::write(sidecar_path(&path), b"{}").unwrap();
+            files.push(path);
+        }
+        (dir, files)
+    }
+
+    #[test]
+    fn test_gc_dry_run_keeps_files() {
+        let (dir, files) = gc_fixture();
+        let referenced: HashSet<String> = ["keep".to_string()].into();
+        let orphan = dir.join("orphan.png");
+
+        let result = collect_unreferenced(files, &referenced, true, Duration::ZERO);
+        assert!(result.dry_run);
+        assert_eq!(result.paths, vec![orphan.to_string_lossy().to_string()]);
+        // Asset plus its two-byte sidecar
+        assert_eq!(result.freed_bytes, 102);
+        assert!(orphan.is_file() && sidecar_path(&orphan).is_file());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_gc_deletes_unreferenced_assets_and_sidecars() {
+        let (dir, files) = gc_fixture();
+        let referenced: HashSet<String> = ["keep".to_string()].into();
+        let keep = dir.join("keep.png");
+        let orphan = dir.join("orphan.png");
+
+        let result = collect_unreferenced(files, &referenced, false, Duration::ZERO);
+        assert_eq!(result.paths.len(), 1);
+        assert_eq!(result.freed_bytes, 102);
+        assert!(result.failed.is_empty());
+        assert!(!orphan.exists() && !sidecar_path(&orphan).exists());
+        assert!(keep.is_file() && sidecar_path(&keep).is_file());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_gc_skips_recent_files() {
+        let (dir, files) = gc_fixture();
+        let result = collect_unreferenced(files, &HashSet::new(), false, GC_GRACE_PERIOD);
+        assert!(result.paths.is_empty());
+        assert_eq!(result.freed_bytes, 0);
+        assert!(dir.join("orphan.png").is_file());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_legacy_sidecars_migrate_but_json_assets_stay() {
+        let dir = std::env::temp_dir().join(format!("sidecar-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut files = Vec::new();
+        for name in ["clip.mp4", "clip.json", "lottie.json"] {
+            let path = dir.join(name);
+            fs::write(&path, b"{}").unwrap();
+            files.push(path);
+        }
+
+        assert_eq!(migrate_legacy_sidecars_in(&files), 1);
+        let sidecar = sidecar_path(&dir.join("clip.mp4"));
+        assert!(sidecar.ends_with("clip.meta.json") && sidecar.is_file() && is_sidecar(&sidecar));
+        assert!(!dir.join("clip.json").exists());
+        assert!(dir.join("lottie.json").is_file() && !is_sidecar(&dir.join("lottie.json")));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_storage_root_checks() {
+        let dir = canonical_root(&std::env::temp_dir().join(format!("roots-test-{}", Uuid::new_v4())));
+        let registered = vec![dir.join("a")];
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::create_dir_all(dir.join("b").join("image")).unwrap();
+        fs::create_dir_all(dir.join("c")).unwrap();
+        fs::write(dir.join("c").join("notes.txt"), b"x").unwrap();
+
+        let code = |root: PathBuf| check_storage_root(&root, &registered).err().map(|e| e.code);
+        let codes = [
+            code(dir.join("b")),
+            code(dir.join("fresh")),
+            code(dir.join("a").join("inner")),
+            code(dir.clone()),
+            code(dir.join("c")),
+            code(PathBuf::from("/")),
+        ];
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(codes[0], None);
+        assert_eq!(codes[1], None);
+        assert_eq!(codes[2].as_deref(), Some("OVERLAPPING_STORAGE_ROOT"));
+        assert_eq!(codes[3].as_deref(), Some("NOT_DEDICATED_DIRECTORY"));
+        assert_eq!(codes[4].as_deref(), Some("NOT_DEDICATED_DIRECTORY"));
+        assert_eq!(codes[5].as_deref(), Some("NOT_DEDICATED_DIRECTORY"));
+        if let Some(home) = dirs::home_dir() {
+            assert!(check_storage_root(&canonical_root(&home), &[]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_canonical_root_resolves_missing_tails() {
+        let real = std::env::temp_dir().join(format!("roots-real-{}", Uuid::new_v4()));
+        fs::create_dir_all(&real).unwrap();
+        let spelled = real.join("..").join("missing").join("root");
+        let resolved = canonical_root(&spelled);
+        let _ = fs::remove_dir_all(&real);
+
+        let tmp = canonical_root(&std::env::temp_dir());
+        assert_eq!(resolved, tmp.join("missing").join("root"));
+    }
+
+    #[test]
+    fn test_type_dir_root_requires_a_type_directory() {
+        let root = PathBuf::from("/store");
+        let roots = [root.clone()];
+        assert_eq!(type_dir_root(&root.join("image").join("a.png"), &roots), Some(root.clone()));
+        assert_eq!(type_dir_root(&root.join("a.png"), &roots), None);
+        assert_eq!(type_dir_root(&root.join("image").join("sub").join("a.png"), &roots), None);
+        assert_eq!(type_dir_root(&root.join("notes").join("a.png"), &roots), None);
+    }
+
+    #[test]
+    fn test_write_asset_stream_hashes_what_it_writes() {
+        let dest = std::env::temp_dir().join(format!("stream-{}.bin", Uuid::new_v4()));
+        let data = vec![5u8; 200_000];
+        let sha256 = write_asset_stream(&dest, &mut data.as_slice());
+        let on_disk = sha256_file(&dest);
+        let _ = fs::remove_file(&dest);
+
+        assert_eq!(sha256.unwrap(), on_disk.unwrap());
+    }
+
+    #[test]
+    fn test_bundle_export_of_one_id_includes_its_sidecar() {
+        let root = std::env::temp_dir().join(format!("bundle-test-{}", Uuid::new_v4()));
+        let image_dir = root.join("image");
+        fs::create_dir_all(&image_dir).unwrap();
+        for name in ["a.png", "a.meta.json", "b.png", "b.meta.json"] {
+            fs::write(image_dir.join(name), b"x").unwrap();
+        }
+        let zip_path = root.join("export.zip");
+
+        let entries = collect_bundle_entries_in(&[root.clone()], Some(&["a".to_string()]));
+        let written = write_assets_zip(
+            zip_path.to_str().unwrap(),
+            &entries,
+            &AtomicBool::new(false),
+            &mut |_, _| {},
+        );
+        let mut names: Vec<String> = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap())
+            .unwrap()
+            .file_names()
+            .map(str::to_string)
+            .collect();
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(written.unwrap(), 2);
+        names.sort();
+        assert_eq!(names, ["image/a.meta.json", "image/a.png"]);
+    }
+
+    #[test]
+    fn test_bundle_entry_target_rejects_escapes() {
+        for name in ["../x", "/abs", "image/../../x", "a\\..\\b"] {
+            assert_eq!(bundle_entry_target(name), Err(None), "{}", name);
+        }
+        assert_eq!(
+            bundle_entry_target("image/id.png"),
+            Ok(("image".to_string(), "id.png".to_string()))
+        );
+        assert_eq!(bundle_entry_target("./image/id.png").unwrap().1, "id.png");
+        assert!(matches!(bundle_entry_target("notes/id.txt"), Err(Some(_))));
+        assert!(matches!(bundle_entry_target("image/bad name.png"), Err(Some(_))));
+        assert!(bundle_entry_target("image/id.meta.json").is_ok());
+    }
 }
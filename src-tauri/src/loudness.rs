@@ -0,0 +1,454 @@
+//! Audio loudness measurement
+//!
+//! This module provides:
+//! - An EBU R128 / ITU-R BS.1770 loudness meter (integrated loudness,
+//!   loudness range and 4x oversampled true peak)
+//! - Decoding a file's main audio stream through FFmpeg into the meter
+
+use serde::{Deserialize, Serialize};
+
+use ffmpeg_next as ffmpeg;
+use ffmpeg_next::format::{input, Sample};
+use ffmpeg_next::media::Type;
+use ffmpeg_next::util::channel_layout::ChannelLayout;
+use ffmpeg_next::util::frame::audio::Audio as AudioFrame;
+
+use crate::video_decoder::VideoError;
+
+// ============================================
+// TYPES
+// ============================================
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LoudnessResult {
+    /// Gated integrated loudness in LUFS (-70 for silence)
+    pub integrated_lufs: f64,
+    /// Maximum true peak in dBTP (-120 for silence)
+    pub true_peak_db: f64,
+    /// Loudness range in LU (EBU Tech 3342)
+    pub loudness_range: f64,
+}
+
+// ============================================
+// METER
+// ============================================
+
+/// All audio is resampled to this rate so the K-weighting coefficients are fixed
+const METER_RATE: u32 = 48_000;
+
+/// 100 ms at METER_RATE; gating blocks are built from these
+const SUB_BLOCK_SAMPLES: usize = 4_800;
+
+/// 400 ms momentary blocks (75% overlap) and 3 s short-term blocks
+const MOMENTARY_SUB_BLOCKS: usize = 4;
+const SHORT_TERM_SUB_BLOCKS: usize = 30;
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const TRUE_PEAK_FLOOR_DB: f64 = -120.0;
+
+const TRUE_PEAK_FACTOR: usize = 4;
+const TRUE_PEAK_TAPS: usize = 12;
+
+/// Transposed direct form II biquad
+#[derive(Clone, Copy, Debug)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    const fn new(b0: f64, b1: f64, b2: f64, a1: f64, a2: f64) -> Self {
+        Biquad { b0, b1, b2, a1, a2, z1: 0.0, z2: 0.0 }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+/// BS.1770 K-weighting at 48 kHz: high-shelf pre-filter, then RLB high-pass
+const K_WEIGHTING: [Biquad; 2] = [
+    Biquad::new(
+        1.535_124_859_586_97,
+        -2.691_696_189_406_38,
+        1.198_392_810_852_85,
+        -1.690_659_293_182_41,
+        0.732_480_774_215_85,
+    ),
+    Biquad::new(1.0, -2.0, 1.0, -1.990_047_454_833_98, 0.990_072_250_366_21),
+];
+
+/// Polyphase 4x interpolator used to estimate inter-sample (true) peaks
+struct TruePeak {
+    phases: [[f64; TRUE_PEAK_TAPS]; TRUE_PEAK_FACTOR],
+    history: Vec<[f64; TRUE_PEAK_TAPS]>,
+    peak: f64,
+}
+
+impl TruePeak {
+    fn new(channels: usize) -> Self {
+        // Hann-windowed sinc low-pass, split into one sub-filter per phase
+        let len = TRUE_PEAK_FACTOR * TRUE_PEAK_TAPS;
+        let center = (len - 1) as f64 / 2.0;
+        let mut phases = [[0.0; TRUE_PEAK_TAPS]; TRUE_PEAK_FACTOR];
+        for n in 0..len {
+            let t = (n as f64 - center) / TRUE_PEAK_FACTOR as f64;
+            let sinc = if t == 0.0 {
+                1.0
+            } else {
+                (std::f64::consts::PI * t).sin() / (std::f64::consts::PI * t)
+            };
+            let window = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * n as f64 / (len - 1) as f64).cos();
+            phases[n % TRUE_PEAK_FACTOR][n / TRUE_PEAK_FACTOR] = sinc * window;
+        }
+        // Unity DC gain per phase
+        for phase in phases.iter_mut() {
+            let sum: f64 = phase.iter().sum();
+            phase.iter_mut().for_each(|h| *h /= sum);
+        }
+
+        TruePeak {
+            phases,
+            history: vec![[0.0; TRUE_PEAK_TAPS]; channels],
+            peak: 0.0,
+        }
+    }
+
+    fn process(&mut self, channel: usize, x: f64) {
+        let history = &mut self.history[channel];
+        history.copy_within(0..TRUE_PEAK_TAPS - 1, 1);
+        history[0] = x;
+
+        self.peak = self.peak.max(x.abs());
+        for phase in &self.phases {
+            let y: f64 = phase.iter().zip(history.iter()).map(|(h, s)| h * s).sum();
+            self.peak = self.peak.max(y.abs());
+        }
+    }
+}
+
+/// Block loudness in LUFS from a weighted mean-square energy
+fn block_loudness(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Mean energy of each `len`-sub-block window, advancing one sub-block at a time
+fn window_energies(sub_blocks: &[f64], len: usize) -> Vec<f64> {
+    sub_blocks.windows(len).map(mean).collect()
+}
+
+/// Value at fraction `p` of a sorted slice (nearest rank)
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * p).round() as usize]
+}
+
+/// Streaming EBU R128 meter over interleaved 48 kHz samples
+pub(crate) struct LoudnessMeter {
+    channels: usize,
+    weights: Vec<f64>,
+    filters: Vec<[Biquad; 2]>,
+    true_peak: TruePeak,
+    /// Per-channel sum of squared K-weighted samples in the current sub-block
+    sums: Vec<f64>,
+    filled: usize,
+    /// Channel-weighted mean-square energy of each completed sub-block
+    sub_blocks: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub(crate) fn new(channels: usize) -> Self {
+        // BS.1770 weights: LFE is ignored and surrounds get +1.5 dB, assuming
+        // FFmpeg's default 5.1 order (FL FR FC LFE SL SR)
+        let weights = if channels == 6 {
+            vec![1.0, 1.0, 1.0, 0.0, 1.41, 1.41]
+        } else {
+            vec![1.0; channels]
+        };
+
+        LoudnessMeter {
+            channels,
+            weights,
+            filters: vec![K_WEIGHTING; channels],
+            true_peak: TruePeak::new(channels),
+            sums: vec![0.0; channels],
+            filled: 0,
+            sub_blocks: Vec::new(),
+        }
+    }
+
+    pub(crate) fn add_interleaved(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            for (channel, &sample) in frame.iter().enumerate() {
+                let x = sample as f64;
+                self.true_peak.process(channel, x);
+
+                let mut y = x;
+                for filter in self.filters[channel].iter_mut() {
+                    y = filter.process(y);
+                }
+                self.sums[channel] += y * y;
+            }
+
+            self.filled += 1;
+            if self.filled == SUB_BLOCK_SAMPLES {
+                let energy = self
+                    .sums
+                    .iter()
+                    .zip(&self.weights)
+                    .map(|(sum, weight)| weight * sum / SUB_BLOCK_SAMPLES as f64)
+                    .sum();
+                self.sub_blocks.push(energy);
+                self.sums.iter_mut().for_each(|sum| *sum = 0.0);
+                self.filled = 0;
+            }
+        }
+    }
+
+    /// Gated integrated loudness (None when everything is below the absolute gate)
+    fn integrated(&self) -> Option<f64> {
+        let blocks: Vec<f64> = window_energies(&self.sub_blocks, MOMENTARY_SUB_BLOCKS)
+            .into_iter()
+            .filter(|&e| block_loudness(e) > ABSOLUTE_GATE_LUFS)
+            .collect();
+        if blocks.is_empty() {
+            return None;
+        }
+
+        let relative_gate = block_loudness(mean(&blocks)) - 10.0;
+        let gated: Vec<f64> = blocks
+            .into_iter()
+            .filter(|&e| block_loudness(e) > relative_gate)
+            .collect();
+        if gated.is_empty() {
+            return None;
+        }
+
+        Some(block_loudness(mean(&gated)))
+    }
+
+    /// Loudness range: spread between the 10th and 95th percentile of gated
+    /// short-term loudness
+    fn loudness_range(&self) -> f64 {
+        let blocks: Vec<f64> = window_energies(&self.sub_blocks, SHORT_TERM_SUB_BLOCKS)
+            .into_iter()
+            .filter(|&e| block_loudness(e) > ABSOLUTE_GATE_LUFS)
+            .collect();
+        if blocks.is_empty() {
+            return 0.0;
+        }
+
+        let relative_gate = block_loudness(mean(&blocks)) - 20.0;
+        let mut loudness: Vec<f64> = blocks
+            .into_iter()
+            .map(block_loudness)
+            .filter(|&l| l > relative_gate)
+            .collect();
+        if loudness.is_empty() {
+            return 0.0;
+        }
+        loudness.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        percentile(&loudness, 0.95) - percentile(&loudness, 0.10)
+    }
+
+    pub(crate) fn finish(&self) -> LoudnessResult {
+        let true_peak_db = if self.true_peak.peak > 0.0 {
+            (20.0 * self.true_peak.peak.log10()).max(TRUE_PEAK_FLOOR_DB)
+        } else {
+            TRUE_PEAK_FLOOR_DB
+        };
+
+        LoudnessResult {
+            integrated_lufs: self.integrated().unwrap_or(ABSOLUTE_GATE_LUFS),
+            true_peak_db,
+            loudness_range: self.loudness_range(),
+        }
+    }
+}
+
+// ============================================
+// DECODING
+// ============================================
+
+/// Interleaved f32 samples from a packed-float audio frame
+fn packed_f32_samples(frame: &AudioFrame, channels: usize) -> Vec<f32> {
+    let len = frame.samples() * channels * 4;
+    frame.data(0)[..len]
+        .chunks_exact(4)
+        .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// Measure integrated loudness, true peak and loudness range of a file's
+/// main audio stream. Analysis stops at the container duration.
+pub fn measure_loudness(path: &str) -> Result<LoudnessResult, VideoError> {
+    let mut input_ctx = input(&path)?;
+
+    let audio_stream = input_ctx
+        .streams()
+        .best(Type::Audio)
+        .ok_or_else(|| VideoError {
+            message: "No audio stream found".to_string(),
+            code: "NO_AUDIO_STREAM".to_string(),
+        })?;
+
+    let audio_stream_index = audio_stream.index();
+    let codec_ctx = ffmpeg::codec::context::Context::from_parameters(audio_stream.parameters())?;
+    let mut decoder = codec_ctx.decoder().audio()?;
+
+    // Some demuxers only fill in the layout, so fall back to its count
+    let mut layout = decoder.channel_layout();
+    let channels = match decoder.channels() as usize {
+        0 => layout.channels() as usize,
+        n => n,
+    };
+    if channels == 0 {
+        return Err(VideoError {
+            message: "Audio stream has no channels".to_string(),
+            code: "NO_AUDIO_CHANNELS".to_string(),
+        });
+    }
+    if layout.is_empty() {
+        layout = ChannelLayout::default(channels as i32);
+    }
+
+    let mut resampler = ffmpeg::software::resampling::Context::get(
+        decoder.format(),
+        layout,
+        decoder.rate(),
+        Sample::F32(ffmpeg::format::sample::Type::Packed),
+        layout,
+        METER_RATE,
+    )?;
+
+    // Some files carry junk past their stated duration; don't analyze it
+    let max_samples = (input_ctx.duration() > 0).then(|| {
+        (input_ctx.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64 * METER_RATE as f64) as usize
+    });
+
+    let mut meter = LoudnessMeter::new(channels);
+    let mut analyzed = 0usize;
+
+    // `None` drains the samples the resampler still buffers at end of stream
+    let mut feed = |frame: Option<&mut AudioFrame>| -> Result<bool, VideoError> {
+        let mut resampled = AudioFrame::empty();
+        match frame {
+            Some(frame) => {
+                if frame.channel_layout().is_empty() {
+                    frame.set_channel_layout(layout);
+                }
+                resampler.run(frame, &mut resampled)?;
+            }
+            None => {
+                resampler.flush(&mut resampled)?;
+            }
+        }
+        if resampled.samples() == 0 {
+            return Ok(false);
+        }
+
+        let mut samples = packed_f32_samples(&resampled, channels);
+        if let Some(max) = max_samples {
+            let remaining = max.saturating_sub(analyzed) * channels;
+            samples.truncate(remaining);
+        }
+        analyzed += samples.len() / channels;
+        meter.add_interleaved(&samples);
+
+        Ok(max_samples.is_some_and(|max| analyzed >= max))
+    };
+
+    let mut decoded = AudioFrame::empty();
+    let mut done = false;
+    for (stream, packet) in input_ctx.packets() {
+        if stream.index() != audio_stream_index {
+            continue;
+        }
+
+        decoder.send_packet(&packet)?;
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            if feed(Some(&mut decoded))? {
+                done = true;
+                break;
+            }
+        }
+        if done {
+            break;
+        }
+    }
+
+    if !done {
+        decoder.send_eof()?;
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            if feed(Some(&mut decoded))? {
+                done = true;
+                break;
+            }
+        }
+    }
+
+    if !done {
+        feed(None)?;
+    }
+
+    Ok(meter.finish())
+}
+
+/// Tauri command to measure loudness for normalization suggestions
+#[tauri::command]
+pub async fn cmd_measure_loudness(path: String) -> Result<LoudnessResult, VideoError> {
+    tokio::task::spawn_blocking(move || measure_loudness(&path)).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Interleaved stereo sine at `freq` Hz with peak amplitude `dbfs`
+    fn stereo_sine(freq: f64, dbfs: f64, secs: f64) -> Vec<f32> {
+        let amplitude = 10f64.powf(dbfs / 20.0);
+        let count = (secs * METER_RATE as f64) as usize;
+        (0..count)
+            .flat_map(|i| {
+                let s = amplitude
+                    * (2.0 * std::f64::consts::PI * freq * i as f64 / METER_RATE as f64).sin();
+                [s as f32, s as f32]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ebu_3341_stereo_sine_reads_minus_23() {
+        // EBU Tech 3341 case 1: 1 kHz stereo sine at -23 dBFS reads -23 LUFS
+        let mut meter = LoudnessMeter::new(2);
+        meter.add_interleaved(&stereo_sine(1000.0, -23.0, 20.0));
+        let result = meter.finish();
+
+        assert!((result.integrated_lufs + 23.0).abs() < 0.1, "{:?}", result);
+        assert!((result.true_peak_db + 23.0).abs() < 0.5, "{:?}", result);
+        assert!(result.loudness_range < 0.5, "{:?}", result);
+    }
+
+    #[test]
+    fn test_silence_reports_floors() {
+        let mut meter = LoudnessMeter::new(2);
+        meter.add_interleaved(&vec![0.0; METER_RATE as usize * 2 * 5]);
+        let result = meter.finish();
+
+        assert_eq!(result.integrated_lufs, ABSOLUTE_GATE_LUFS);
+        assert_eq!(result.true_peak_db, TRUE_PEAK_FLOOR_DB);
+        assert_eq!(result.loudness_range, 0.0);
+    }
+}
@@ -0,0 +1,197 @@
+//! 3D LUT support
+//!
+//! This module provides:
+//! - Parsing Adobe/Resolve `.cube` 3D LUT files
+//! - Trilinear lookup matching ffmpeg's `lut3d` filter (`interp=trilinear`),
+//!   which melt uses at render time, so previews match the export
+
+use std::fs;
+use std::path::Path;
+
+/// Largest grid accepted; real-world LUTs top out at 65 or 129
+const MAX_LUT_SIZE: usize = 256;
+
+/// A parsed 3D LUT: `size`^3 RGB entries, red varying fastest
+#[derive(Debug, Clone)]
+pub struct Lut3d {
+    size: usize,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    table: Vec<[f32; 3]>,
+}
+
+fn parse_triplet(keyword: &str, rest: &[&str], line_no: usize) -> Result<[f32; 3], String> {
+    if rest.len() != 3 {
+        return Err(format!("line {}: {} expects 3 values", line_no, keyword));
+    }
+    let mut out = [0.0; 3];
+    for (slot, value) in out.iter_mut().zip(rest) {
+        *slot = value
+            .parse()
+            .map_err(|_| format!("line {}: invalid {} value '{}'", line_no, keyword, value))?;
+    }
+    Ok(out)
+}
+
+impl Lut3d {
+    /// Parse the text of a `.cube` file
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut size: Option<usize> = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = Vec::new();
+
+        for (index, raw) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[0] {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => return Err("1D LUTs are not supported".to_string()),
+                "LUT_3D_SIZE" => {
+                    let n: usize = fields
+                        .get(1)
+                        .and_then(|v| v.parse().ok())
+                        .ok_or_else(|| format!("line {}: invalid LUT_3D_SIZE", line_no))?;
+                    if !(2..=MAX_LUT_SIZE).contains(&n) {
+                        return Err(format!(
+                            "LUT_3D_SIZE must be between 2 and {} (got {})",
+                            MAX_LUT_SIZE, n
+                        ));
+                    }
+                    size = Some(n);
+                }
+                "DOMAIN_MIN" => domain_min = parse_triplet("DOMAIN_MIN", &fields[1..], line_no)?,
+                "DOMAIN_MAX" => domain_max = parse_triplet("DOMAIN_MAX", &fields[1..], line_no)?,
+                keyword if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                    // Vendor keywords (LUT_IN_VIDEO_RANGE etc.) don't affect the table
+                }
+                _ => table.push(parse_triplet("entry", &fields, line_no)?),
+            }
+        }
+
+        let size = size.ok_or("missing LUT_3D_SIZE")?;
+        let expected = size * size * size;
+        if table.len() != expected {
+            return Err(format!(
+                "expected {} entries for a {}^3 LUT, found {}",
+                expected,
+                size,
+                table.len()
+            ));
+        }
+        if (0..3).any(|c| domain_max[c] <= domain_min[c]) {
+            return Err("DOMAIN_MAX must be greater than DOMAIN_MIN".to_string());
+        }
+
+        Ok(Self {
+            size,
+            domain_min,
+            domain_max,
+            table,
+        })
+    }
+
+    /// Read and parse a `.cube` file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read LUT '{}': {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("Invalid LUT '{}': {}", path.display(), e))
+    }
+
+    fn entry(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.table[r + g * self.size + b * self.size * self.size]
+    }
+
+    /// Map one RGB value (0.0-1.0) through the LUT with trilinear interpolation
+    pub fn lookup(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let max_index = (self.size - 1) as f32;
+        let mut lo = [0usize; 3];
+        let mut hi = [0usize; 3];
+        let mut frac = [0.0f32; 3];
+
+        for c in 0..3 {
+            let scaled = ((rgb[c] - self.domain_min[c]) / (self.domain_max[c] - self.domain_min[c]))
+                .clamp(0.0, 1.0)
+                * max_index;
+            lo[c] = scaled.floor() as usize;
+            hi[c] = (lo[c] + 1).min(self.size - 1);
+            frac[c] = scaled - lo[c] as f32;
+        }
+
+        let lerp = |a: [f32; 3], b: [f32; 3], t: f32| {
+            [
+                a[0] + (b[0] - a[0]) * t,
+                a[1] + (b[1] - a[1]) * t,
+                a[2] + (b[2] - a[2]) * t,
+            ]
+        };
+
+        let c00 = lerp(self.entry(lo[0], lo[1], lo[2]), self.entry(hi[0], lo[1], lo[2]), frac[0]);
+        let c10 = lerp(self.entry(lo[0], hi[1], lo[2]), self.entry(hi[0], hi[1], lo[2]), frac[0]);
+        let c01 = lerp(self.entry(lo[0], lo[1], hi[2]), self.entry(hi[0], lo[1], hi[2]), frac[0]);
+        let c11 = lerp(self.entry(lo[0], hi[1], hi[2]), self.entry(hi[0], hi[1], hi[2]), frac[0]);
+
+        lerp(lerp(c00, c10, frac[1]), lerp(c01, c11, frac[1]), frac[2])
+    }
+
+    /// Apply the LUT in place to packed RGB24 rows
+    pub fn apply_rgb24(&self, data: &mut [u8], width: usize, height: usize, stride: usize) {
+        for y in 0..height {
+            let row = &mut data[y * stride..y * stride + width * 3];
+            for px in row.chunks_exact_mut(3) {
+                let out = self.lookup([
+                    px[0] as f32 / 255.0,
+                    px[1] as f32 / 255.0,
+                    px[2] as f32 / 255.0,
+                ]);
+                for (dst, value) in px.iter_mut().zip(out) {
+                    *dst = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity_cube(size: usize) -> String {
+        let mut text = format!("TITLE \"identity\"\nLUT_3D_SIZE {}\n", size);
+        let max = (size - 1) as f32;
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    text.push_str(&format!("{} {} {}\n", r as f32 / max, g as f32 / max, b as f32 / max));
+                }
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn test_identity_lut_is_transparent() {
+        let lut = Lut3d::parse(&identity_cube(5)).unwrap();
+        let out = lut.lookup([0.1, 0.5, 0.93]);
+        for (a, b) in out.iter().zip([0.1, 0.5, 0.93]) {
+            assert!((a - b).abs() < 1e-5);
+        }
+
+        let mut pixels = vec![12, 200, 77, 255, 0, 128];
+        lut.apply_rgb24(&mut pixels, 2, 1, 6);
+        assert_eq!(pixels, vec![12, 200, 77, 255, 0, 128]);
+    }
+
+    #[test]
+    fn test_malformed_lut_is_rejected() {
+        assert!(Lut3d::parse("0 0 0\n1 1 1\n").unwrap_err().contains("LUT_3D_SIZE"));
+        assert!(Lut3d::parse("LUT_3D_SIZE 2\n0 0 0\n").unwrap_err().contains("expected 8"));
+        assert!(Lut3d::parse("LUT_3D_SIZE 2\n0 0\n").unwrap_err().contains("line 2"));
+        assert!(Lut3d::parse("LUT_1D_SIZE 1024\n").is_err());
+    }
+}
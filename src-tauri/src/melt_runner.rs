@@ -1,18 +1,32 @@
 //! MLT/melt integration for Tauri
 //!
 //! This module provides Tauri commands for:
+//! - Locating melt (user-configured, bundled with the app, or system)
 //! - Checking melt availability
 //! - Running melt for rendering
+//! - Queueing renders with bounded concurrency
 //! - Tracking render progress
+//! - Verifying rendered output is playable
+//! - Generating low-res editing proxies
+//! - Extracting audio tracks
 //! - Managing temp files
 
+use quick_xml::events::Event;
+use quick_xml::Reader;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
+use std::io::{BufReader, Read};
 use std::path::PathBuf;
-use std::process::Command;
-use std::sync::Mutex;
-use tauri::State;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::lut::Lut3d;
+use crate::mlt_builder::{escape_xml, Timeline};
+use crate::mlt_parser;
+use crate::video_decoder;
 
 // ============================================
 // TYPES
@@ -26,8 +40,186 @@ pub struct MeltCheckResult {
     pub error: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Parsed `major.minor.patch` from `melt --version`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MeltVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+/// What the resolved melt can do, from `melt -query`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MeltCapabilities {
+    pub path: String,
+    pub version_string: Option<String>,
+    pub version: Option<MeltVersion>,
+    pub consumers: Vec<String>,
+    pub filters: Vec<String>,
+    pub producers: Vec<String>,
+    pub transitions: Vec<String>,
+}
+
+/// Container/codec combination for the rendered output
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderFormat {
+    /// H.264 in an MP4 container (default web/general delivery)
+    Mp4H264,
+    /// VP9 + Opus in a WebM container
+    WebmVp9,
+    /// Apple ProRes 422 HQ in a QuickTime container (editing round-trips)
+    ProRes,
+    /// Animated GIF (no audio)
+    GifAnimated,
+    /// H.264 + AAC as an HLS playlist with MPEG-TS segments, written into
+    /// the output directory for web streaming
+    Hls { segment_secs: u32 },
+    /// H.264 + AAC in MPEG-TS, which needs no seeking, for FIFO/pipe output
+    MpegTs,
+}
+
+impl RenderFormat {
+    fn container(&self) -> &'static str {
+        match self {
+            RenderFormat::Mp4H264 => "mp4",
+            RenderFormat::WebmVp9 => "webm",
+            RenderFormat::ProRes => "mov",
+            RenderFormat::GifAnimated => "gif",
+            RenderFormat::Hls { .. } => "hls",
+            RenderFormat::MpegTs => "mpegts",
+        }
+    }
+
+    fn video_codec(&self) -> &'static str {
+        match self {
+            RenderFormat::Mp4H264 => "libx264",
+            RenderFormat::WebmVp9 => "libvpx-vp9",
+            RenderFormat::ProRes => "prores_ks",
+            RenderFormat::GifAnimated => "gif",
+            RenderFormat::Hls { .. } => "libx264",
+            RenderFormat::MpegTs => "libx264",
+        }
+    }
+
+    fn audio_codec(&self) -> Option<&'static str> {
+        match self {
+            RenderFormat::Mp4H264 => Some("aac"),
+            RenderFormat::WebmVp9 => Some("libopus"),
+            RenderFormat::ProRes => Some("pcm_s16le"),
+            RenderFormat::GifAnimated => None,
+            RenderFormat::Hls { .. } => Some("aac"),
+            RenderFormat::MpegTs => Some("aac"),
+        }
+    }
+
+    fn pix_fmt(&self) -> &'static str {
+        match self {
+            RenderFormat::Mp4H264 => "yuv420p",
+            RenderFormat::WebmVp9 => "yuv420p",
+            RenderFormat::ProRes => "yuv422p10le",
+            RenderFormat::GifAnimated => "rgb8",
+            RenderFormat::Hls { .. } => "yuv420p",
+            RenderFormat::MpegTs => "yuv420p",
+        }
+    }
+
+    fn supports_crf(&self) -> bool {
+        matches!(
+            self,
+            RenderFormat::Mp4H264
+                | RenderFormat::WebmVp9
+                | RenderFormat::Hls { .. }
+                | RenderFormat::MpegTs
+        )
+    }
+}
+
+/// melt profile controlling output geometry and frame rate
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderProfile {
+    /// A profile shipped with MLT, e.g. "hdv_720_25p" or "atsc_1080p_30"
+    Named(String),
+    /// Explicit geometry, written to a temporary profile file
+    Custom {
+        width: u32,
+        height: u32,
+        frame_rate_num: u32,
+        frame_rate_den: u32,
+        display_aspect_num: Option<u32>,
+        display_aspect_den: Option<u32>,
+    },
+}
+
+/// Frame corner (or centre) a watermark or overlay is pinned to
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Watermark {
+    pub image_path: String,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    /// 0.0 (invisible) to 1.0 (opaque), defaults to 1.0
+    pub opacity: Option<f64>,
+    /// Logo width as a fraction of the frame width, defaults to 0.15
+    pub scale: Option<f64>,
+}
+
+/// Captions burned into the output from an SRT or ASS/SSA file
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SubtitleOptions {
+    pub path: String,
+    /// Overrides for the file's own styling (ASS) or libass defaults (SRT)
+    pub font_name: Option<String>,
+    pub font_size: Option<u32>,
+    /// "#RRGGBB"
+    pub color: Option<String>,
+    /// "#RRGGBB"
+    pub outline_color: Option<String>,
+    /// Distance from the bottom edge in script pixels
+    pub margin_v: Option<u32>,
+}
+
+/// What happens to the project's audio in the rendered output
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioAction {
+    #[default]
+    Keep,
+    /// Write no audio stream at all
+    Mute,
+    /// Silence the project and use this file as the only audio
+    ReplaceWith { path: String },
+}
+
+/// Running timecode burned into review copies
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TimecodeOptions {
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    /// Text height in pixels, defaults to 36
+    pub font_size: Option<u32>,
+    /// Added to the displayed time, e.g. 3600 to start at 01:00:00:00
+    pub start_offset_secs: Option<f64>,
+    /// TrueType/OpenType font file; defaults to a common system sans font
+    pub font_path: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct RenderOptions {
+    /// Passed to melt as `-profile` for deterministic output geometry
+    pub profile: Option<RenderProfile>,
+    pub output_format: Option<RenderFormat>,
     pub video_codec: Option<String>,
     pub audio_codec: Option<String>,
     pub audio_bitrate: Option<String>,
@@ -35,13 +227,71 @@ pub struct RenderOptions {
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub frame_rate: Option<u32>,
+    /// First project frame to render (inclusive)
+    pub in_frame: Option<u64>,
+    /// Last project frame to render (inclusive)
+    pub out_frame: Option<u64>,
+    /// x264/x265 speed preset (ultrafast..veryslow), defaults to "medium"
+    pub preset: Option<String>,
+    /// Run melt twice (pass=1 / pass=2) sharing a passlog in the temp dir
+    #[serde(default)]
+    pub two_pass: bool,
+    /// Kill the render if it runs longer than this
+    pub timeout_secs: Option<u64>,
+    /// Treat the render as hung if melt reports no progress for this long
+    pub stall_timeout_secs: Option<u64>,
+    /// Probe the output after melt exits and fail the render if it isn't playable
+    #[serde(default)]
+    pub verify_output: bool,
+    /// Extra consumer properties appended verbatim (e.g. "g=48", "movflags=+faststart")
+    #[serde(default)]
+    pub extra_consumer_args: Vec<String>,
+    /// Extra producer properties appended verbatim after the project XML
+    #[serde(default)]
+    pub extra_producer_args: Vec<String>,
+    /// Allow extra args starting with '-' (melt flags); off by default since
+    /// they can break the command structure
+    #[serde(default)]
+    pub allow_extra_flags: bool,
+    /// Swap registered proxy files back to their originals before rendering
+    #[serde(default)]
+    pub use_originals: bool,
+    /// `.cube` 3D LUT applied to the whole output (before the watermark)
+    pub lut_path: Option<String>,
+    /// Logo burned over the whole output
+    pub watermark: Option<Watermark>,
+    /// Captions burned into the output (after the LUT, under the watermark)
+    pub subtitles: Option<SubtitleOptions>,
+    /// Timecode burned over the output for review copies (on top of everything)
+    pub timecode_overlay: Option<TimecodeOptions>,
+    /// Export only the mixed audio; format and codec follow the output extension
+    #[serde(default)]
+    pub audio_only: bool,
+    /// Keep (default), drop or replace the project's audio
+    pub audio_action: Option<AudioAction>,
+    /// Fail before starting melt when producer media is missing (see `preflight_mlt`)
+    #[serde(default)]
+    pub check_media: bool,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct RenderResult {
     pub success: bool,
     pub error: Option<String>,
+    /// The rendered file, or the playlist for HLS output
     pub output_path: Option<String>,
+    /// Number of media segments, for HLS output
+    #[serde(default)]
+    pub segment_count: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RenderVerification {
+    pub valid: bool,
+    pub error: Option<String>,
+    pub duration_secs: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -51,28 +301,156 @@ pub struct MeltRawResult {
     pub exit_code: i32,
 }
 
+/// One line of output from `run_melt_raw_streaming`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MeltRawOutputEvent {
+    pub job_id: String,
+    /// "stdout" or "stderr"
+    pub stream: String,
+    pub line: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MeltRawStreamResult {
+    /// None when the process was killed (cancelled or by a signal)
+    pub exit_code: Option<i32>,
+    pub cancelled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    Error,
+    Warning,
+}
+
+/// One finished export, as stored in render_history.json
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RenderHistoryEntry {
+    pub job_id: String,
+    pub output_path: String,
+    pub options: RenderOptions,
+    /// Unix milliseconds
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub duration_secs: f64,
+    pub success: bool,
+    pub error: Option<String>,
+    pub output_size_bytes: Option<u64>,
+}
+
+/// A producer whose media file can't be used
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MissingResource {
+    pub producer_id: String,
+    pub resource: String,
+    /// "missing" or "unreadable"
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PreflightResult {
+    pub ok: bool,
+    pub missing: Vec<MissingResource>,
+}
+
+/// One problem found in an MLT document or in RenderOptions
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ValidationIssue {
+    pub severity: IssueSeverity,
+    pub message: String,
+    /// 1-based position, known for XML syntax errors
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Media file or service melt couldn't load
+    pub resource: Option<String>,
+    /// RenderOptions field at fault, e.g. "crf" or "watermark.opacity"
+    #[serde(default)]
+    pub field: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ValidateResult {
     pub valid: bool,
+    /// First error message (kept for callers that only show one line)
     pub error: Option<String>,
+    #[serde(default)]
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidateResult {
+    fn from_issues(issues: Vec<ValidationIssue>) -> Self {
+        let error = issues
+            .iter()
+            .find(|i| i.severity == IssueSeverity::Error)
+            .map(|i| i.message.clone());
+        Self {
+            valid: error.is_none(),
+            error,
+            issues,
+        }
+    }
 }
 
 // ============================================
 // STATE
 // ============================================
 
+/// A render waiting for a free slot in the queue
+pub struct QueuedRender {
+    pub job_id: String,
+    pub mlt_xml: String,
+    pub output_path: String,
+    pub options: RenderOptions,
+}
+
 pub struct MeltState {
     pub active_jobs: Mutex<HashMap<String, bool>>, // job_id -> is_cancelled
+    pub children: Mutex<HashMap<String, Child>>,   // job_id -> running melt process
+    pub queue: Mutex<VecDeque<QueuedRender>>,
+    /// Queue-started renders counted against `max_concurrent`
+    pub running_renders: Mutex<HashSet<String>>,
+    pub max_concurrent: Mutex<usize>,
+    /// User-configured melt binary, preferred over the default locations
+    pub melt_path: Mutex<Option<String>>,
+    /// Tauri resource directory, searched for a bundled melt
+    pub resource_dir: Mutex<Option<PathBuf>>,
+    /// Cached `melt -query` results (querying spawns several processes)
+    pub capabilities: Mutex<Option<MeltCapabilities>>,
+    /// Last `check_melt` result and when it was probed
+    pub melt_check: Mutex<Option<(Instant, MeltCheckResult)>>,
+    /// Serializes read-modify-write of render_history.json
+    pub history_lock: Mutex<()>,
+    /// Serializes read-modify-write of proxies.json
+    pub proxy_lock: Mutex<()>,
 }
 
 impl MeltState {
     pub fn new() -> Self {
         Self {
             active_jobs: Mutex::new(HashMap::new()),
+            children: Mutex::new(HashMap::new()),
+            queue: Mutex::new(VecDeque::new()),
+            running_renders: Mutex::new(HashSet::new()),
+            max_concurrent: Mutex::new(1),
+            melt_path: Mutex::new(None),
+            resource_dir: Mutex::new(None),
+            capabilities: Mutex::new(None),
+            melt_check: Mutex::new(None),
+            history_lock: Mutex::new(()),
+            proxy_lock: Mutex::new(()),
         }
     }
 }
 
+/// Record where Tauri unpacked the app's resources (called from setup)
+pub fn set_resource_dir(state: &MeltState, resource_dir: PathBuf) {
+    if let Ok(mut dir) = state.resource_dir.lock() {
+        *dir = Some(resource_dir);
+    }
+    invalidate_melt_check(state);
+}
+
 // ============================================
 // TEMP DIRECTORY
 // ============================================
@@ -105,8 +483,84 @@ fn get_renders_dir() -> Result<PathBuf, String> {
 // MELT AVAILABILITY CHECK
 // ============================================
 
-/// Find the melt binary on the system
-fn find_melt() -> Option<String> {
+/// Name of the melt binary shipped in the resource directory
+const BUNDLED_MELT_BINARY: &str = if cfg!(windows) { "melt.exe" } else { "melt" };
+
+/// Layout of a bundled MLT install, relative to the melt binary's directory:
+/// modules in `mlt/lib`, data (including profiles) in `mlt/share`
+const BUNDLED_MLT_REPOSITORY: &str = "mlt/lib";
+const BUNDLED_MLT_DATA: &str = "mlt/share";
+
+/// Create a melt `Command`. A melt with a bundled MLT tree next to it gets
+/// MLT_REPOSITORY / MLT_DATA / MLT_PROFILES_PATH pointed at that tree so it
+/// never picks up a mismatched system install.
+fn melt_command(melt_path: &str) -> Command {
+    let mut cmd = Command::new(melt_path);
+
+    if let Some(bin_dir) = std::path::Path::new(melt_path).parent() {
+        let repository = bin_dir.join(BUNDLED_MLT_REPOSITORY);
+        let data = bin_dir.join(BUNDLED_MLT_DATA);
+        if repository.is_dir() && data.is_dir() {
+            cmd.env("MLT_REPOSITORY", &repository);
+            cmd.env("MLT_PROFILES_PATH", data.join("profiles"));
+            cmd.env("MLT_DATA", data);
+        }
+    }
+
+    cmd
+}
+
+/// Run `melt --version`, returning the version text if the binary works
+fn melt_version(path: &str) -> Option<String> {
+    let output = melt_command(path).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Find the melt binary, trusting a fresh `check_melt` result that found
+/// one so renders don't re-spawn `melt --version` probes. A cached binary
+/// that has since been removed falls back to probing.
+fn find_melt(state: &MeltState) -> Option<String> {
+    if let Some(check) = cached_melt_check(state) {
+        if check.available {
+            // A bare name is resolved through PATH and can't be checked here
+            match check.path {
+                Some(path) if path == "melt" || std::path::Path::new(&path).is_file() => {
+                    return Some(path);
+                }
+                _ => invalidate_melt_check(state),
+            }
+        }
+    }
+    locate_melt(
+        state.melt_path.lock().ok().and_then(|p| p.clone()),
+        state.resource_dir.lock().ok().and_then(|d| d.clone()),
+    )
+}
+
+/// Probe for melt: the user-configured path, then a bundled melt, then the
+/// usual system locations. Spawns processes, so keep it off the main thread.
+fn locate_melt(configured: Option<String>, resource_dir: Option<PathBuf>) -> Option<String> {
+    if let Some(path) = configured {
+        // A configured binary that has since disappeared falls back to probing
+        if std::path::Path::new(&path).is_file() {
+            return Some(path);
+        }
+    }
+
+    // Then a melt shipped with the app
+    if let Some(resource_dir) = resource_dir {
+        let bundled = resource_dir.join(BUNDLED_MELT_BINARY);
+        if bundled.is_file() {
+            let bundled = bundled.to_string_lossy().to_string();
+            if melt_version(&bundled).is_some() {
+                return Some(bundled);
+            }
+        }
+    }
+
     // Try common paths
     let paths = [
         "melt",
@@ -116,10 +570,8 @@ fn find_melt() -> Option<String> {
     ];
 
     for path in &paths {
-        if let Ok(output) = Command::new(path).arg("--version").output() {
-            if output.status.success() {
-                return Some(path.to_string());
-            }
+        if melt_version(path).is_some() {
+            return Some(path.to_string());
         }
     }
 
@@ -136,20 +588,28 @@ fn find_melt() -> Option<String> {
     None
 }
 
-// ============================================
-// TAURI COMMANDS
-// ============================================
+/// How long a `check_melt` result is reused before probing again
+const MELT_CHECK_TTL: Duration = Duration::from_secs(5 * 60);
 
-#[tauri::command]
-pub fn check_melt() -> MeltCheckResult {
-    match find_melt() {
+/// The last `check_melt` result, if it is younger than the TTL
+fn cached_melt_check(state: &MeltState) -> Option<MeltCheckResult> {
+    let cache = state.melt_check.lock().ok()?;
+    let (checked_at, result) = cache.as_ref()?;
+    (checked_at.elapsed() < MELT_CHECK_TTL).then(|| result.clone())
+}
+
+/// Forget the cached `check_melt` result (the search inputs changed)
+fn invalidate_melt_check(state: &MeltState) {
+    if let Ok(mut cache) = state.melt_check.lock() {
+        *cache = None;
+    }
+}
+
+/// Probe for melt and its version (blocking)
+fn probe_melt(configured: Option<String>, resource_dir: Option<PathBuf>) -> MeltCheckResult {
+    match locate_melt(configured, resource_dir) {
         Some(path) => {
-            // Get version
-            let version = Command::new(&path)
-                .arg("--version")
-                .output()
-                .ok()
-                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+            let version = melt_version(&path);
 
             MeltCheckResult {
                 available: true,
@@ -167,198 +627,3899 @@ pub fn check_melt() -> MeltCheckResult {
     }
 }
 
-#[tauri::command]
-pub async fn run_melt_render(
-    mlt_xml: String,
-    output_path: String,
-    options: RenderOptions,
-    job_id: String,
-    state: State<'_, MeltState>,
-) -> Result<RenderResult, String> {
-    // Find melt
-    let melt_path = find_melt().ok_or("melt not found on system")?;
+/// Run `melt -query <kind>` and return the listed names
+fn query_melt_list(melt_path: &str, kind: &str) -> Option<Vec<String>> {
+    let output = melt_command(melt_path).arg("-query").arg(kind).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
 
-    // Create temp XML file
-    let temp_dir = get_mlt_temp_dir_path()?;
-    let xml_path = temp_dir.join(format!("{}.mlt", job_id));
+    // Output is YAML-ish: a header followed by "  - name" lines
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let names = stdout
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("- "))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
 
-    fs::write(&xml_path, &mlt_xml)
-        .map_err(|e| format!("Failed to write MLT XML: {}", e))?;
+    Some(names)
+}
 
-    // Register job
-    {
-        let mut jobs = state.active_jobs.lock().map_err(|e| e.to_string())?;
-        jobs.insert(job_id.clone(), false);
+/// Pull the first `X.Y.Z` out of `melt --version` output ("melt 7.22.0")
+fn parse_melt_version(text: &str) -> Option<MeltVersion> {
+    // Leading digits only, so suffixes like "26-dirty" still parse
+    let number = |part: &str| -> Option<u32> {
+        let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse().ok()
+    };
+
+    text.split_whitespace().find_map(|token| {
+        let mut parts = token.trim_start_matches('v').split('.');
+        let major = number(parts.next()?)?;
+        let minor = number(parts.next()?)?;
+        let patch = parts.next().and_then(number).unwrap_or(0);
+        Some(MeltVersion { major, minor, patch })
+    })
+}
+
+/// Query versions and service lists from a melt binary
+fn query_melt_capabilities(melt_path: &str) -> MeltCapabilities {
+    let version_string = melt_version(melt_path);
+    let list = |kind: &str| query_melt_list(melt_path, kind).unwrap_or_default();
+
+    MeltCapabilities {
+        path: melt_path.to_string(),
+        version: version_string.as_deref().and_then(parse_melt_version),
+        version_string,
+        consumers: list("consumers"),
+        filters: list("filters"),
+        producers: list("producers"),
+        transitions: list("transitions"),
     }
+}
+
+// ============================================
+// PROFILE ARGUMENTS
+// ============================================
+
+/// Resolve the `-profile` arguments for a render.
+///
+/// Named profiles are checked against `melt -query profiles` when melt can
+/// list them. Custom profiles are written to `<temp>/<job_id>.profile`.
+fn build_profile_args(
+    melt_path: &str,
+    options: &RenderOptions,
+    temp_dir: &PathBuf,
+    job_id: &str,
+) -> Result<Vec<String>, String> {
+    match &options.profile {
+        None => Ok(Vec::new()),
+        Some(RenderProfile::Named(name)) => {
+            if let Some(known) = query_melt_list(melt_path, "profiles") {
+                if !known.is_empty() && !known.contains(name) {
+                    return Err(format!("Unknown melt profile '{}'", name));
+                }
+            }
+            Ok(vec!["-profile".to_string(), name.clone()])
+        }
+        Some(RenderProfile::Custom {
+            width,
+            height,
+            frame_rate_num,
+            frame_rate_den,
+            display_aspect_num,
+            display_aspect_den,
+        }) => {
+            if *width == 0 || *height == 0 || *frame_rate_num == 0 || *frame_rate_den == 0 {
+                return Err("Custom profile dimensions and frame rate must be positive".to_string());
+            }
+
+            let contents = format!(
+                "description=Custom {}x{}\nframe_rate_num={}\nframe_rate_den={}\nwidth={}\nheight={}\nprogressive=1\nsample_aspect_num=1\nsample_aspect_den=1\ndisplay_aspect_num={}\ndisplay_aspect_den={}\ncolorspace=709\n",
+                width,
+                height,
+                frame_rate_num,
+                frame_rate_den,
+                width,
+                height,
+                display_aspect_num.unwrap_or(*width),
+                display_aspect_den.unwrap_or(*height),
+            );
+
+            let profile_path = temp_dir.join(format!("{}.profile", job_id));
+            fs::write(&profile_path, contents)
+                .map_err(|e| format!("Failed to write melt profile: {}", e))?;
+
+            Ok(vec!["-profile".to_string(), profile_path.to_string_lossy().to_string()])
+        }
+    }
+}
+
+// ============================================
+// CONSUMER ARGUMENTS
+// ============================================
+
+/// Speed presets accepted by libx264/libx265
+const X264_PRESETS: [&str; 9] = [
+    "ultrafast",
+    "superfast",
+    "veryfast",
+    "faster",
+    "fast",
+    "medium",
+    "slow",
+    "slower",
+    "veryslow",
+];
+
+/// Whether `vcodec` is libx264/libx265 (melt's default when unset)
+fn is_x26x(vcodec: Option<&str>) -> bool {
+    vcodec.is_none_or(|c| c == "libx264" || c == "libx265")
+}
+
+fn valid_watermark_opacity(opacity: f64) -> bool {
+    (0.0..=1.0).contains(&opacity)
+}
+
+fn valid_watermark_scale(scale: f64) -> bool {
+    scale > 0.0 && scale <= 1.0
+}
 
-    // Build melt command
-    let mut cmd = Command::new(&melt_path);
-    cmd.arg(xml_path.to_string_lossy().to_string());
+/// Translate RenderOptions into avformat consumer properties.
+///
+/// These must come after `-consumer` on the melt command line, otherwise
+/// melt applies them to the producer instead.
+fn build_consumer_args(options: &RenderOptions) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+
+    if let Some(format) = options.output_format {
+        if options.crf.is_some() && !format.supports_crf() {
+            return Err(format!("crf is not supported for {:?} output", format));
+        }
+        if format == RenderFormat::GifAnimated
+            && (options.audio_codec.is_some() || options.audio_bitrate.is_some())
+        {
+            return Err("GifAnimated output has no audio; remove audio_codec/audio_bitrate".to_string());
+        }
+        args.push(format!("f={}", format.container()));
+        args.push(format!("pix_fmt={}", format.pix_fmt()));
+        if format == RenderFormat::ProRes {
+            // prores_ks profile 3 = 422 HQ
+            args.push("vprofile=3".to_string());
+        }
+        if format == RenderFormat::GifAnimated {
+            args.push("an=1".to_string());
+        }
+        if let RenderFormat::Hls { segment_secs } = format {
+            if segment_secs == 0 {
+                return Err("HLS segment_secs must be at least 1".to_string());
+            }
+            args.push(format!("hls_time={}", segment_secs));
+            // Keep every segment in the playlist and mark it complete
+            args.push("hls_list_size=0".to_string());
+            args.push("hls_playlist_type=vod".to_string());
+        }
+    }
+    if options.audio_action == Some(AudioAction::Mute) && !args.iter().any(|a| a == "an=1") {
+        args.push("an=1".to_string());
+    }
 
-    // Consumer arguments for output
-    let mut consumer = format!("avformat:{}", output_path);
+    // An explicit codec overrides the format default
+    let vcodec = options
+        .video_codec
+        .clone()
+        .or_else(|| options.output_format.map(|f| f.video_codec().to_string()));
+    let acodec = options
+        .audio_codec
+        .clone()
+        .or_else(|| options.output_format.and_then(|f| f.audio_codec().map(String::from)));
 
-    if let Some(ref vcodec) = options.video_codec {
-        cmd.arg(format!("vcodec={}", vcodec));
+    if let Some(ref vcodec) = vcodec {
+        args.push(format!("vcodec={}", vcodec));
     }
-    if let Some(ref acodec) = options.audio_codec {
-        cmd.arg(format!("acodec={}", acodec));
+    if let Some(ref acodec) = acodec {
+        args.push(format!("acodec={}", acodec));
     }
     if let Some(ref abitrate) = options.audio_bitrate {
-        cmd.arg(format!("ab={}", abitrate));
+        args.push(format!("ab={}", abitrate));
     }
     if let Some(crf) = options.crf {
-        cmd.arg(format!("crf={}", crf));
+        args.push(format!("crf={}", crf));
+        if vcodec.as_deref() == Some("libvpx-vp9") {
+            // VP9 only honours crf in constant-quality mode
+            args.push("vb=0".to_string());
+        }
     }
     if let Some(width) = options.width {
-        cmd.arg(format!("width={}", width));
+        args.push(format!("width={}", width));
     }
     if let Some(height) = options.height {
-        cmd.arg(format!("height={}", height));
+        args.push(format!("height={}", height));
     }
     if let Some(fr) = options.frame_rate {
-        cmd.arg(format!("frame_rate_num={}", fr));
+        args.push(format!("frame_rate_num={}", fr));
     }
 
-    // Add x264 preset for speed
-    cmd.arg("preset=medium");
+    // x264 preset for speed; melt defaults to libx264 when nothing is specified
+    let is_x26x = is_x26x(vcodec.as_deref());
 
-    cmd.arg("-consumer");
-    cmd.arg(&consumer);
+    if let Some(ref preset) = options.preset {
+        if !X264_PRESETS.contains(&preset.as_str()) {
+            return Err(format!(
+                "Unknown preset '{}'. Expected one of: {}",
+                preset,
+                X264_PRESETS.join(", ")
+            ));
+        }
+        if !is_x26x {
+            return Err("preset only applies to libx264/libx265".to_string());
+        }
+    }
 
-    // Capture progress output
-    cmd.arg("-progress");
+    if is_x26x {
+        args.push(format!("preset={}", options.preset.as_deref().unwrap_or("medium")));
+    }
 
-    // Run the command
-    let output = cmd
-        .output()
-        .map_err(|e| format!("Failed to run melt: {}", e))?;
+    args.extend(sanitize_extra_args(
+        "extra_consumer_args",
+        &options.extra_consumer_args,
+        options.allow_extra_flags,
+    )?);
 
-    // Cleanup job registration
-    {
-        let mut jobs = state.active_jobs.lock().map_err(|e| e.to_string())?;
-        jobs.remove(&job_id);
+    Ok(args)
+}
+
+/// Audio containers by output extension: (ffmpeg format, default codec, accepted codecs)
+const AUDIO_CONTAINERS: [(&str, &str, &str, &[&str]); 7] = [
+    ("mp3", "mp3", "libmp3lame", &["libmp3lame"]),
+    ("m4a", "ipod", "aac", &["aac", "alac"]),
+    ("aac", "adts", "aac", &["aac"]),
+    ("wav", "wav", "pcm_s16le", &["pcm_s16le", "pcm_s24le", "pcm_f32le"]),
+    ("flac", "flac", "flac", &["flac"]),
+    ("ogg", "ogg", "libvorbis", &["libvorbis", "libopus", "flac"]),
+    ("opus", "opus", "libopus", &["libopus"]),
+];
+
+/// Consumer properties for an audio-only export (`vn=1`), with the
+/// container and codec checked against the output extension
+fn build_audio_consumer_args(options: &RenderOptions, output_path: &str) -> Result<Vec<String>, String> {
+    if options.output_format.is_some() || options.video_codec.is_some() {
+        return Err("audio_only exports take their format from the output extension; remove output_format/video_codec".to_string());
+    }
+    if options.crf.is_some() || options.preset.is_some() || options.two_pass {
+        return Err("crf, preset and two_pass do not apply to audio_only exports".to_string());
+    }
+    if options.audio_action.as_ref().is_some_and(|a| *a != AudioAction::Keep) {
+        return Err("audio_action does not apply to audio_only exports".to_string());
+    }
+    if options.verify_output {
+        // Verification decodes the first video frame
+        return Err("verify_output is not supported for audio_only exports".to_string());
     }
 
-    // Clean up temp XML
-    let _ = fs::remove_file(&xml_path);
+    let extension = std::path::Path::new(output_path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let Some(&(_, format, default_codec, accepted)) =
+        AUDIO_CONTAINERS.iter().find(|(ext, ..)| *ext == extension)
+    else {
+        let known: Vec<&str> = AUDIO_CONTAINERS.iter().map(|(ext, ..)| *ext).collect();
+        return Err(format!(
+            "Unsupported audio extension '.{}'. Expected one of: {}",
+            extension,
+            known.join(", ")
+        ));
+    };
 
-    if output.status.success() {
-        Ok(RenderResult {
-            success: true,
-            error: None,
-            output_path: Some(output_path),
-        })
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        Ok(RenderResult {
-            success: false,
-            error: Some(format!("melt exited with error: {}", stderr)),
-            output_path: None,
-        })
+    let acodec = options.audio_codec.as_deref().unwrap_or(default_codec);
+    if !accepted.contains(&acodec) {
+        return Err(format!(
+            "Audio codec '{}' can't be written to .{} (use {})",
+            acodec,
+            extension,
+            accepted.join(", ")
+        ));
     }
-}
 
-#[tauri::command]
-pub async fn cancel_melt_render(
-    job_id: String,
-    state: State<'_, MeltState>,
-) -> Result<bool, String> {
-    let mut jobs = state.active_jobs.lock().map_err(|e| e.to_string())?;
-    if let Some(cancelled) = jobs.get_mut(&job_id) {
-        *cancelled = true;
-        Ok(true)
-    } else {
-        Ok(false)
+    let mut args = vec![
+        format!("f={}", format),
+        "vn=1".to_string(),
+        format!("acodec={}", acodec),
+    ];
+    if let Some(ref abitrate) = options.audio_bitrate {
+        args.push(format!("ab={}", abitrate));
     }
-}
 
-#[tauri::command]
-pub fn get_mlt_temp_dir() -> Result<String, String> {
-    let dir = get_mlt_temp_dir_path()?;
-    Ok(dir.to_string_lossy().to_string())
+    args.extend(sanitize_extra_args(
+        "extra_consumer_args",
+        &options.extra_consumer_args,
+        options.allow_extra_flags,
+    )?);
+
+    Ok(args)
 }
 
-#[tauri::command]
-pub fn cleanup_mlt_temp_files() -> Result<(), String> {
-    let temp_dir = get_mlt_temp_dir_path()?;
+/// Validate user-supplied melt arguments before appending them verbatim.
+///
+/// Rejects empty args and control characters, and anything that looks like
+/// a melt flag (`-consumer`, `-profile`, ...) unless `allow_flags` is set.
+fn sanitize_extra_args(field: &str, args: &[String], allow_flags: bool) -> Result<Vec<String>, String> {
+    for arg in args {
+        if arg.trim().is_empty() {
+            return Err(format!("{} contains an empty argument", field));
+        }
+        if arg.chars().any(|c| c.is_control()) {
+            return Err(format!("{} argument {:?} contains control characters", field, arg));
+        }
+        if arg.starts_with('-') && !allow_flags {
+            return Err(format!(
+                "{} argument {:?} looks like a melt flag; set allow_extra_flags to pass it",
+                field, arg
+            ));
+        }
+    }
+    Ok(args.to_vec())
+}
 
-    if temp_dir.exists() {
-        let entries = fs::read_dir(&temp_dir)
-            .map_err(|e| format!("Failed to read temp dir: {}", e))?;
+/// Remove the encoder's passlog files (`<prefix>-0.log`, `.mbtree`, ...)
+fn remove_passlog_files(temp_dir: &PathBuf, passlog: &PathBuf) {
+    let prefix = match passlog.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => return,
+    };
 
+    if let Ok(entries) = fs::read_dir(temp_dir) {
         for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_file() {
-                let _ = fs::remove_file(&path);
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                let _ = fs::remove_file(entry.path());
             }
         }
     }
-
-    Ok(())
 }
 
-#[tauri::command]
-pub fn run_melt_raw(args: Vec<String>) -> Result<MeltRawResult, String> {
-    let melt_path = find_melt().ok_or("melt not found on system")?;
+// ============================================
+// RENDER RANGE
+// ============================================
 
-    let output = Command::new(&melt_path)
-        .args(&args)
-        .output()
-        .map_err(|e| format!("Failed to run melt: {}", e))?;
+/// Read the project length in frames from the MLT XML.
+///
+/// Uses the largest `out` on a `<tractor>` element (the main tractor spans the
+/// whole project). Returns None when no tractor declares an out point.
+fn mlt_project_length(mlt_xml: &str) -> Option<u64> {
+    let mut length: Option<u64> = None;
+    let mut rest = mlt_xml;
 
-    Ok(MeltRawResult {
-        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        exit_code: output.status.code().unwrap_or(-1),
-    })
-}
+    while let Some(start) = rest.find("<tractor") {
+        let tag = &rest[start..];
+        let end = tag.find('>').unwrap_or(tag.len());
+        let tag_text = &tag[..end];
 
-#[tauri::command]
-pub fn validate_mlt_xml(mlt_xml: String) -> Result<ValidateResult, String> {
-    let melt_path = match find_melt() {
-        Some(p) => p,
-        None => return Ok(ValidateResult {
-            valid: false,
-            error: Some("melt not found".to_string()),
-        }),
-    };
+        if let Some(out_pos) = tag_text.find(" out=\"") {
+            let value = &tag_text[out_pos + 6..];
+            if let Some(quote) = value.find('"') {
+                if let Ok(out) = value[..quote].trim().parse::<u64>() {
+                    let frames = out + 1;
+                    length = Some(length.map_or(frames, |l| l.max(frames)));
+                }
+            }
+        }
 
-    // Write XML to temp file
-    let temp_dir = get_mlt_temp_dir_path()?;
-    let xml_path = temp_dir.join("validate_temp.mlt");
+        rest = &tag[end..];
+    }
 
-    fs::write(&xml_path, &mlt_xml)
-        .map_err(|e| format!("Failed to write temp XML: {}", e))?;
+    length
+}
 
-    // Run melt in info mode to validate
-    let output = Command::new(&melt_path)
-        .arg(xml_path.to_string_lossy().to_string())
-        .arg("-consumer")
-        .arg("xml")
-        .output();
+/// Build producer arguments for the in/out range (validating it first),
+/// followed by any extra producer properties
+fn build_range_args(options: &RenderOptions, mlt_xml: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
 
-    // Cleanup
-    let _ = fs::remove_file(&xml_path);
+    if let (Some(in_frame), Some(out_frame)) = (options.in_frame, options.out_frame) {
+        if in_frame >= out_frame {
+            return Err(format!(
+                "in_frame ({}) must be less than out_frame ({})",
+                in_frame, out_frame
+            ));
+        }
+    }
 
-    match output {
-        Ok(out) => {
-            if out.status.success() {
-                Ok(ValidateResult {
-                    valid: true,
-                    error: None,
-                })
-            } else {
-                let stderr = String::from_utf8_lossy(&out.stderr).to_string();
-                Ok(ValidateResult {
-                    valid: false,
-                    error: Some(stderr),
-                })
+    if let Some(length) = mlt_project_length(mlt_xml) {
+        for (name, value) in [("in_frame", options.in_frame), ("out_frame", options.out_frame)] {
+            if let Some(frame) = value {
+                if frame >= length {
+                    return Err(format!(
+                        "{} ({}) is beyond the end of the project ({} frames)",
+                        name, frame, length
+                    ));
+                }
             }
         }
-        Err(e) => Ok(ValidateResult {
-            valid: false,
-            error: Some(format!("Failed to validate: {}", e)),
+    }
+
+    if let Some(in_frame) = options.in_frame {
+        args.push(format!("in={}", in_frame));
+    }
+    if let Some(out_frame) = options.out_frame {
+        args.push(format!("out={}", out_frame));
+    }
+
+    args.extend(sanitize_extra_args(
+        "extra_producer_args",
+        &options.extra_producer_args,
+        options.allow_extra_flags,
+    )?);
+
+    Ok(args)
+}
+
+// ============================================
+// FILTERS
+// ============================================
+
+/// Distance between a watermark and the frame edge, in percent
+const WATERMARK_MARGIN_PCT: f64 = 3.0;
+
+/// MLT composite geometry ("X%/Y%:W%xH%:opacity") for a watermark box
+fn watermark_geometry(position: WatermarkPosition, scale: f64, opacity: f64) -> String {
+    let size = scale * 100.0;
+    let near = WATERMARK_MARGIN_PCT;
+    let far = 100.0 - WATERMARK_MARGIN_PCT - size;
+    let middle = (100.0 - size) / 2.0;
+
+    let (x, y) = match position {
+        WatermarkPosition::TopLeft => (near, near),
+        WatermarkPosition::TopRight => (far, near),
+        WatermarkPosition::BottomLeft => (near, far),
+        WatermarkPosition::BottomRight => (far, far),
+        WatermarkPosition::Center => (middle, middle),
+    };
+
+    format!("{:.2}%/{:.2}%:{:.2}%x{:.2}%:{:.0}", x, y, size, size, opacity * 100.0)
+}
+
+/// "#RRGGBB" as an ASS colour ("&H00BBGGRR")
+fn ass_color(color: &str) -> Result<String, String> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid subtitle color '{}', expected #RRGGBB", color));
+    }
+    Ok(format!("&H00{}{}{}", &hex[4..6], &hex[2..4], &hex[0..2]).to_uppercase())
+}
+
+/// Check a subtitle file exists, has a known extension and contains cues
+fn check_subtitle_file(path: &str) -> Result<(), String> {
+    let extension = std::path::Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !matches!(extension.as_str(), "srt" | "ass" | "ssa") {
+        return Err(format!("Unsupported subtitle file '{}', expected .srt, .ass or .ssa", path));
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read subtitles '{}': {}", path, e))?;
+    let has_cues = if extension == "srt" {
+        text.lines().any(|line| line.contains("-->"))
+    } else {
+        text.contains("[Events]") && text.lines().any(|line| line.trim_start().starts_with("Dialogue:"))
+    };
+    if !has_cues {
+        return Err(format!("Subtitle file '{}' contains no cues", path));
+    }
+    Ok(())
+}
+
+/// libass `force_style` override for the subtitle options, if any are set
+fn subtitle_force_style(subtitles: &SubtitleOptions) -> Result<Option<String>, String> {
+    let mut style = Vec::new();
+    if let Some(font_name) = &subtitles.font_name {
+        if font_name.contains([',', '=']) {
+            return Err(format!("Invalid subtitle font name '{}'", font_name));
+        }
+        style.push(format!("FontName={}", font_name));
+    }
+    if let Some(font_size) = subtitles.font_size {
+        style.push(format!("FontSize={}", font_size));
+    }
+    if let Some(color) = &subtitles.color {
+        style.push(format!("PrimaryColour={}", ass_color(color)?));
+    }
+    if let Some(color) = &subtitles.outline_color {
+        style.push(format!("OutlineColour={}", ass_color(color)?));
+    }
+    if let Some(margin_v) = subtitles.margin_v {
+        style.push(format!("MarginV={}", margin_v));
+    }
+    Ok((!style.is_empty()).then(|| style.join(",")))
+}
+
+/// Fonts tried, in order, when a timecode overlay doesn't name one
+const DEFAULT_FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu-sans-fonts/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "/Library/Fonts/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+
+/// Font file for a timecode overlay: the requested one, which must exist,
+/// or the first available default
+fn timecode_font(requested: Option<&str>) -> Result<String, String> {
+    match requested {
+        Some(path) if std::path::Path::new(path).is_file() => Ok(path.to_string()),
+        Some(path) => Err(format!("Timecode font not found: {}", path)),
+        None => DEFAULT_FONT_PATHS
+            .iter()
+            .find(|path| std::path::Path::new(path).is_file())
+            .map(|path| path.to_string())
+            .ok_or_else(|| {
+                "No default font found for the timecode overlay; set font_path".to_string()
+            }),
+    }
+}
+
+/// "HH:MM:SS:FF" for `frame` at `fps` (rounded to a whole rate, non-drop)
+fn timecode_string(frame: u64, fps: f64) -> String {
+    let rate = (fps.round() as u64).max(1);
+    let secs = frame / rate;
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        frame % rate
+    )
+}
+
+/// Build `-attach` arguments for filters applied to the whole project output
+fn build_filter_args(options: &RenderOptions, mlt_xml: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+
+    if let Some(lut_path) = &options.lut_path {
+        // Parse up front so a malformed file fails before melt starts
+        Lut3d::load(std::path::Path::new(lut_path))?;
+        args.push("-attach".to_string());
+        args.push("avfilter.lut3d".to_string());
+        args.push(format!("av.file={}", lut_path));
+        args.push("av.interp=trilinear".to_string());
+    }
+
+    if let Some(subtitles) = &options.subtitles {
+        if options.audio_only {
+            return Err("subtitles can't be burned into an audio-only export".to_string());
+        }
+        check_subtitle_file(&subtitles.path)?;
+        args.push("-attach".to_string());
+        args.push("avfilter.subtitles".to_string());
+        args.push(format!("av.filename={}", subtitles.path));
+        if let Some(style) = subtitle_force_style(subtitles)? {
+            args.push(format!("av.force_style={}", style));
+        }
+    }
+
+    if let Some(watermark) = &options.watermark {
+        if !std::path::Path::new(&watermark.image_path).is_file() {
+            return Err(format!("Watermark image not found: {}", watermark.image_path));
+        }
+        let opacity = watermark.opacity.unwrap_or(1.0);
+        if !valid_watermark_opacity(opacity) {
+            return Err(format!("Watermark opacity must be between 0 and 1 (got {})", opacity));
+        }
+        let scale = watermark.scale.unwrap_or(0.15);
+        if !valid_watermark_scale(scale) {
+            return Err(format!("Watermark scale must be in (0, 1] (got {})", scale));
+        }
+
+        let (halign, valign) = match watermark.position {
+            WatermarkPosition::TopLeft => ("left", "top"),
+            WatermarkPosition::TopRight => ("right", "top"),
+            WatermarkPosition::BottomLeft => ("left", "bottom"),
+            WatermarkPosition::BottomRight => ("right", "bottom"),
+            WatermarkPosition::Center => ("centre", "middle"),
+        };
+
+        args.push("-attach".to_string());
+        args.push(format!("watermark:{}", watermark.image_path));
+        args.push(format!(
+            "composite.geometry={}",
+            watermark_geometry(watermark.position, scale, opacity)
+        ));
+        // Keep the logo's aspect inside the box, aligned toward its corner
+        args.push("composite.fill=0".to_string());
+        args.push(format!("composite.halign={}", halign));
+        args.push(format!("composite.valign={}", valign));
+    }
+
+    if let Some(timecode) = &options.timecode_overlay {
+        if options.audio_only {
+            return Err("a timecode can't be burned into an audio-only export".to_string());
+        }
+        let offset_secs = timecode.start_offset_secs.unwrap_or(0.0);
+        if offset_secs.is_nan() || offset_secs < 0.0 {
+            return Err(format!("Timecode start offset must not be negative (got {})", offset_secs));
+        }
+        let font = timecode_font(timecode.font_path.as_deref())?;
+        let profile_fps = mlt_parser::parse_mlt(mlt_xml)
+            .ok()
+            .and_then(|p| p.profile)
+            .and_then(|p| p.fps);
+        let fps = output_fps(options, profile_fps);
+        // drawtext counts from the first rendered frame, so add the range start
+        let start_frame = (offset_secs * fps).round() as u64 + options.in_frame.unwrap_or(0);
+
+        let margin = "20";
+        let (x, y) = match timecode.position {
+            WatermarkPosition::TopLeft => (margin.to_string(), margin.to_string()),
+            WatermarkPosition::TopRight => (format!("w-tw-{}", margin), margin.to_string()),
+            WatermarkPosition::BottomLeft => (margin.to_string(), format!("h-th-{}", margin)),
+            WatermarkPosition::BottomRight => {
+                (format!("w-tw-{}", margin), format!("h-th-{}", margin))
+            }
+            WatermarkPosition::Center => ("(w-tw)/2".to_string(), "(h-th)/2".to_string()),
+        };
+
+        args.push("-attach".to_string());
+        args.push("avfilter.drawtext".to_string());
+        args.push(format!("av.fontfile={}", font));
+        args.push(format!("av.timecode={}", timecode_string(start_frame, fps)));
+        args.push(format!("av.rate={}", fps));
+        args.push(format!("av.fontsize={}", timecode.font_size.unwrap_or(36)));
+        args.push("av.fontcolor=white".to_string());
+        args.push("av.box=1".to_string());
+        args.push("av.boxcolor=black@0.6".to_string());
+        args.push("av.boxborderw=8".to_string());
+        args.push(format!("av.x={}", x));
+        args.push(format!("av.y={}", y));
+    }
+
+    Ok(args)
+}
+
+/// How far a replacement audio file's duration may drift from the render's
+/// before it's rejected, as a fraction of the render duration (at least 1 s)
+const AUDIO_REPLACEMENT_TOLERANCE: f64 = 0.05;
+
+/// Check a replacement audio file exists, has an audio stream and roughly
+/// matches the render's duration `expected_secs` (when known)
+fn check_replacement_audio(path: &str, expected_secs: Option<f64>) -> Result<(), String> {
+    if !std::path::Path::new(path).is_file() {
+        return Err(format!("Replacement audio not found: {}", path));
+    }
+    let streams = video_decoder::probe_streams(path).map_err(|e| e.message)?;
+    let audio = streams
+        .iter()
+        .find(|s| s.media_type == "audio")
+        .ok_or_else(|| format!("Replacement audio '{}' has no audio stream", path))?;
+
+    if let (Some(actual), Some(expected)) = (audio.duration_secs, expected_secs) {
+        let tolerance = (expected * AUDIO_REPLACEMENT_TOLERANCE).max(1.0);
+        if (actual - expected).abs() > tolerance {
+            return Err(format!(
+                "Replacement audio is {:.1}s but the render is {:.1}s",
+                actual, expected
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Producer arguments for `AudioAction::ReplaceWith`: silence the project's
+/// track and add the file as an audio-only track trimmed to the render length.
+/// These must come after every argument aimed at the project producer, and
+/// the mute must be attached before the new track exists so it only hits
+/// the original.
+fn build_audio_replacement_args(options: &RenderOptions, mlt_xml: &str) -> Result<Vec<String>, String> {
+    let Some(AudioAction::ReplaceWith { path }) = &options.audio_action else {
+        return Ok(Vec::new());
+    };
+
+    let shape = render_shape(options, mlt_xml);
+    check_replacement_audio(path, shape.as_ref().map(|s| s.duration_secs))?;
+
+    let mut args = vec![
+        "-attach-track".to_string(),
+        "volume".to_string(),
+        "gain=0".to_string(),
+        "-audio-track".to_string(),
+        path.clone(),
+    ];
+    if let Some(shape) = shape {
+        args.push(format!("out={}", shape.frames.saturating_sub(1)));
+    }
+    Ok(args)
+}
+
+// ============================================
+// OPTION VALIDATION
+// ============================================
+
+/// Video encoders we expect melt's avformat consumer to have
+const KNOWN_VIDEO_CODECS: &[&str] = &[
+    "libx264",
+    "libx265",
+    "libvpx-vp9",
+    "libvpx",
+    "libaom-av1",
+    "libsvtav1",
+    "prores_ks",
+    "dnxhd",
+    "mpeg4",
+    "mjpeg",
+    "ffv1",
+    "gif",
+    "png",
+];
+
+/// Audio encoders we expect melt's avformat consumer to have
+const KNOWN_AUDIO_CODECS: &[&str] = &[
+    "aac",
+    "libopus",
+    "libvorbis",
+    "libmp3lame",
+    "flac",
+    "alac",
+    "ac3",
+    "pcm_s16le",
+    "pcm_s24le",
+    "pcm_f32le",
+];
+
+/// Encoders that honour `crf`
+const CRF_CODECS: &[&str] = &[
+    "libx264",
+    "libx265",
+    "libvpx-vp9",
+    "libvpx",
+    "libaom-av1",
+    "libsvtav1",
+];
+
+/// Check RenderOptions for out-of-range values and contradictory fields
+/// without touching melt or the filesystem
+fn check_render_options(options: &RenderOptions) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut push = |severity: IssueSeverity, field: &str, message: String| {
+        issues.push(ValidationIssue {
+            severity,
+            message,
+            line: None,
+            column: None,
+            resource: None,
+            field: Some(field.to_string()),
+        });
+    };
+    let error = IssueSeverity::Error;
+    let warning = IssueSeverity::Warning;
+
+    let vcodec = options
+        .video_codec
+        .clone()
+        .or_else(|| options.output_format.map(|f| f.video_codec().to_string()));
+
+    if let Some(codec) = &options.video_codec {
+        if !KNOWN_VIDEO_CODECS.contains(&codec.as_str()) {
+            push(
+                warning,
+                "video_codec",
+                format!("Unknown video codec '{}'", codec),
+            );
+        }
+    }
+    if let Some(codec) = &options.audio_codec {
+        if !KNOWN_AUDIO_CODECS.contains(&codec.as_str()) {
+            push(
+                warning,
+                "audio_codec",
+                format!("Unknown audio codec '{}'", codec),
+            );
+        }
+    }
+    if let Some(bitrate) = &options.audio_bitrate {
+        let digits = bitrate.strip_suffix(['k', 'K']).unwrap_or(bitrate);
+        if digits.is_empty()
+            || !digits.chars().all(|c| c.is_ascii_digit())
+            || digits.parse() == Ok(0u64)
+        {
+            push(
+                error,
+                "audio_bitrate",
+                format!(
+                    "Invalid audio bitrate '{}', expected e.g. \"192k\"",
+                    bitrate
+                ),
+            );
+        }
+    }
+
+    if let Some(crf) = options.crf {
+        if crf > 51 {
+            push(
+                error,
+                "crf",
+                format!("crf must be between 0 and 51 (got {})", crf),
+            );
+        }
+        if let Some(format) = options.output_format.filter(|f| !f.supports_crf()) {
+            push(
+                error,
+                "crf",
+                format!("crf is not supported for {:?} output", format),
+            );
+        } else if let Some(codec) = vcodec.as_deref().filter(|c| !CRF_CODECS.contains(c)) {
+            push(warning, "crf", format!("crf is ignored by {}", codec));
+        }
+    }
+
+    for (field, value) in [("width", options.width), ("height", options.height)] {
+        if value == Some(0) {
+            push(error, field, format!("{} must be positive", field));
+        } else if value.is_some_and(|v| v % 2 == 1)
+            && vcodec.as_deref().is_none_or(|c| CRF_CODECS.contains(&c))
+        {
+            // 4:2:0 encoders need even dimensions
+            push(
+                error,
+                field,
+                format!(
+                    "{} must be even for {}",
+                    field,
+                    vcodec.as_deref().unwrap_or("libx264")
+                ),
+            );
+        }
+    }
+    match (options.width, options.height) {
+        (Some(_), None) => push(
+            error,
+            "height",
+            "height is required when width is set".to_string(),
+        ),
+        (None, Some(_)) => push(
+            error,
+            "width",
+            "width is required when height is set".to_string(),
+        ),
+        _ => {}
+    }
+    if options.frame_rate == Some(0) {
+        push(
+            error,
+            "frame_rate",
+            "frame_rate must be positive".to_string(),
+        );
+    }
+
+    if let (Some(in_frame), Some(out_frame)) = (options.in_frame, options.out_frame) {
+        if in_frame >= out_frame {
+            push(
+                error,
+                "in_frame",
+                format!(
+                    "in_frame ({}) must be less than out_frame ({})",
+                    in_frame, out_frame
+                ),
+            );
+        }
+    }
+
+    if let Some(preset) = &options.preset {
+        if !X264_PRESETS.contains(&preset.as_str()) {
+            push(error, "preset", format!("Unknown preset '{}'", preset));
+        } else if !is_x26x(vcodec.as_deref()) {
+            push(
+                error,
+                "preset",
+                "preset only applies to libx264/libx265".to_string(),
+            );
+        }
+    }
+
+    for (field, value) in [
+        ("timeout_secs", options.timeout_secs),
+        ("stall_timeout_secs", options.stall_timeout_secs),
+    ] {
+        if value == Some(0) {
+            push(error, field, format!("{} must be positive", field));
+        }
+    }
+
+    match options.output_format {
+        Some(RenderFormat::GifAnimated)
+            if options.audio_codec.is_some() || options.audio_bitrate.is_some() =>
+        {
+            push(
+                error,
+                "audio_codec",
+                "GifAnimated output has no audio".to_string(),
+            )
+        }
+        Some(RenderFormat::Hls { segment_secs: 0 }) => push(
+            error,
+            "output_format",
+            "HLS segment_secs must be at least 1".to_string(),
+        ),
+        _ => {}
+    }
+
+    if options.audio_only {
+        if options.output_format.is_some() || options.video_codec.is_some() {
+            push(
+                error,
+                "audio_only",
+                "audio_only exports take their format from the output extension".to_string(),
+            );
+        }
+        if options.two_pass {
+            push(
+                error,
+                "two_pass",
+                "two_pass does not apply to audio_only exports".to_string(),
+            );
+        }
+        if options.subtitles.is_some() || options.watermark.is_some() {
+            push(
+                warning,
+                "audio_only",
+                "subtitles and watermark are ignored for audio_only exports".to_string(),
+            );
+        }
+    }
+
+    if let Some(watermark) = &options.watermark {
+        if !watermark.opacity.is_none_or(valid_watermark_opacity) {
+            push(
+                error,
+                "watermark.opacity",
+                "Watermark opacity must be between 0 and 1".to_string(),
+            );
+        }
+        if !watermark.scale.is_none_or(valid_watermark_scale) {
+            push(
+                error,
+                "watermark.scale",
+                "Watermark scale must be in (0, 1]".to_string(),
+            );
+        }
+    }
+    if let Some(subtitles) = &options.subtitles {
+        for (field, color) in [
+            ("subtitles.color", &subtitles.color),
+            ("subtitles.outline_color", &subtitles.outline_color),
+        ] {
+            if let Some(Err(e)) = color.as_deref().map(ass_color) {
+                push(error, field, e);
+            }
+        }
+    }
+    if let Some(timecode) = &options.timecode_overlay {
+        if timecode.start_offset_secs.is_some_and(|o| o.is_nan() || o < 0.0) {
+            push(
+                error,
+                "timecode_overlay.start_offset_secs",
+                "Timecode start offset must not be negative".to_string(),
+            );
+        }
+        if timecode.font_size == Some(0) {
+            push(
+                error,
+                "timecode_overlay.font_size",
+                "Timecode font size must be positive".to_string(),
+            );
+        }
+    }
+
+    issues
+}
+
+/// Check RenderOptions before starting a render, so the export dialog can
+/// show each problem next to its field
+#[tauri::command]
+pub fn validate_render_options(options: RenderOptions) -> ValidateResult {
+    ValidateResult::from_issues(check_render_options(&options))
+}
+
+// ============================================
+// PREFLIGHT
+// ============================================
+
+/// Producer services whose resource is a media file
+const FILE_SERVICES: &[&str] = &["avformat", "avformat-novalidate", "qimage", "pixbuf", "xml"];
+
+/// Whether a resource starts with a loader prefix such as `color:` or
+/// `avformat:` rather than being a plain path. A single letter is a
+/// Windows drive, not a prefix.
+fn has_service_prefix(resource: &str) -> bool {
+    resource.split_once(':').is_some_and(|(prefix, _)| {
+        prefix.len() > 1
+            && prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    })
+}
+
+/// Check every file-backed producer's media exists and is readable.
+/// Relative resources are resolved against the document's `<mlt root>`,
+/// else `base_dir`, and skipped when neither is known.
+fn preflight_resources(mlt_xml: &str, base_dir: Option<&std::path::Path>) -> Result<PreflightResult, String> {
+    let project = mlt_parser::parse_mlt(mlt_xml).map_err(|e| e.to_string())?;
+    let base_dir = project.root.as_deref().map(std::path::Path::new).or(base_dir);
+    let mut missing = Vec::new();
+
+    for producer in &project.producers {
+        let Some(resource) = producer.resource.as_deref() else {
+            continue;
+        };
+        let reads_file = producer
+            .service
+            .as_deref()
+            .is_none_or(|service| FILE_SERVICES.contains(&service));
+        // Nested MLT documents and tractors are referenced by id, not path
+        if !reads_file
+            || resource.is_empty()
+            || resource.starts_with('<')
+            || has_service_prefix(resource)
+        {
+            continue;
+        }
+
+        let path = if std::path::Path::new(resource).is_absolute() {
+            PathBuf::from(resource)
+        } else if let Some(base) = base_dir {
+            base.join(resource)
+        } else {
+            continue;
+        };
+        let path_text = path.to_string_lossy().to_string();
+
+        let reason = if video_decoder::is_sequence_pattern(&path_text) {
+            video_decoder::scan_sequence(&path_text).is_none().then_some("missing")
+        } else if !path.is_file() {
+            Some("missing")
+        } else if fs::File::open(&path).is_err() {
+            Some("unreadable")
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            missing.push(MissingResource {
+                producer_id: producer.id.clone(),
+                resource: resource.to_string(),
+                reason: reason.to_string(),
+            });
+        }
+    }
+
+    Ok(PreflightResult {
+        ok: missing.is_empty(),
+        missing,
+    })
+}
+
+// ============================================
+// DISK SPACE
+// ============================================
+
+/// Headroom kept free on the output volume on top of the estimate
+const DISK_RESERVE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Conservative bits per pixel per frame by video codec (high-motion,
+/// default quality); overestimating is fine, the check is a guard rail
+fn bits_per_pixel(vcodec: &str) -> f64 {
+    match vcodec {
+        "prores_ks" | "prores" => 3.6,
+        "gif" => 1.0,
+        "mjpeg" => 2.0,
+        "libvpx-vp9" => 0.15,
+        _ => 0.25,
+    }
+}
+
+/// Span, rate and geometry of a render, resolved from the options and the
+/// document's profile
+struct RenderShape {
+    /// First project frame rendered
+    start_frame: u64,
+    /// Project frames rendered
+    frames: u64,
+    duration_secs: f64,
+    /// Output frame rate
+    fps: f64,
+    width: u32,
+    height: u32,
+    vcodec: String,
+    /// Explicit audio bitrate, if set
+    audio_bps: Option<f64>,
+}
+
+/// Output frame rate: the explicit option, else the project profile's
+fn output_fps(options: &RenderOptions, profile_fps: Option<f64>) -> f64 {
+    options.frame_rate.map(|f| f as f64).or(profile_fps).unwrap_or(30.0)
+}
+
+/// Resolve the render's shape, or None when the duration can't be
+/// determined from the options or document
+fn render_shape(options: &RenderOptions, mlt_xml: &str) -> Option<RenderShape> {
+    let profile = mlt_parser::parse_mlt(mlt_xml).ok().and_then(|p| p.profile);
+    let fps = output_fps(options, profile.as_ref().and_then(|p| p.fps));
+    let project_fps = profile.as_ref().and_then(|p| p.fps).unwrap_or(fps);
+
+    let start_frame = options.in_frame.unwrap_or(0);
+    let frames = match (options.in_frame, options.out_frame) {
+        (Some(in_frame), Some(out_frame)) => out_frame.saturating_sub(in_frame) + 1,
+        (in_frame, _) => mlt_project_length(mlt_xml)?.saturating_sub(in_frame.unwrap_or(0)),
+    };
+
+    Some(RenderShape {
+        start_frame,
+        frames,
+        duration_secs: frames as f64 / project_fps.max(1.0),
+        fps,
+        width: options.width.or(profile.as_ref().and_then(|p| p.width)).unwrap_or(1920),
+        height: options.height.or(profile.as_ref().and_then(|p| p.height)).unwrap_or(1080),
+        vcodec: options
+            .video_codec
+            .clone()
+            .or_else(|| options.output_format.map(|f| f.video_codec().to_string()))
+            .unwrap_or_else(|| "libx264".to_string()),
+        audio_bps: options
+            .audio_bitrate
+            .as_deref()
+            .and_then(|b| b.trim_end_matches(['k', 'K']).parse::<f64>().ok())
+            .map(|kbps| kbps * 1000.0),
+    })
+}
+
+/// Typical bits per pixel per frame by video codec at the given CRF, for
+/// size estimates shown to the user (unlike `bits_per_pixel`, not a bound)
+fn typical_bits_per_pixel(vcodec: &str, crf: Option<u32>) -> f64 {
+    let (base, default_crf) = match vcodec {
+        "prores_ks" | "prores" => return 3.5,
+        "gif" => return 0.8,
+        "mjpeg" => return 1.5,
+        "libvpx-vp9" => (0.05, 31),
+        "libx265" => (0.04, 28),
+        _ => (0.07, 23),
+    };
+    // Bitrate roughly halves every 6 CRF steps
+    let crf = crf.unwrap_or(default_crf) as f64;
+    base * 2f64.powf((default_crf as f64 - crf) / 6.0)
+}
+
+/// Rough upper bound on the output size in bytes, or None when the
+/// duration can't be determined from the options or document
+fn estimate_output_bytes(options: &RenderOptions, mlt_xml: &str) -> Option<u64> {
+    let shape = render_shape(options, mlt_xml)?;
+
+    // Uncompressed stereo PCM is the worst case we produce
+    let audio_bps = shape.audio_bps.unwrap_or(1_536_000.0);
+
+    let video_bps = if options.audio_only {
+        0.0
+    } else {
+        shape.width as f64 * shape.height as f64 * shape.fps * bits_per_pixel(&shape.vcodec)
+    };
+
+    Some(((video_bps + audio_bps) * shape.duration_secs / 8.0) as u64)
+}
+
+/// Fail before spawning melt when the output volume clearly can't hold the result
+fn check_disk_space(options: &RenderOptions, mlt_xml: &str, output_path: &str) -> Result<(), String> {
+    let Some(estimate) = estimate_output_bytes(options, mlt_xml) else {
+        return Ok(());
+    };
+    // A FIFO hands the stream to its reader and stores nothing
+    if is_fifo(std::path::Path::new(output_path)) {
+        return Ok(());
+    }
+
+    // The output directory may not exist yet; measure its nearest existing ancestor
+    let output = std::path::Path::new(output_path);
+    let Some(volume) = output.ancestors().skip(1).find(|p| p.is_dir()) else {
+        return Ok(());
+    };
+    let Ok(available) = fs2::available_space(volume) else {
+        return Ok(());
+    };
+
+    let required = estimate + DISK_RESERVE_BYTES;
+    if available < required {
+        return Err(format!(
+            "Not enough disk space for this render: about {} MB needed, {} MB free on {}",
+            required / (1024 * 1024),
+            available / (1024 * 1024),
+            volume.display()
+        ));
+    }
+    Ok(())
+}
+
+// ============================================
+// HLS OUTPUT
+// ============================================
+
+/// Playlist written inside an HLS output directory
+const HLS_PLAYLIST_NAME: &str = "playlist.m3u8";
+
+/// Check an HLS target is a directory (creating it if missing) and return
+/// the playlist path inside it
+fn prepare_hls_dir(output_dir: &str) -> Result<PathBuf, String> {
+    let dir = std::path::Path::new(output_dir);
+    if dir.exists() && !dir.is_dir() {
+        return Err(format!("HLS output must be a directory, not a file: {}", output_dir));
+    }
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create HLS output directory '{}': {}", output_dir, e))?;
+    Ok(dir.join(HLS_PLAYLIST_NAME))
+}
+
+/// Media segments listed in an HLS playlist
+fn count_hls_segments(playlist: &str) -> u32 {
+    playlist
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .count() as u32
+}
+
+// ============================================
+// PIPE OUTPUT
+// ============================================
+
+#[cfg(unix)]
+fn is_fifo(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &std::path::Path) -> bool {
+    false
+}
+
+/// Check `path` is a FIFO, creating one with `mkfifo` if nothing is there
+fn prepare_fifo(path: &str) -> Result<(), String> {
+    let fifo = std::path::Path::new(path);
+    if is_fifo(fifo) {
+        return Ok(());
+    }
+    if fifo.exists() {
+        return Err(format!("Pipe output must be a FIFO, not a regular file: {}", path));
+    }
+    if !cfg!(unix) {
+        return Err("FIFO output is only supported on macOS and Linux".to_string());
+    }
+    let status = Command::new("mkfifo")
+        .arg(path)
+        .status()
+        .map_err(|e| format!("Failed to run mkfifo: {}", e))?;
+    if !status.success() {
+        return Err(format!("mkfifo failed for {}", path));
+    }
+    Ok(())
+}
+
+/// Options for a render into a FIFO: MPEG-TS unless a streamable format
+/// was chosen, and nothing that needs to seek or re-read the output
+fn fifo_render_options(options: &RenderOptions) -> Result<RenderOptions, String> {
+    let mut options = options.clone();
+    match options.output_format {
+        None => options.output_format = Some(RenderFormat::MpegTs),
+        Some(RenderFormat::MpegTs | RenderFormat::WebmVp9) => {}
+        Some(format) => {
+            return Err(format!(
+                "{:?} output needs a seekable file; use MpegTs or WebmVp9 for a pipe",
+                format
+            ))
+        }
+    }
+    if options.two_pass {
+        return Err("two_pass can't render into a pipe (pass 1 would be streamed too)".to_string());
+    }
+    if options.verify_output {
+        return Err("verify_output can't re-read a pipe; verify on the reading side".to_string());
+    }
+    if options.audio_only {
+        return Err("audio_only can't pick a container for a pipe".to_string());
+    }
+    Ok(options)
+}
+
+// ============================================
+// RENDER EXECUTION
+// ============================================
+
+/// Outcome of a single melt process
+enum MeltRunOutcome {
+    Finished { success: bool, stderr: String },
+    Cancelled,
+    TimedOut(u64),
+    Stalled(u64),
+}
+
+/// Latest progress reported by melt's `-progress` output
+#[derive(Clone, Copy)]
+struct MeltProgress {
+    frame: u64,
+    percentage: u32,
+    last_update: Instant,
+}
+
+/// Payload of the "melt-render-progress" event
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MeltRenderProgress {
+    pub job_id: String,
+    /// 1 for single-pass renders, 1 or 2 for two-pass renders
+    pub pass: u32,
+    pub frame: u64,
+    pub percentage: u32,
+    /// Smoothed encoding speed in frames per second
+    pub fps_encoding: Option<f64>,
+    /// Estimated seconds remaining in this pass
+    pub eta_secs: Option<f64>,
+}
+
+/// Rolling ETA from frames done versus elapsed time, smoothed with an
+/// exponential moving average so early estimates don't jump around.
+struct EtaEstimator {
+    first_update: Option<Instant>,
+    last_sample: Option<(Instant, u64)>,
+    ema_fps: Option<f64>,
+}
+
+impl EtaEstimator {
+    /// Weight of the newest speed sample
+    const SMOOTHING: f64 = 0.2;
+    /// No ETA is reported until progress has been observed for this long
+    const WARMUP: Duration = Duration::from_secs(3);
+
+    fn new() -> Self {
+        Self {
+            first_update: None,
+            last_sample: None,
+            ema_fps: None,
+        }
+    }
+
+    /// Feed a progress sample, returning (fps_encoding, eta_secs)
+    fn update(&mut self, frame: u64, percentage: u32, now: Instant) -> (Option<f64>, Option<f64>) {
+        let first = *self.first_update.get_or_insert(now);
+
+        if let Some((last_time, last_frame)) = self.last_sample {
+            let dt = now.duration_since(last_time).as_secs_f64();
+            if dt > 0.0 && frame >= last_frame {
+                let fps = (frame - last_frame) as f64 / dt;
+                self.ema_fps = Some(match self.ema_fps {
+                    Some(ema) => Self::SMOOTHING * fps + (1.0 - Self::SMOOTHING) * ema,
+                    None => fps,
+                });
+            }
+        }
+        self.last_sample = Some((now, frame));
+
+        if now.duration_since(first) < Self::WARMUP {
+            return (self.ema_fps, None);
+        }
+
+        let eta = match (self.ema_fps, percentage) {
+            (Some(fps), p) if fps > 0.0 && p > 0 => {
+                let total_frames = frame as f64 * 100.0 / p as f64;
+                Some(((total_frames - frame as f64).max(0.0)) / fps)
+            }
+            _ => None,
+        };
+
+        (self.ema_fps, eta)
+    }
+}
+
+/// Time limits applied while waiting on a melt process
+#[derive(Clone, Copy)]
+struct RenderLimits {
+    /// Absolute deadline for the whole render, with the configured seconds for messages
+    deadline: Option<(Instant, u64)>,
+    /// Abort when no progress line arrives within this window
+    stall_window: Option<Duration>,
+}
+
+impl RenderLimits {
+    fn from_options(options: &RenderOptions) -> Self {
+        Self {
+            deadline: options
+                .timeout_secs
+                .map(|secs| (Instant::now() + Duration::from_secs(secs), secs)),
+            stall_window: options.stall_timeout_secs.map(Duration::from_secs),
+        }
+    }
+}
+
+/// Parse a melt progress line ("Current Frame:  42, percentage:  5")
+fn parse_progress_line(line: &str) -> Option<(u64, u32)> {
+    let frame_part = line.split("Current Frame:").nth(1)?;
+    let (frame, rest) = frame_part.split_once(',')?;
+    let percentage = rest.split("percentage:").nth(1)?;
+
+    Some((frame.trim().parse().ok()?, percentage.trim().parse().ok()?))
+}
+
+/// Call `on_line` for each `\r`- or `\n`-terminated line read from `pipe`
+fn for_each_melt_line<R: Read>(pipe: R, mut on_line: impl FnMut(&str)) {
+    let mut line = Vec::new();
+    for byte in BufReader::new(pipe).bytes() {
+        let Ok(byte) = byte else { break };
+        if byte == b'\r' || byte == b'\n' {
+            if !line.is_empty() {
+                on_line(&String::from_utf8_lossy(&line));
+                line.clear();
+            }
+        } else {
+            line.push(byte);
+        }
+    }
+    if !line.is_empty() {
+        on_line(&String::from_utf8_lossy(&line));
+    }
+}
+
+fn is_job_cancelled(state: &MeltState, job_id: &str) -> bool {
+    state
+        .active_jobs
+        .lock()
+        .map(|jobs| jobs.get(job_id).copied().unwrap_or(false))
+        .unwrap_or(false)
+}
+
+/// Drop every queued render, flag every running one as cancelled and kill
+/// all tracked melt processes. Each job's wait loop then reaps its child and
+/// removes it from `active_jobs`. Returns the ids of the jobs stopped.
+pub fn cancel_all_jobs(state: &MeltState) -> Vec<String> {
+    let mut stopped: Vec<String> = match state.queue.lock() {
+        Ok(mut queue) => queue.drain(..).map(|job| job.job_id).collect(),
+        Err(_) => Vec::new(),
+    };
+
+    if let Ok(mut jobs) = state.active_jobs.lock() {
+        for (job_id, cancelled) in jobs.iter_mut() {
+            *cancelled = true;
+            stopped.push(job_id.clone());
+        }
+    }
+
+    if let Ok(mut children) = state.children.lock() {
+        for child in children.values_mut() {
+            let _ = child.kill();
+        }
+    }
+
+    stopped
+}
+
+/// Stop all renders before the app exits. Unlike `cancel_all_jobs` this
+/// reaps the melt processes itself, since the jobs' wait loops won't get to
+/// run again.
+pub fn shutdown(state: &MeltState) -> Vec<String> {
+    let stopped = cancel_all_jobs(state);
+    if let Ok(mut children) = state.children.lock() {
+        for (_, mut child) in children.drain() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+    stopped
+}
+
+/// Kill and reap a tracked melt process
+fn kill_tracked_child(state: &MeltState, job_id: &str) -> Result<(), String> {
+    let mut children = state.children.lock().map_err(|e| e.to_string())?;
+    if let Some(mut child) = children.remove(job_id) {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    Ok(())
+}
+
+/// Spawn melt, track the child in MeltState and wait for it to exit,
+/// killing it if the job is cancelled, times out or stops reporting progress.
+async fn run_melt_process(
+    state: &MeltState,
+    app: Option<&AppHandle>,
+    job_id: &str,
+    pass: u32,
+    mut cmd: Command,
+    limits: RenderLimits,
+) -> Result<MeltRunOutcome, String> {
+    let mut child = cmd
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run melt: {}", e))?;
+
+    let progress = Arc::new(Mutex::new(MeltProgress {
+        frame: 0,
+        percentage: 0,
+        last_update: Instant::now(),
+    }));
+
+    // Drain stderr on a thread so melt never blocks on a full pipe.
+    // Progress lines update the shared progress; everything else is kept
+    // for error reporting.
+    let stderr_pipe = child.stderr.take();
+    let reader_progress = progress.clone();
+    let stderr_reader = std::thread::spawn(move || {
+        let mut stderr = String::new();
+        if let Some(pipe) = stderr_pipe {
+            for_each_melt_line(pipe, |line| match parse_progress_line(line) {
+                Some((frame, percentage)) => {
+                    if let Ok(mut p) = reader_progress.lock() {
+                        p.frame = frame;
+                        p.percentage = percentage;
+                        p.last_update = Instant::now();
+                    }
+                }
+                None => {
+                    stderr.push_str(line);
+                    stderr.push('\n');
+                }
+            });
+        }
+        stderr
+    });
+
+    {
+        let mut children = state.children.lock().map_err(|e| e.to_string())?;
+        children.insert(job_id.to_string(), child);
+    }
+
+    let mut eta = EtaEstimator::new();
+    let mut last_reported_frame = None;
+
+    // Errors while polling must not leave the child running untracked
+    let outcome: Result<MeltRunOutcome, String> = async {
+        Ok(loop {
+            if let Some(app) = app {
+                let current = *progress.lock().map_err(|e| e.to_string())?;
+                if current.percentage > 0 && last_reported_frame != Some(current.frame) {
+                    last_reported_frame = Some(current.frame);
+                    let (fps_encoding, eta_secs) =
+                        eta.update(current.frame, current.percentage, current.last_update);
+                    let _ = app.emit(
+                        "melt-render-progress",
+                        MeltRenderProgress {
+                            job_id: job_id.to_string(),
+                            pass,
+                            frame: current.frame,
+                            percentage: current.percentage,
+                            fps_encoding,
+                            eta_secs,
+                        },
+                    );
+                }
+            }
+
+            if is_job_cancelled(state, job_id) {
+                kill_tracked_child(state, job_id)?;
+                break MeltRunOutcome::Cancelled;
+            }
+
+            if let Some((deadline, secs)) = limits.deadline {
+                if Instant::now() >= deadline {
+                    kill_tracked_child(state, job_id)?;
+                    break MeltRunOutcome::TimedOut(secs);
+                }
+            }
+
+            if let Some(window) = limits.stall_window {
+                let last_update = progress.lock().map_err(|e| e.to_string())?.last_update;
+                if last_update.elapsed() >= window {
+                    kill_tracked_child(state, job_id)?;
+                    break MeltRunOutcome::Stalled(window.as_secs());
+                }
+            }
+
+            {
+                let mut children = state.children.lock().map_err(|e| e.to_string())?;
+                let exited = match children.get_mut(job_id) {
+                    Some(child) => child
+                        .try_wait()
+                        .map_err(|e| format!("Failed to wait for melt: {}", e))?,
+                    None => break MeltRunOutcome::Cancelled,
+                };
+                if let Some(status) = exited {
+                    children.remove(job_id);
+                    break MeltRunOutcome::Finished {
+                        success: status.success(),
+                        stderr: String::new(),
+                    };
+                }
+            }
+
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        })
+    }
+    .await;
+    if outcome.is_err() {
+        let _ = kill_tracked_child(state, job_id);
+    }
+
+    // The reader finishes once melt closes stderr; wait off the async runtime
+    let stderr = tauri::async_runtime::spawn_blocking(move || stderr_reader.join().unwrap_or_default())
+        .await
+        .unwrap_or_default();
+
+    Ok(match outcome? {
+        MeltRunOutcome::Finished { success, .. } => MeltRunOutcome::Finished { success, stderr },
+        other => other,
+    })
+}
+
+/// Run a full render (one or two passes) for a registered job
+async fn execute_render(
+    state: &MeltState,
+    app: Option<&AppHandle>,
+    job_id: &str,
+    mlt_xml: &str,
+    output_path: &str,
+    options: &RenderOptions,
+) -> Result<RenderResult, String> {
+    // Find melt
+    let melt_path = find_melt(state).ok_or("melt not found on system")?;
+
+    let substituted;
+    let mlt_xml = if options.use_originals {
+        substituted = substitute_proxy_originals(mlt_xml);
+        substituted.as_str()
+    } else {
+        mlt_xml
+    };
+
+    // Fail fast on moved/deleted media instead of a cryptic mid-render error.
+    // Documents our parser can't read are left for melt to judge.
+    if let Some(Ok(preflight)) = options
+        .check_media
+        .then(|| preflight_resources(mlt_xml, None))
+    {
+        if !preflight.ok {
+            let names: Vec<String> = preflight
+                .missing
+                .iter()
+                .map(|m| format!("{} ({}, {})", m.producer_id, m.resource, m.reason))
+                .collect();
+            return Ok(RenderResult {
+                success: false,
+                error: Some(format!("Missing media: {}", names.join(", "))),
+                output_path: None,
+                segment_count: None,
+            });
+        }
+    }
+
+    // Reject incompatible options before touching the filesystem
+    let mut consumer_args = if options.audio_only {
+        build_audio_consumer_args(options, output_path)?
+    } else {
+        build_consumer_args(options)?
+    };
+    let range_args = build_range_args(options, mlt_xml)?;
+    let filter_args = build_filter_args(options, mlt_xml)?;
+    let audio_replacement_args = build_audio_replacement_args(options, mlt_xml)?;
+    check_disk_space(options, mlt_xml, output_path)?;
+
+    // HLS renders into a directory: a playlist plus numbered segments
+    let hls_playlist = match options.output_format {
+        Some(RenderFormat::Hls { .. }) => Some(prepare_hls_dir(output_path)?),
+        _ => None,
+    };
+    if let Some(playlist) = &hls_playlist {
+        let segments = playlist.with_file_name("segment_%05d.ts");
+        consumer_args.push(format!("hls_segment_filename={}", segments.to_string_lossy()));
+    }
+    let output_path = hls_playlist
+        .as_ref()
+        .map_or_else(|| output_path.to_string(), |p| p.to_string_lossy().to_string());
+    let output_path = output_path.as_str();
+
+    // Create temp XML file
+    let temp_dir = get_mlt_temp_dir_path()?;
+    let xml_path = temp_dir.join(format!("{}.mlt", job_id));
+    let passlog = temp_dir.join(format!("{}-passlog", job_id));
+
+    // Errors after this point must not leak temp files or the job slot
+    let outcome: Result<MeltRunOutcome, String> = async {
+        let profile_args = build_profile_args(&melt_path, options, &temp_dir, job_id)?;
+
+        fs::write(&xml_path, mlt_xml)
+            .map_err(|e| format!("Failed to write MLT XML: {}", e))?;
+
+        // Register job (queued jobs already hold a slot)
+        {
+            let mut jobs = state.active_jobs.lock().map_err(|e| e.to_string())?;
+            jobs.entry(job_id.to_string()).or_insert(false);
+        }
+
+        // The timeout covers every pass, so compute the deadline once
+        let limits = RenderLimits::from_options(options);
+
+        // Single pass, or pass=1 then pass=2 sharing a passlog
+        let passes: Vec<Vec<String>> = if options.two_pass {
+            let passlog_arg = format!("passlogfile={}", passlog.to_string_lossy());
+            vec![
+                vec!["pass=1".to_string(), passlog_arg.clone()],
+                vec!["pass=2".to_string(), passlog_arg],
+            ]
+        } else {
+            vec![Vec::new()]
+        };
+
+        let mut outcome = MeltRunOutcome::Cancelled;
+        for (pass_index, pass_args) in passes.iter().enumerate() {
+            // Build melt command
+            let mut cmd = melt_command(&melt_path);
+            cmd.args(&profile_args);
+            cmd.arg(xml_path.to_string_lossy().to_string());
+            cmd.args(&range_args);
+            cmd.args(&filter_args);
+            cmd.args(&audio_replacement_args);
+
+            // Consumer arguments for output
+            cmd.arg("-consumer");
+            cmd.arg(format!("avformat:{}", output_path));
+            cmd.args(&consumer_args);
+            cmd.args(pass_args);
+
+            // Capture progress output
+            cmd.arg("-progress");
+
+            let pass = pass_index as u32 + 1;
+            outcome = run_melt_process(state, app, job_id, pass, cmd, limits).await?;
+            if !matches!(outcome, MeltRunOutcome::Finished { success: true, .. }) {
+                break;
+            }
+        }
+        Ok(outcome)
+    }
+    .await;
+
+    if options.two_pass {
+        remove_passlog_files(&temp_dir, &passlog);
+    }
+
+    // Cleanup job registration
+    if let Ok(mut jobs) = state.active_jobs.lock() {
+        jobs.remove(job_id);
+    }
+
+    // Clean up temp XML and any custom profile
+    let _ = fs::remove_file(&xml_path);
+    let _ = fs::remove_file(temp_dir.join(format!("{}.profile", job_id)));
+
+    let mut result = match outcome? {
+        MeltRunOutcome::Finished { success: true, .. } if options.verify_output => {
+            let path = output_path.to_string();
+            let verification = tauri::async_runtime::spawn_blocking(move || verify_output_file(&path))
+                .await
+                .map_err(|e| format!("Task join error: {}", e))?;
+            Ok(match verification.error {
+                None => RenderResult {
+                    success: true,
+                    error: None,
+                    output_path: Some(output_path.to_string()),
+                    segment_count: None,
+                },
+                Some(error) => RenderResult {
+                    success: false,
+                    error: Some(format!("render output failed verification: {}", error)),
+                    output_path: None,
+                    segment_count: None,
+                },
+            })
+        }
+        MeltRunOutcome::Finished { success: true, .. } => Ok(RenderResult {
+            success: true,
+            error: None,
+            output_path: Some(output_path.to_string()),
+            segment_count: None,
+        }),
+        MeltRunOutcome::Finished { stderr, .. } => Ok(RenderResult {
+            success: false,
+            error: Some(format!("melt exited with error: {}", stderr)),
+            output_path: None,
+            segment_count: None,
+        }),
+        MeltRunOutcome::Cancelled => Ok(RenderResult {
+            success: false,
+            error: Some("Render cancelled".to_string()),
+            output_path: None,
+            segment_count: None,
+        }),
+        MeltRunOutcome::TimedOut(secs) => Ok(RenderResult {
+            success: false,
+            error: Some(format!("render timed out after {} s", secs)),
+            output_path: None,
+            segment_count: None,
+        }),
+        MeltRunOutcome::Stalled(secs) => Ok(RenderResult {
+            success: false,
+            error: Some(format!("render stalled: no progress for {} s", secs)),
+            output_path: None,
+            segment_count: None,
+        }),
+    }?;
+
+    if let (true, Some(playlist)) = (result.success, &hls_playlist) {
+        result.segment_count = fs::read_to_string(playlist)
+            .ok()
+            .map(|text| count_hls_segments(&text));
+    }
+    Ok(result)
+}
+
+// ============================================
+// OUTPUT VERIFICATION
+// ============================================
+
+/// Check that a rendered file is non-empty, has a positive duration and
+/// that its first frame decodes. melt can exit 0 and still leave a broken file.
+fn verify_output_file(path: &str) -> RenderVerification {
+    let failed = |error: String| RenderVerification {
+        valid: false,
+        error: Some(error),
+        duration_secs: None,
+        width: None,
+        height: None,
+    };
+
+    match fs::metadata(path) {
+        Ok(meta) if meta.len() == 0 => return failed("output file is empty".to_string()),
+        Ok(_) => {}
+        Err(e) => return failed(format!("output file is missing: {}", e)),
+    }
+
+    let info = match video_decoder::get_video_info(path) {
+        Ok(info) => info,
+        Err(e) => return failed(e.message),
+    };
+    if info.duration_secs <= 0.0 {
+        return failed("output has zero duration".to_string());
+    }
+    if let Err(e) = video_decoder::get_first_frame(path) {
+        return failed(format!("first frame does not decode: {}", e.message));
+    }
+
+    RenderVerification {
+        valid: true,
+        error: None,
+        duration_secs: Some(info.duration_secs),
+        width: Some(info.display_width),
+        height: Some(info.display_height),
+    }
+}
+
+// ============================================
+// PREVIEW RENDERS
+// ============================================
+
+/// Preview renders are this tall; width follows the project aspect
+const PREVIEW_HEIGHT: u32 = 480;
+
+/// Preview frame rate (frame_rate_den is forced to 1)
+const PREVIEW_FPS: u32 = 15;
+
+/// Low-resolution, fast-preset options for a rough-cut preview of `mlt_xml`
+fn preview_options(mlt_xml: &str, in_frame: Option<u64>, out_frame: Option<u64>) -> RenderOptions {
+    let aspect = mlt_parser::parse_mlt(mlt_xml)
+        .ok()
+        .and_then(|project| project.profile)
+        .and_then(|profile| match (profile.width, profile.height) {
+            (Some(w), Some(h)) if w > 0 && h > 0 => Some(w as f64 / h as f64),
+            _ => None,
+        })
+        .unwrap_or(16.0 / 9.0);
+    let width = ((PREVIEW_HEIGHT as f64 * aspect).round() as u32).max(2) & !1;
+
+    RenderOptions {
+        output_format: Some(RenderFormat::Mp4H264),
+        width: Some(width),
+        height: Some(PREVIEW_HEIGHT),
+        frame_rate: Some(PREVIEW_FPS),
+        preset: Some("ultrafast".to_string()),
+        crf: Some(30),
+        in_frame,
+        out_frame,
+        extra_consumer_args: vec!["frame_rate_den=1".to_string()],
+        ..Default::default()
+    }
+}
+
+// ============================================
+// RENDER PRESETS
+// ============================================
+
+/// A named, fully populated set of RenderOptions for a common target
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RenderPreset {
+    /// Stable key passed to `render_preset`, e.g. "youtube_1080p"
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub options: RenderOptions,
+}
+
+/// H.264/AAC web delivery at a fixed size and integer frame rate
+fn h264_preset_options(
+    width: u32,
+    height: u32,
+    fps: u32,
+    crf: u32,
+    audio_bitrate: &str,
+) -> RenderOptions {
+    RenderOptions {
+        output_format: Some(RenderFormat::Mp4H264),
+        video_codec: Some("libx264".to_string()),
+        audio_codec: Some("aac".to_string()),
+        audio_bitrate: Some(audio_bitrate.to_string()),
+        crf: Some(crf),
+        width: Some(width),
+        height: Some(height),
+        frame_rate: Some(fps),
+        preset: Some("slow".to_string()),
+        // Integer fps, a keyframe every 2 s, and the index up front so
+        // uploads start processing before the whole file arrives
+        extra_consumer_args: vec![
+            "frame_rate_den=1".to_string(),
+            format!("g={}", fps * 2),
+            "movflags=+faststart".to_string(),
+        ],
+        ..Default::default()
+    }
+}
+
+/// 1080x1920 H.264 for vertical feeds. The custom profile makes melt
+/// compose a 9:16 frame instead of letterboxing a landscape project.
+fn vertical_preset_options(fps: u32, crf: u32, audio_bitrate: &str) -> RenderOptions {
+    RenderOptions {
+        profile: Some(RenderProfile::Custom {
+            width: 1080,
+            height: 1920,
+            frame_rate_num: fps,
+            frame_rate_den: 1,
+            display_aspect_num: Some(9),
+            display_aspect_den: Some(16),
+        }),
+        ..h264_preset_options(1080, 1920, fps, crf, audio_bitrate)
+    }
+}
+
+/// Built-in presets, in the order the export dialog lists them
+fn render_presets() -> Vec<RenderPreset> {
+    let preset = |id: &str, name: &str, description: &str, options: RenderOptions| RenderPreset {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        options,
+    };
+    vec![
+        preset(
+            "youtube_1080p",
+            "YouTube 1080p",
+            "1920x1080, 30 fps, H.264 + AAC 192 kbps",
+            h264_preset_options(1920, 1080, 30, 18, "192k"),
+        ),
+        preset(
+            "youtube_4k",
+            "YouTube 4K",
+            "3840x2160, 30 fps, H.264 + AAC 192 kbps",
+            h264_preset_options(3840, 2160, 30, 18, "192k"),
+        ),
+        preset(
+            "instagram_square",
+            "Instagram square",
+            "1080x1080, 30 fps, H.264 + AAC 128 kbps",
+            h264_preset_options(1080, 1080, 30, 20, "128k"),
+        ),
+        preset(
+            "instagram_reels",
+            "Instagram Reels",
+            "1080x1920 vertical, 30 fps, H.264 + AAC 128 kbps",
+            vertical_preset_options(30, 20, "128k"),
+        ),
+        preset(
+            "tiktok",
+            "TikTok",
+            "1080x1920 vertical, 30 fps, H.264 + AAC 128 kbps",
+            vertical_preset_options(30, 20, "128k"),
+        ),
+        preset(
+            "prores_master",
+            "ProRes 1080p master",
+            "1920x1080, 30 fps, ProRes 422 HQ + PCM for archiving or grading",
+            RenderOptions {
+                output_format: Some(RenderFormat::ProRes),
+                width: Some(1920),
+                height: Some(1080),
+                frame_rate: Some(30),
+                extra_consumer_args: vec!["frame_rate_den=1".to_string()],
+                ..Default::default()
+            },
+        ),
+    ]
+}
+
+/// List the built-in render presets for the export dialog
+#[tauri::command]
+pub fn list_render_presets() -> Vec<RenderPreset> {
+    render_presets()
+}
+
+/// Resolve a preset id to its RenderOptions
+#[tauri::command]
+pub fn render_preset(name: String) -> Result<RenderOptions, String> {
+    let presets = render_presets();
+    let ids: Vec<&str> = presets.iter().map(|p| p.id.as_str()).collect();
+    let unknown = format!("Unknown render preset '{}'. Expected one of: {}", name, ids.join(", "));
+    presets
+        .iter()
+        .find(|p| p.id == name)
+        .map(|p| p.options.clone())
+        .ok_or(unknown)
+}
+
+// ============================================
+// RENDER ESTIMATES
+// ============================================
+
+/// Length of the timing benchmark render
+const BENCHMARK_SECS: f64 = 3.0;
+
+/// How much an estimate can be trusted
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EstimateConfidence {
+    /// Codec heuristics only; no benchmark was run
+    Low,
+    /// Extrapolated from a benchmark of part of the render
+    Medium,
+    /// The benchmark covered the whole render
+    High,
+}
+
+/// Expected output size and render time
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RenderEstimate {
+    pub estimated_bytes: u64,
+    /// None when the benchmark couldn't run
+    pub estimated_secs: Option<f64>,
+    pub confidence: EstimateConfidence,
+}
+
+/// Output size from codec heuristics
+fn heuristic_output_bytes(options: &RenderOptions, shape: &RenderShape) -> u64 {
+    let audio_bps = shape.audio_bps.unwrap_or(192_000.0);
+    let video_bps = if options.audio_only {
+        0.0
+    } else {
+        shape.width as f64
+            * shape.height as f64
+            * shape.fps
+            * typical_bits_per_pixel(&shape.vcodec, options.crf)
+    };
+    ((video_bps + audio_bps) * shape.duration_secs / 8.0) as u64
+}
+
+/// Render `frames` project frames from `start_frame` into the temp dir with
+/// the user's options. Returns wall time and output size.
+async fn benchmark_render(
+    state: &MeltState,
+    mlt_xml: &str,
+    options: &RenderOptions,
+    extension: &str,
+    start_frame: u64,
+    frames: u64,
+) -> Option<(f64, u64)> {
+    let job_id = format!("estimate-{}", uuid::Uuid::new_v4());
+    let output_path = get_mlt_temp_dir_path()
+        .ok()?
+        .join(format!("{}.{}", job_id, extension));
+    let options = RenderOptions {
+        in_frame: Some(start_frame),
+        out_frame: Some(start_frame + frames.max(1) - 1),
+        verify_output: false,
+        // Same codecs as HLS, but a single file whose size can be measured
+        output_format: match options.output_format {
+            Some(RenderFormat::Hls { .. }) => Some(RenderFormat::Mp4H264),
+            format => format,
+        },
+        // A replacement track matches the full render, not the benchmark slice
+        audio_action: match &options.audio_action {
+            Some(AudioAction::ReplaceWith { .. }) => None,
+            action => action.clone(),
+        },
+        ..options.clone()
+    };
+
+    let started = Instant::now();
+    let result = execute_render(state, None, &job_id, mlt_xml, &output_path.to_string_lossy(), &options).await;
+    let elapsed = started.elapsed().as_secs_f64();
+    let size = fs::metadata(&output_path).map(|m| m.len()).ok();
+    let _ = fs::remove_file(&output_path);
+
+    match (result, size) {
+        (Ok(RenderResult { success: true, .. }), Some(size)) => Some((elapsed, size)),
+        _ => None,
+    }
+}
+
+/// Estimate a render's output size and duration before committing to it.
+///
+/// The size starts from codec/CRF heuristics. Unless `benchmark` is false, a
+/// few seconds from the middle of the range are rendered with the same
+/// options (plus a one-frame render to measure melt's startup cost) and both
+/// figures are extrapolated from that. `output_path` only supplies the
+/// container extension; nothing is written there. Benchmarking an audio_only
+/// export requires it, since the extension picks the audio format.
+#[tauri::command]
+pub async fn estimate_render(
+    mlt_xml: String,
+    options: RenderOptions,
+    output_path: Option<String>,
+    benchmark: Option<bool>,
+    state: State<'_, MeltState>,
+) -> Result<RenderEstimate, String> {
+    build_range_args(&options, &mlt_xml)?;
+    let shape = render_shape(&options, &mlt_xml).ok_or("Could not determine the render duration")?;
+    let mut estimate = RenderEstimate {
+        estimated_bytes: heuristic_output_bytes(&options, &shape),
+        estimated_secs: None,
+        confidence: EstimateConfidence::Low,
+    };
+    if !benchmark.unwrap_or(true) || shape.frames == 0 {
+        return Ok(estimate);
+    }
+    if options.audio_only && output_path.is_none() {
+        return Err("audio_only estimates need output_path to pick the audio format; pass it or set benchmark to false".to_string());
+    }
+
+    let extension = output_path
+        .as_deref()
+        .and_then(|path| std::path::Path::new(path).extension())
+        .map(|ext| ext.to_string_lossy().to_string())
+        .or_else(|| options.output_format.map(|f| f.container().to_string()))
+        .unwrap_or_else(|| "mp4".to_string());
+
+    let project_fps = shape.frames as f64 / shape.duration_secs.max(f64::EPSILON);
+    let bench_frames = ((BENCHMARK_SECS * project_fps).ceil() as u64).clamp(1, shape.frames);
+    let bench_start = shape.start_frame + (shape.frames - bench_frames) / 2;
+
+    let Some((bench_secs, bench_bytes)) =
+        benchmark_render(&state, &mlt_xml, &options, &extension, bench_start, bench_frames).await
+    else {
+        return Ok(estimate);
+    };
+
+    if bench_frames == shape.frames {
+        estimate.estimated_bytes = bench_bytes;
+        estimate.estimated_secs = Some(bench_secs);
+        estimate.confidence = EstimateConfidence::High;
+        return Ok(estimate);
+    }
+
+    // Separate melt's fixed startup (loading producers) from per-frame cost
+    let startup_secs = benchmark_render(&state, &mlt_xml, &options, &extension, bench_start, 1)
+        .await
+        .map_or(0.0, |(secs, _)| secs.min(bench_secs));
+    let per_frame_secs = (bench_secs - startup_secs) / bench_frames as f64;
+
+    estimate.estimated_bytes = (bench_bytes as f64 * shape.frames as f64 / bench_frames as f64) as u64;
+    estimate.estimated_secs = Some(startup_secs + per_frame_secs * shape.frames as f64);
+    estimate.confidence = EstimateConfidence::Medium;
+    Ok(estimate)
+}
+
+// ============================================
+// RENDER HISTORY
+// ============================================
+
+/// Oldest entries are dropped beyond this many
+const MAX_RENDER_HISTORY: usize = 500;
+
+fn render_history_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    Ok(home.join(".dreamcloud").join("render_history.json"))
+}
+
+fn load_render_history() -> Vec<RenderHistoryEntry> {
+    render_history_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_render_history(entries: &[RenderHistoryEntry]) -> Result<(), String> {
+    let path = render_history_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create history directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize render history: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write render history: {}", e))
+}
+
+fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Run an export and append its outcome to the render history.
+/// History write failures are logged, never surfaced as render failures.
+async fn execute_and_record(
+    state: &MeltState,
+    app: Option<&AppHandle>,
+    job_id: &str,
+    mlt_xml: &str,
+    output_path: &str,
+    options: &RenderOptions,
+) -> Result<RenderResult, String> {
+    let started_at = unix_millis();
+    let started = Instant::now();
+    let result = execute_render(state, app, job_id, mlt_xml, output_path, options).await;
+
+    let (success, error) = match &result {
+        Ok(r) => (r.success, r.error.clone()),
+        Err(e) => (false, Some(e.clone())),
+    };
+    let entry = RenderHistoryEntry {
+        job_id: job_id.to_string(),
+        output_path: output_path.to_string(),
+        options: options.clone(),
+        started_at,
+        finished_at: unix_millis(),
+        duration_secs: started.elapsed().as_secs_f64(),
+        success,
+        error,
+        output_size_bytes: fs::metadata(output_path).ok().filter(|_| success).map(|m| m.len()),
+    };
+
+    if let Ok(_guard) = state.history_lock.lock() {
+        let mut history = load_render_history();
+        history.push(entry);
+        let overflow = history.len().saturating_sub(MAX_RENDER_HISTORY);
+        history.drain(..overflow);
+        if let Err(e) = save_render_history(&history) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+
+    result
+}
+
+// ============================================
+// RENDER QUEUE
+// ============================================
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RenderQueuedEvent {
+    pub job_id: String,
+    pub position: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RenderStartedEvent {
+    pub job_id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RenderFinishedEvent {
+    pub job_id: String,
+    pub success: bool,
+    pub error: Option<String>,
+    pub output_path: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RenderJobInfo {
+    pub job_id: String,
+    /// "queued", "running" or "cancelling"
+    pub status: String,
+    /// Zero-based position in the queue (None once running)
+    pub queue_position: Option<usize>,
+}
+
+/// Emit the current position of every queued job
+fn emit_queue_positions(app: &AppHandle, state: &MeltState) {
+    if let Ok(queue) = state.queue.lock() {
+        for (position, job) in queue.iter().enumerate() {
+            let _ = app.emit(
+                "render-queued",
+                RenderQueuedEvent {
+                    job_id: job.job_id.clone(),
+                    position,
+                },
+            );
+        }
+    }
+}
+
+/// Start queued jobs until the concurrency limit is reached
+fn pump_render_queue(app: &AppHandle) {
+    let state = app.state::<MeltState>();
+
+    loop {
+        let next = {
+            // Proxy, extract and estimate jobs also live in active_jobs,
+            // so only renders started here count toward the limit
+            let (Ok(mut running), Ok(mut queue), Ok(max)) = (
+                state.running_renders.lock(),
+                state.queue.lock(),
+                state.max_concurrent.lock(),
+            ) else {
+                return;
+            };
+            if running.len() >= *max {
+                return;
+            }
+            match queue.pop_front() {
+                Some(job) => {
+                    // Reserve the slot before the task starts so the limit holds
+                    running.insert(job.job_id.clone());
+                    job
+                }
+                None => return,
+            }
+        };
+
+        if let Ok(mut jobs) = state.active_jobs.lock() {
+            jobs.insert(next.job_id.clone(), false);
+        }
+
+        let _ = app.emit(
+            "render-started",
+            RenderStartedEvent {
+                job_id: next.job_id.clone(),
+            },
+        );
+        emit_queue_positions(app, &state);
+
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let state = app.state::<MeltState>();
+            let result = execute_and_record(
+                &state,
+                Some(&app),
+                &next.job_id,
+                &next.mlt_xml,
+                &next.output_path,
+                &next.options,
+            )
+            .await;
+
+            // Make sure the slot is released even if the render errored early
+            if let Ok(mut jobs) = state.active_jobs.lock() {
+                jobs.remove(&next.job_id);
+            }
+            if let Ok(mut running) = state.running_renders.lock() {
+                running.remove(&next.job_id);
+            }
+
+            let event = match result {
+                Ok(r) => RenderFinishedEvent {
+                    job_id: next.job_id.clone(),
+                    success: r.success,
+                    error: r.error,
+                    output_path: r.output_path,
+                },
+                Err(e) => RenderFinishedEvent {
+                    job_id: next.job_id.clone(),
+                    success: false,
+                    error: Some(e),
+                    output_path: None,
+                },
+            };
+            let _ = app.emit("render-finished", event);
+
+            pump_render_queue(&app);
+        });
+    }
+}
+
+// ============================================
+// PROXIES
+// ============================================
+
+/// Codec used for editing proxies, chosen for decode speed over size
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyCodec {
+    /// Low-latency H.264 (small files, decent scrubbing)
+    #[default]
+    H264,
+    /// Intra-only MJPEG (largest files, every frame is a keyframe)
+    Mjpeg,
+    /// ProRes 422 Proxy (intra-only, editing round-trips)
+    ProresProxy,
+}
+
+impl ProxyCodec {
+    fn consumer_args(&self) -> Vec<String> {
+        let args: &[&str] = match self {
+            ProxyCodec::H264 => &[
+                "vcodec=libx264",
+                "preset=ultrafast",
+                "tune=fastdecode",
+                "crf=28",
+                "g=12",
+                "acodec=aac",
+                "ab=128k",
+            ],
+            ProxyCodec::Mjpeg => &["vcodec=mjpeg", "qscale=5", "acodec=pcm_s16le"],
+            ProxyCodec::ProresProxy => &["vcodec=prores_ks", "vprofile=0", "acodec=pcm_s16le"],
+        };
+        args.iter().map(|a| a.to_string()).collect()
+    }
+}
+
+/// A generated proxy and the original it stands in for
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProxyMapping {
+    pub source_path: String,
+    pub proxy_path: String,
+    pub width: u32,
+    pub height: u32,
+    pub codec: ProxyCodec,
+}
+
+fn proxy_mappings_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    Ok(home.join(".dreamcloud").join("proxies.json"))
+}
+
+fn load_proxy_mappings() -> Vec<ProxyMapping> {
+    proxy_mappings_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Record a proxy, replacing any earlier proxy for the same source
+fn record_proxy_mapping(state: &MeltState, mapping: ProxyMapping) -> Result<(), String> {
+    let _guard = state.proxy_lock.lock().map_err(|e| e.to_string())?;
+    let mut mappings = load_proxy_mappings();
+    mappings.retain(|m| m.source_path != mapping.source_path && m.proxy_path != mapping.proxy_path);
+    mappings.push(mapping);
+
+    let path = proxy_mappings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create proxy directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&mappings)
+        .map_err(|e| format!("Failed to serialize proxy mappings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write proxy mappings: {}", e))
+}
+
+/// Point `resource` properties that name a registered proxy back at its
+/// original
+fn substitute_proxy_originals(mlt_xml: &str) -> String {
+    let originals: HashMap<String, String> = load_proxy_mappings()
+        .into_iter()
+        .map(|m| (m.proxy_path, m.source_path))
+        .collect();
+    replace_resource_values(mlt_xml, &originals)
+}
+
+/// Rewrite each `<property name="resource">` whose whole value is a key of
+/// `replacements`. Paths that merely contain a key, and other properties,
+/// are left alone. Malformed documents are returned unchanged for melt to
+/// report.
+fn replace_resource_values(mlt_xml: &str, replacements: &HashMap<String, String>) -> String {
+    if replacements.is_empty() {
+        return mlt_xml.to_string();
+    }
+
+    let mut reader = Reader::from_str(mlt_xml);
+    let mut out = String::with_capacity(mlt_xml.len());
+    let mut copied = 0;
+    let mut in_resource = false;
+    loop {
+        let start = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(element)) => {
+                in_resource = element.name().as_ref() == b"property"
+                    && element
+                        .try_get_attribute("name")
+                        .ok()
+                        .flatten()
+                        .is_some_and(|name| name.value.as_ref() == b"resource");
+            }
+            Ok(Event::Text(text)) if in_resource => {
+                let value = text.unescape().map(|value| value.trim().to_string());
+                if let Some(original) = value.ok().and_then(|value| replacements.get(&value)) {
+                    out.push_str(&mlt_xml[copied..start]);
+                    out.push_str(&escape_xml(original));
+                    copied = reader.buffer_position() as usize;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => in_resource = false,
+            Err(_) => return mlt_xml.to_string(),
+        }
+    }
+    out.push_str(&mlt_xml[copied..]);
+    out
+}
+
+/// Refuse a proxy path that is the source itself: a failed proxy is deleted,
+/// which would take the original with it
+fn check_proxy_path(source_path: &str, proxy_path: &str) -> Result<(), String> {
+    // A proxy that doesn't exist yet can't be the source
+    let Ok(proxy) = std::path::Path::new(proxy_path).canonicalize() else {
+        return Ok(());
+    };
+    if std::path::Path::new(source_path).canonicalize().is_ok_and(|source| source == proxy) {
+        return Err(format!("Proxy '{}' would overwrite its source", proxy_path));
+    }
+    Ok(())
+}
+
+/// Proxy height for a target width, keeping the source aspect and an even size
+fn proxy_dimensions(source_width: u32, source_height: u32, width: u32) -> (u32, u32) {
+    let width = (width.min(source_width).max(2)) & !1;
+    let height = (source_height as u64 * width as u64 / source_width.max(1) as u64) as u32;
+    (width, height.max(2) & !1)
+}
+
+// ============================================
+// TAURI COMMANDS
+// ============================================
+
+/// Report whether melt is available. The probe runs off the main thread
+/// and its result is reused for `MELT_CHECK_TTL`, so a melt installed
+/// later is picked up on the next check after expiry; pass
+/// `force_refresh` to probe immediately.
+#[tauri::command]
+pub async fn check_melt(
+    force_refresh: Option<bool>,
+    state: State<'_, MeltState>,
+) -> Result<MeltCheckResult, String> {
+    if !force_refresh.unwrap_or(false) {
+        if let Some(cached) = cached_melt_check(&state) {
+            return Ok(cached);
+        }
+    }
+
+    let configured = state.melt_path.lock().map_err(|e| e.to_string())?.clone();
+    let resource_dir = state.resource_dir.lock().map_err(|e| e.to_string())?.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || probe_melt(configured, resource_dir))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?;
+
+    *state.melt_check.lock().map_err(|e| e.to_string())? = Some((Instant::now(), result.clone()));
+    Ok(result)
+}
+
+/// Use a specific melt binary (validated with `--version`); `None` clears it
+#[tauri::command]
+pub fn set_melt_path(path: Option<String>, state: State<'_, MeltState>) -> Result<MeltCheckResult, String> {
+    if let Some(path) = &path {
+        if !std::path::Path::new(path).is_file() || melt_version(path).is_none() {
+            return Ok(MeltCheckResult {
+                available: false,
+                version: None,
+                path: Some(path.clone()),
+                error: Some(format!("{} is not a working melt binary", path)),
+            });
+        }
+    }
+
+    *state.melt_path.lock().map_err(|e| e.to_string())? = path.clone();
+    *state.capabilities.lock().map_err(|e| e.to_string())? = None;
+
+    let resource_dir = state.resource_dir.lock().map_err(|e| e.to_string())?.clone();
+    let result = probe_melt(path, resource_dir);
+    *state.melt_check.lock().map_err(|e| e.to_string())? = Some((Instant::now(), result.clone()));
+    Ok(result)
+}
+
+/// Consumers, filters, producers and version of the resolved melt.
+/// Cached per binary; pass `refresh` to query again.
+#[tauri::command]
+pub async fn melt_capabilities(
+    refresh: Option<bool>,
+    state: State<'_, MeltState>,
+) -> Result<MeltCapabilities, String> {
+    let melt_path = find_melt(&state).ok_or("melt not found on system")?;
+
+    if !refresh.unwrap_or(false) {
+        let cached = state.capabilities.lock().map_err(|e| e.to_string())?.clone();
+        if let Some(capabilities) = cached.filter(|c| c.path == melt_path) {
+            return Ok(capabilities);
+        }
+    }
+
+    let capabilities = tauri::async_runtime::spawn_blocking(move || query_melt_capabilities(&melt_path))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?;
+
+    *state.capabilities.lock().map_err(|e| e.to_string())? = Some(capabilities.clone());
+    Ok(capabilities)
+}
+
+/// Render an MLT document, emitting "melt-render-progress" events
+#[tauri::command]
+pub async fn run_melt_render(
+    app: AppHandle,
+    mlt_xml: String,
+    output_path: String,
+    options: RenderOptions,
+    job_id: String,
+    state: State<'_, MeltState>,
+) -> Result<RenderResult, String> {
+    execute_and_record(&state, Some(&app), &job_id, &mlt_xml, &output_path, &options).await
+}
+
+/// Render a quick low-res preview into the temp dir, emitting
+/// "melt-render-progress" events. Cancel with `cancel_melt_render(job_id)`.
+#[tauri::command]
+pub async fn render_preview(
+    app: AppHandle,
+    mlt_xml: String,
+    job_id: String,
+    in_frame: Option<u64>,
+    out_frame: Option<u64>,
+    state: State<'_, MeltState>,
+) -> Result<RenderResult, String> {
+    let output_path = get_mlt_temp_dir_path()?.join(format!("preview-{}.mp4", job_id));
+    let options = preview_options(&mlt_xml, in_frame, out_frame);
+    execute_render(
+        &state,
+        Some(&app),
+        &job_id,
+        &mlt_xml,
+        &output_path.to_string_lossy(),
+        &options,
+    )
+    .await
+}
+
+/// Render into a FIFO (created if missing) for live-streaming tools to read,
+/// emitting "melt-render-progress" events. melt blocks until a reader opens
+/// the FIFO, so start the reader before the stall timeout runs out. Not
+/// recorded in the render history since nothing is left on disk.
+#[tauri::command]
+pub async fn render_to_fifo(
+    app: AppHandle,
+    mlt_xml: String,
+    fifo_path: String,
+    options: RenderOptions,
+    job_id: String,
+    state: State<'_, MeltState>,
+) -> Result<RenderResult, String> {
+    let options = fifo_render_options(&options)?;
+    prepare_fifo(&fifo_path)?;
+    execute_render(&state, Some(&app), &job_id, &mlt_xml, &fifo_path, &options).await
+}
+
+/// Render a typed timeline, generating the MLT XML in Rust
+#[tauri::command]
+pub async fn build_and_render(
+    app: AppHandle,
+    timeline: Timeline,
+    output_path: String,
+    options: RenderOptions,
+    job_id: String,
+    state: State<'_, MeltState>,
+) -> Result<RenderResult, String> {
+    let mlt_xml = timeline.to_xml()?;
+    execute_and_record(&state, Some(&app), &job_id, &mlt_xml, &output_path, &options).await
+}
+
+/// Add a render to the FIFO queue. Returns the job's queue position.
+#[tauri::command]
+pub async fn enqueue_render(
+    app: AppHandle,
+    mlt_xml: String,
+    output_path: String,
+    options: RenderOptions,
+    job_id: String,
+    state: State<'_, MeltState>,
+) -> Result<usize, String> {
+    // Fail fast on bad options rather than when the job reaches the front
+    build_consumer_args(&options)?;
+    build_range_args(&options, &mlt_xml)?;
+
+    let position = {
+        let mut queue = state.queue.lock().map_err(|e| e.to_string())?;
+        queue.push_back(QueuedRender {
+            job_id: job_id.clone(),
+            mlt_xml,
+            output_path,
+            options,
+        });
+        queue.len() - 1
+    };
+
+    let _ = app.emit("render-queued", RenderQueuedEvent { job_id, position });
+    pump_render_queue(&app);
+
+    Ok(position)
+}
+
+/// List queued and running render jobs
+#[tauri::command]
+pub fn list_render_jobs(state: State<'_, MeltState>) -> Result<Vec<RenderJobInfo>, String> {
+    let mut list = active_render_infos(&state)?;
+    let queue = state.queue.lock().map_err(|e| e.to_string())?;
+
+    list.extend(queue.iter().enumerate().map(|(position, job)| RenderJobInfo {
+        job_id: job.job_id.clone(),
+        status: "queued".to_string(),
+        queue_position: Some(position),
+    }));
+
+    Ok(list)
+}
+
+/// List running render jobs (status "running" or "cancelling"), excluding
+/// the queue
+#[tauri::command]
+pub fn list_active_renders(state: State<'_, MeltState>) -> Result<Vec<RenderJobInfo>, String> {
+    active_render_infos(&state)
+}
+
+fn active_render_infos(state: &MeltState) -> Result<Vec<RenderJobInfo>, String> {
+    let jobs = state.active_jobs.lock().map_err(|e| e.to_string())?;
+    Ok(jobs
+        .iter()
+        .map(|(job_id, cancelled)| RenderJobInfo {
+            job_id: job_id.clone(),
+            status: if *cancelled { "cancelling" } else { "running" }.to_string(),
+            queue_position: None,
+        })
+        .collect())
+}
+
+/// Cancel every queued and running render, killing their melt processes.
+/// Returns the ids of the jobs stopped.
+#[tauri::command]
+pub fn cancel_all_renders(state: State<'_, MeltState>) -> Vec<String> {
+    cancel_all_jobs(&state)
+}
+
+/// Set how many queued renders may run at once (minimum 1)
+#[tauri::command]
+pub fn set_max_concurrent_renders(
+    app: AppHandle,
+    max_concurrent: usize,
+    state: State<'_, MeltState>,
+) -> Result<(), String> {
+    {
+        let mut max = state.max_concurrent.lock().map_err(|e| e.to_string())?;
+        *max = max_concurrent.max(1);
+    }
+    pump_render_queue(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_melt_render(
+    app: AppHandle,
+    job_id: String,
+    state: State<'_, MeltState>,
+) -> Result<bool, String> {
+    // Queued jobs are simply dropped
+    let was_queued = {
+        let mut queue = state.queue.lock().map_err(|e| e.to_string())?;
+        let before = queue.len();
+        queue.retain(|job| job.job_id != job_id);
+        queue.len() != before
+    };
+    if was_queued {
+        emit_queue_positions(&app, &state);
+        return Ok(true);
+    }
+
+    // Running jobs are flagged and their melt process killed
+    {
+        let mut jobs = state.active_jobs.lock().map_err(|e| e.to_string())?;
+        match jobs.get_mut(&job_id) {
+            Some(cancelled) => *cancelled = true,
+            None => return Ok(false),
+        }
+    }
+
+    let mut children = state.children.lock().map_err(|e| e.to_string())?;
+    if let Some(child) = children.get_mut(&job_id) {
+        let _ = child.kill();
+    }
+
+    Ok(true)
+}
+
+/// Transcode a source into a downscaled, fast-to-decode proxy, emitting
+/// "melt-render-progress" events under `job_id`. The proxy is recorded so
+/// renders with `use_originals` swap the source back in.
+#[tauri::command]
+pub async fn generate_proxy(
+    app: AppHandle,
+    source_path: String,
+    proxy_path: String,
+    width: u32,
+    codec: Option<ProxyCodec>,
+    job_id: String,
+    state: State<'_, MeltState>,
+) -> Result<RenderResult, String> {
+    check_proxy_path(&source_path, &proxy_path)?;
+    let melt_path = find_melt(&state).ok_or("melt not found on system")?;
+    let codec = codec.unwrap_or_default();
+
+    let probe_path = source_path.clone();
+    let info = tauri::async_runtime::spawn_blocking(move || video_decoder::get_video_info(&probe_path))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+        .map_err(|e| e.message)?;
+    // melt autorotates, so size the proxy from the displayed frame
+    let (width, height) = proxy_dimensions(info.display_width, info.display_height, width);
+
+    if let Some(parent) = std::path::Path::new(&proxy_path).parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create proxy directory: {}", e))?;
+    }
+
+    {
+        let mut jobs = state.active_jobs.lock().map_err(|e| e.to_string())?;
+        jobs.insert(job_id.clone(), false);
+    }
+
+    // No -profile: melt derives frame rate and geometry from the source
+    let mut cmd = melt_command(&melt_path);
+    cmd.arg(&source_path);
+    cmd.arg("-consumer");
+    cmd.arg(format!("avformat:{}", proxy_path));
+    cmd.arg(format!("width={}", width));
+    cmd.arg(format!("height={}", height));
+    cmd.args(codec.consumer_args());
+    cmd.arg("-progress");
+
+    let limits = RenderLimits::from_options(&RenderOptions::default());
+    let outcome = run_melt_process(&state, Some(&app), &job_id, 1, cmd, limits).await;
+
+    {
+        let mut jobs = state.active_jobs.lock().map_err(|e| e.to_string())?;
+        jobs.remove(&job_id);
+    }
+
+    let failed = |error: String| {
+        let _ = fs::remove_file(&proxy_path);
+        Ok(RenderResult {
+            success: false,
+            error: Some(error),
+            output_path: None,
+            segment_count: None,
+        })
+    };
+
+    match outcome? {
+        MeltRunOutcome::Finished { success: true, .. } => {
+            record_proxy_mapping(&state, ProxyMapping {
+                source_path,
+                proxy_path: proxy_path.clone(),
+                width,
+                height,
+                codec,
+            })?;
+            Ok(RenderResult {
+                success: true,
+                error: None,
+                output_path: Some(proxy_path),
+                segment_count: None,
+            })
+        }
+        MeltRunOutcome::Finished { stderr, .. } => failed(format!("melt exited with error: {}", stderr)),
+        MeltRunOutcome::Cancelled => failed("Proxy generation cancelled".to_string()),
+        MeltRunOutcome::TimedOut(secs) => failed(format!("proxy timed out after {} s", secs)),
+        MeltRunOutcome::Stalled(secs) => failed(format!("proxy stalled: no progress for {} s", secs)),
+    }
+}
+
+/// Detach one audio stream of `path` into a standalone file, emitting
+/// "melt-render-progress" events under `job_id`. `format` ("wav", "mp3",
+/// "aac", or any audio-only export extension) defaults to `out_path`'s
+/// extension; `stream_index` (an absolute stream index) defaults to the best
+/// audio stream.
+#[tauri::command]
+pub async fn extract_audio(
+    app: AppHandle,
+    path: String,
+    out_path: String,
+    format: Option<String>,
+    stream_index: Option<usize>,
+    job_id: Option<String>,
+    state: State<'_, MeltState>,
+) -> Result<RenderResult, String> {
+    let melt_path = find_melt(&state).ok_or("melt not found on system")?;
+    let job_id = job_id.unwrap_or_else(|| format!("extract-{}", uuid::Uuid::new_v4()));
+
+    let extension = std::path::Path::new(&out_path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    if let (Some(format), Some(extension)) = (&format, &extension) {
+        if !format.eq_ignore_ascii_case(extension) {
+            return Err(format!("out_path extension .{} doesn't match format '{}'", extension, format));
+        }
+    }
+    let out_path = match (&format, &extension) {
+        (Some(format), None) => format!("{}.{}", out_path, format.to_lowercase()),
+        _ => out_path,
+    };
+    let consumer_args = build_audio_consumer_args(
+        &RenderOptions {
+            audio_only: true,
+            ..Default::default()
+        },
+        &out_path,
+    )?;
+
+    let probe_path = path.clone();
+    let stream_index = tauri::async_runtime::spawn_blocking(move || -> Result<usize, String> {
+        match stream_index {
+            Some(index) => {
+                let streams = video_decoder::probe_streams(&probe_path).map_err(|e| e.message)?;
+                match streams.iter().find(|s| s.index == index) {
+                    Some(stream) if stream.media_type == "audio" => Ok(index),
+                    Some(stream) => Err(format!("Stream {} is {}, not audio", index, stream.media_type)),
+                    None => Err(format!("No stream {} in {}", index, probe_path)),
+                }
+            }
+            None => video_decoder::best_audio_stream(&probe_path).map_err(|e| e.message),
+        }
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))??;
+
+    if let Some(parent) = std::path::Path::new(&out_path).parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create output directory: {}", e))?;
+    }
+
+    {
+        let mut jobs = state.active_jobs.lock().map_err(|e| e.to_string())?;
+        jobs.insert(job_id.clone(), false);
+    }
+
+    // video_index=-1 skips decoding picture entirely
+    let mut cmd = melt_command(&melt_path);
+    cmd.arg(&path);
+    cmd.arg(format!("audio_index={}", stream_index));
+    cmd.arg("video_index=-1");
+    cmd.arg("-consumer");
+    cmd.arg(format!("avformat:{}", out_path));
+    cmd.args(&consumer_args);
+    cmd.arg("-progress");
+
+    let limits = RenderLimits::from_options(&RenderOptions::default());
+    let outcome = run_melt_process(&state, Some(&app), &job_id, 1, cmd, limits).await;
+
+    {
+        let mut jobs = state.active_jobs.lock().map_err(|e| e.to_string())?;
+        jobs.remove(&job_id);
+    }
+
+    let failed = |error: String| {
+        let _ = fs::remove_file(&out_path);
+        Ok(RenderResult {
+            success: false,
+            error: Some(error),
+            output_path: None,
+            segment_count: None,
+        })
+    };
+
+    match outcome? {
+        MeltRunOutcome::Finished { success: true, .. } => Ok(RenderResult {
+            success: true,
+            error: None,
+            output_path: Some(out_path.clone()),
+            segment_count: None,
         }),
+        MeltRunOutcome::Finished { stderr, .. } => failed(format!("melt exited with error: {}", stderr)),
+        MeltRunOutcome::Cancelled => failed("Audio extraction cancelled".to_string()),
+        MeltRunOutcome::TimedOut(secs) => failed(format!("extraction timed out after {} s", secs)),
+        MeltRunOutcome::Stalled(secs) => failed(format!("extraction stalled: no progress for {} s", secs)),
+    }
+}
+
+/// List every recorded proxy and its original
+#[tauri::command]
+pub fn list_proxies() -> Vec<ProxyMapping> {
+    load_proxy_mappings()
+}
+
+/// Return the document with proxy paths replaced by their originals
+#[tauri::command]
+pub fn substitute_proxies(mlt_xml: String) -> String {
+    substitute_proxy_originals(&mlt_xml)
+}
+
+#[tauri::command]
+pub async fn verify_render_output(path: String) -> Result<RenderVerification, String> {
+    tauri::async_runtime::spawn_blocking(move || verify_output_file(&path))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))
+}
+
+/// Past exports, oldest first
+#[tauri::command]
+pub fn list_render_history() -> Vec<RenderHistoryEntry> {
+    load_render_history()
+}
+
+#[tauri::command]
+pub fn clear_render_history(state: State<'_, MeltState>) -> Result<(), String> {
+    let _guard = state.history_lock.lock().map_err(|e| e.to_string())?;
+    save_render_history(&[])
+}
+
+#[tauri::command]
+pub fn get_mlt_temp_dir() -> Result<String, String> {
+    let dir = get_mlt_temp_dir_path()?;
+    Ok(dir.to_string_lossy().to_string())
+}
+
+/// Job a temp file belongs to, from the names jobs write: `{id}.mlt`,
+/// `{id}.profile`, `{id}-passlog*` and `preview-{id}.mp4`
+fn temp_file_job_id(name: &str) -> Option<&str> {
+    if let Some(id) = name.strip_prefix("preview-").and_then(|rest| rest.strip_suffix(".mp4")) {
+        return Some(id);
+    }
+    if let Some(end) = name.find("-passlog") {
+        return Some(&name[..end]);
+    }
+    name.strip_suffix(".mlt").or_else(|| name.strip_suffix(".profile"))
+}
+
+/// Remove temp files not belonging to an active job, optionally only those
+/// last modified more than `max_age` ago. Returns how many were removed.
+fn remove_mlt_temp_files(state: &MeltState, max_age: Option<Duration>) -> Result<usize, String> {
+    let temp_dir = get_mlt_temp_dir_path()?;
+    let active: Vec<String> = state
+        .active_jobs
+        .lock()
+        .map_err(|e| e.to_string())?
+        .keys()
+        .cloned()
+        .collect();
+
+    let entries = fs::read_dir(&temp_dir)
+        .map_err(|e| format!("Failed to read temp dir: {}", e))?;
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        if temp_file_job_id(&name).is_some_and(|id| active.iter().any(|job_id| job_id == id)) {
+            continue;
+        }
+
+        if let Some(max_age) = max_age {
+            let age = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok());
+            // Unknown or future mtimes count as fresh
+            if !age.is_some_and(|age| age > max_age) {
+                continue;
+            }
+        }
+
+        if fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Remove every temp file except those of running jobs
+#[tauri::command]
+pub fn cleanup_mlt_temp_files(state: State<'_, MeltState>) -> Result<(), String> {
+    remove_mlt_temp_files(&state, None).map(|_| ())
+}
+
+/// Remove orphaned temp files older than `max_age_secs`; safe to run on a timer
+#[tauri::command]
+pub fn cleanup_mlt_temp_files_older_than(
+    max_age_secs: u64,
+    state: State<'_, MeltState>,
+) -> Result<usize, String> {
+    remove_mlt_temp_files(&state, Some(Duration::from_secs(max_age_secs)))
+}
+
+#[tauri::command]
+pub fn run_melt_raw(args: Vec<String>, state: State<'_, MeltState>) -> Result<MeltRawResult, String> {
+    let melt_path = find_melt(&state).ok_or("melt not found on system")?;
+
+    let output = melt_command(&melt_path)
+        .args(&args)
+        .output()
+        .map_err(|e| format!("Failed to run melt: {}", e))?;
+
+    Ok(MeltRawResult {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        exit_code: output.status.code().unwrap_or(-1),
+    })
+}
+
+/// Emit every line of a melt pipe as a "melt-raw-output" event on a thread
+fn forward_melt_lines<R: Read + Send + 'static>(
+    app: &AppHandle,
+    job_id: &str,
+    stream: &'static str,
+    pipe: Option<R>,
+) -> std::thread::JoinHandle<()> {
+    let app = app.clone();
+    let job_id = job_id.to_string();
+    std::thread::spawn(move || {
+        if let Some(pipe) = pipe {
+            for_each_melt_line(pipe, |line| {
+                let _ = app.emit(
+                    "melt-raw-output",
+                    MeltRawOutputEvent {
+                        job_id: job_id.clone(),
+                        stream: stream.to_string(),
+                        line: line.to_string(),
+                    },
+                );
+            });
+        }
+    })
+}
+
+/// Run melt with arbitrary arguments, emitting each stdout/stderr line as a
+/// "melt-raw-output" event. Cancel with `cancel_melt_render(job_id)`.
+#[tauri::command]
+pub async fn run_melt_raw_streaming(
+    app: AppHandle,
+    args: Vec<String>,
+    job_id: String,
+    state: State<'_, MeltState>,
+) -> Result<MeltRawStreamResult, String> {
+    let melt_path = find_melt(&state).ok_or("melt not found on system")?;
+
+    let mut child = melt_command(&melt_path)
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run melt: {}", e))?;
+
+    // One forwarding thread per pipe so neither can fill up and block melt
+    let stdout_reader = forward_melt_lines(&app, &job_id, "stdout", child.stdout.take());
+    let stderr_reader = forward_melt_lines(&app, &job_id, "stderr", child.stderr.take());
+
+    {
+        let mut jobs = state.active_jobs.lock().map_err(|e| e.to_string())?;
+        jobs.insert(job_id.clone(), false);
+        let mut children = state.children.lock().map_err(|e| e.to_string())?;
+        children.insert(job_id.clone(), child);
+    }
+
+    // Errors while polling must not leave the child running untracked
+    let result: Result<MeltRawStreamResult, String> = async {
+        Ok(loop {
+            if is_job_cancelled(&state, &job_id) {
+                kill_tracked_child(&state, &job_id)?;
+                break MeltRawStreamResult {
+                    exit_code: None,
+                    cancelled: true,
+                };
+            }
+
+            {
+                let mut children = state.children.lock().map_err(|e| e.to_string())?;
+                let exited = match children.get_mut(&job_id) {
+                    Some(child) => child
+                        .try_wait()
+                        .map_err(|e| format!("Failed to wait for melt: {}", e))?,
+                    None => {
+                        break MeltRawStreamResult {
+                            exit_code: None,
+                            cancelled: true,
+                        }
+                    }
+                };
+                if let Some(status) = exited {
+                    children.remove(&job_id);
+                    break MeltRawStreamResult {
+                        exit_code: status.code(),
+                        cancelled: false,
+                    };
+                }
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        })
+    }
+    .await;
+    if result.is_err() {
+        let _ = kill_tracked_child(&state, &job_id);
+    }
+
+    // Let the readers flush the last lines before reporting completion,
+    // waiting off the async runtime
+    let _ = tauri::async_runtime::spawn_blocking(move || {
+        let _ = stdout_reader.join();
+        let _ = stderr_reader.join();
+    })
+    .await;
+
+    if let Ok(mut jobs) = state.active_jobs.lock() {
+        jobs.remove(&job_id);
+    }
+
+    result
+}
+
+/// List producer media files that are missing or unreadable
+#[tauri::command]
+pub fn preflight_mlt(mlt_xml: String, base_dir: Option<String>) -> Result<PreflightResult, String> {
+    preflight_resources(&mlt_xml, base_dir.as_deref().map(std::path::Path::new))
+}
+
+/// Classify melt's stderr into structured issues. Only the common
+/// load/resource failures are recognised; other lines are ignored.
+fn parse_melt_stderr(stderr: &str) -> Vec<ValidationIssue> {
+    stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            let lower = line.to_lowercase();
+            let severity = if lower.contains("failed to load")
+                || lower.contains("unable to open")
+                || lower.contains("no such file")
+                || lower.contains("invalid data")
+                || lower.contains("error")
+            {
+                IssueSeverity::Error
+            } else if lower.contains("warning") {
+                IssueSeverity::Warning
+            } else {
+                return None;
+            };
+
+            // melt quotes the resource it failed on: Failed to load "clip.mp4"
+            let resource = line
+                .split_once('"')
+                .and_then(|(_, rest)| rest.split_once('"'))
+                .map(|(quoted, _)| quoted.to_string());
+
+            Some(ValidationIssue {
+                severity,
+                message: line.to_string(),
+                line: None,
+                column: None,
+                resource,
+                field: None,
+            })
+        })
+        .collect()
+}
+
+/// Validate an MLT document: XML syntax first (with line:column), then a
+/// dry run through melt for problems only melt can see
+#[tauri::command]
+pub fn validate_mlt_xml(mlt_xml: String, state: State<'_, MeltState>) -> Result<ValidateResult, String> {
+    if let Err(e) = mlt_parser::check_well_formed(&mlt_xml) {
+        let (line, column) = mlt_parser::line_col(&mlt_xml, e.position);
+        return Ok(ValidateResult::from_issues(vec![ValidationIssue {
+            severity: IssueSeverity::Error,
+            message: format!("{}:{}: {}", line, column, e.message),
+            line: Some(line),
+            column: Some(column),
+            resource: None,
+            field: None,
+        }]));
+    }
+
+    let issue = |message: String| {
+        ValidateResult::from_issues(vec![ValidationIssue {
+            severity: IssueSeverity::Error,
+            message,
+            line: None,
+            column: None,
+            resource: None,
+            field: None,
+        }])
+    };
+
+    let melt_path = match find_melt(&state) {
+        Some(p) => p,
+        None => return Ok(issue("melt not found".to_string())),
+    };
+
+    // Write XML to temp file
+    let temp_dir = get_mlt_temp_dir_path()?;
+    let xml_path = temp_dir.join("validate_temp.mlt");
+
+    fs::write(&xml_path, &mlt_xml)
+        .map_err(|e| format!("Failed to write temp XML: {}", e))?;
+
+    // Run melt in info mode to validate
+    let output = melt_command(&melt_path)
+        .arg(xml_path.to_string_lossy().to_string())
+        .arg("-consumer")
+        .arg("xml")
+        .output();
+
+    // Cleanup
+    let _ = fs::remove_file(&xml_path);
+
+    match output {
+        Ok(out) => {
+            let stderr = String::from_utf8_lossy(&out.stderr).to_string();
+            let mut issues = parse_melt_stderr(&stderr);
+            if out.status.success() {
+                // melt still succeeds with missing media, so keep errors as warnings
+                for issue in &mut issues {
+                    issue.severity = IssueSeverity::Warning;
+                }
+                Ok(ValidateResult::from_issues(issues))
+            } else if issues.iter().any(|i| i.severity == IssueSeverity::Error) {
+                Ok(ValidateResult::from_issues(issues))
+            } else {
+                Ok(issue(stderr))
+            }
+        }
+        Err(e) => Ok(issue(format!("Failed to validate: {}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_presets_are_valid() {
+        for preset in render_presets() {
+            let issues = check_render_options(&preset.options);
+            assert!(issues.is_empty(), "{}: {:?}", preset.id, issues);
+            assert!(build_consumer_args(&preset.options).is_ok(), "{}", preset.id);
+        }
+        for id in ["tiktok", "instagram_reels"] {
+            let options = render_preset(id.to_string()).unwrap();
+            assert_eq!((options.width, options.height), (Some(1080), Some(1920)));
+            assert!(
+                matches!(
+                    options.profile,
+                    Some(RenderProfile::Custom {
+                        width: 1080,
+                        height: 1920,
+                        display_aspect_num: Some(9),
+                        display_aspect_den: Some(16),
+                        ..
+                    })
+                ),
+                "{}",
+                id
+            );
+        }
+        assert!(render_preset("vhs".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_proxy_over_source_rejected() {
+        let dir = std::env::temp_dir().join(format!("proxy-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("clip.mp4");
+        fs::write(&source, b"original").unwrap();
+        let source_str = source.to_str().unwrap();
+        let respelled = dir.join(".").join("clip.mp4");
+
+        let same = check_proxy_path(source_str, source_str);
+        let same_respelled = check_proxy_path(source_str, respelled.to_str().unwrap());
+        let distinct = check_proxy_path(source_str, dir.join("clip.proxy.mp4").to_str().unwrap());
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(same.is_err());
+        assert!(same_respelled.is_err());
+        assert!(distinct.is_ok());
+    }
+
+    #[test]
+    fn test_replace_resource_values_matches_whole_values() {
+        let xml = r#"<mlt><producer id="a"><property name="resource">/p/clip.mp4</property><property name="kdenlive:proxy">/p/clip.mp4</property></producer><producer id="b"><property name="resource">/p/clip.mp4.bak</property></producer><producer id="c"><property name="resource">/p/a&amp;b.mp4</property></producer></mlt>"#;
+        let replacements: HashMap<String, String> = [
+            ("/p/clip.mp4".to_string(), "/o/clip.mov".to_string()),
+            ("/p/a&b.mp4".to_string(), "/o/a&b.mov".to_string()),
+        ]
+        .into();
+
+        let out = replace_resource_values(xml, &replacements);
+        assert_eq!(
+            out,
+            xml.replacen(
+                r#"resource">/p/clip.mp4<"#,
+                r#"resource">/o/clip.mov<"#,
+                1
+            )
+            .replace("/p/a&amp;b.mp4", "/o/a&amp;b.mov")
+        );
+        assert_eq!(replace_resource_values("<mlt><unclosed", &replacements), "<mlt><unclosed");
+    }
+
+    #[test]
+    fn test_temp_file_job_id() {
+        assert_eq!(temp_file_job_id("job-1.mlt"), Some("job-1"));
+        assert_eq!(temp_file_job_id("job-1.profile"), Some("job-1"));
+        assert_eq!(temp_file_job_id("job-1-passlog-0.log.mbtree"), Some("job-1"));
+        assert_eq!(temp_file_job_id("preview-job-1.mp4"), Some("job-1"));
+        // A job id that is a prefix of another's doesn't claim its files
+        assert_eq!(temp_file_job_id("job-12.mlt"), Some("job-12"));
+        assert_eq!(temp_file_job_id("validate_temp.txt"), None);
+    }
+
+    #[test]
+    fn test_fifo_render_options() {
+        let options = fifo_render_options(&RenderOptions::default()).unwrap();
+        assert_eq!(options.output_format, Some(RenderFormat::MpegTs));
+        assert!(build_consumer_args(&options).unwrap().contains(&"f=mpegts".to_string()));
+
+        for bad in [
+            RenderOptions {
+                output_format: Some(RenderFormat::Mp4H264),
+                ..Default::default()
+            },
+            RenderOptions {
+                two_pass: true,
+                ..Default::default()
+            },
+        ] {
+            assert!(fifo_render_options(&bad).is_err());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_prepare_fifo_creates_and_rejects_files() {
+        let dir = std::env::temp_dir().join(format!("fifo-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let fifo = dir.join("live.ts");
+        prepare_fifo(fifo.to_str().unwrap()).unwrap();
+        assert!(is_fifo(&fifo));
+        // An existing FIFO is reused
+        prepare_fifo(fifo.to_str().unwrap()).unwrap();
+
+        let file = dir.join("plain.ts");
+        fs::write(&file, b"").unwrap();
+        assert!(prepare_fifo(file.to_str().unwrap()).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_prores_rejects_crf() {
+        let options = RenderOptions {
+            output_format: Some(RenderFormat::ProRes),
+            crf: Some(18),
+            ..Default::default()
+        };
+        assert!(build_consumer_args(&options).is_err());
+    }
+
+    #[test]
+    fn test_preset_only_for_x264() {
+        let webm = RenderOptions {
+            output_format: Some(RenderFormat::WebmVp9),
+            ..Default::default()
+        };
+        let args = build_consumer_args(&webm).unwrap();
+        assert!(args.contains(&"vcodec=libvpx-vp9".to_string()));
+        assert!(!args.iter().any(|a| a.starts_with("preset=")));
+
+        let mp4 = RenderOptions::default();
+        assert!(build_consumer_args(&mp4).unwrap().contains(&"preset=medium".to_string()));
+    }
+
+    #[test]
+    fn test_unknown_preset_rejected() {
+        let options = RenderOptions {
+            preset: Some("warp".to_string()),
+            ..Default::default()
+        };
+        let err = build_consumer_args(&options).unwrap_err();
+        assert!(err.contains("Unknown preset 'warp'"), "{}", err);
+        assert!(check_render_options(&options)
+            .iter()
+            .any(|issue| issue.field.as_deref() == Some("preset")));
+    }
+
+    #[test]
+    fn test_extra_args_reject_flags_unless_allowed() {
+        let mut options = RenderOptions {
+            extra_consumer_args: vec!["g=48".to_string(), "-consumer".to_string()],
+            ..Default::default()
+        };
+        assert!(build_consumer_args(&options).is_err());
+
+        options.allow_extra_flags = true;
+        let args = build_consumer_args(&options).unwrap();
+        assert!(args.ends_with(&["g=48".to_string(), "-consumer".to_string()]));
+
+        let newline = RenderOptions {
+            extra_producer_args: vec!["a=1\nb=2".to_string()],
+            ..Default::default()
+        };
+        assert!(build_range_args(&newline, "").is_err());
+    }
+
+    #[test]
+    fn test_range_validated_against_project_length() {
+        let xml = r#"<mlt><tractor id="main" in="0" out="249"></tractor></mlt>"#;
+        assert_eq!(mlt_project_length(xml), Some(250));
+
+        let inverted = RenderOptions {
+            in_frame: Some(100),
+            out_frame: Some(50),
+            ..Default::default()
+        };
+        assert!(build_range_args(&inverted, xml).is_err());
+
+        let too_long = RenderOptions {
+            in_frame: Some(0),
+            out_frame: Some(250),
+            ..Default::default()
+        };
+        assert!(build_range_args(&too_long, xml).is_err());
+
+        let ok = RenderOptions {
+            in_frame: Some(25),
+            out_frame: Some(99),
+            ..Default::default()
+        };
+        assert_eq!(build_range_args(&ok, xml).unwrap(), vec!["in=25", "out=99"]);
+    }
+
+    #[test]
+    fn test_parse_progress_line() {
+        assert_eq!(
+            parse_progress_line("Current Frame:         42, percentage:          5"),
+            Some((42, 5))
+        );
+        assert_eq!(parse_progress_line("[consumer avformat] error"), None);
+    }
+
+    #[test]
+    fn test_eta_waits_for_warmup() {
+        let start = Instant::now();
+        let mut eta = EtaEstimator::new();
+
+        let (_, early) = eta.update(0, 1, start);
+        assert_eq!(early, None);
+        let (_, early) = eta.update(50, 5, start + Duration::from_secs(1));
+        assert_eq!(early, None);
+
+        // 50 fps steady, 10% done at frame 200 -> 1800 frames left -> 36 s
+        let (fps, later) = eta.update(200, 10, start + Duration::from_secs(4));
+        assert!((fps.unwrap() - 50.0).abs() < 1e-6);
+        assert!((later.unwrap() - 36.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_proxy_dimensions_keep_aspect_and_even() {
+        assert_eq!(proxy_dimensions(1920, 1080, 960), (960, 540));
+        assert_eq!(proxy_dimensions(1920, 1080, 641), (640, 360));
+        // Never upscales past the source
+        assert_eq!(proxy_dimensions(640, 480, 1280), (640, 480));
+    }
+
+    #[test]
+    fn test_watermark_geometry_corners() {
+        assert_eq!(
+            watermark_geometry(WatermarkPosition::BottomRight, 0.1, 0.5),
+            "87.00%/87.00%:10.00%x10.00%:50"
+        );
+        assert_eq!(
+            watermark_geometry(WatermarkPosition::TopLeft, 0.2, 1.0),
+            "3.00%/3.00%:20.00%x20.00%:100"
+        );
+    }
+
+    #[test]
+    fn test_subtitle_style_and_validation() {
+        assert_eq!(ass_color("#ff8000").unwrap(), "&H000080FF");
+        assert!(ass_color("red").is_err());
+
+        let style = subtitle_force_style(&SubtitleOptions {
+            font_name: Some("Inter".to_string()),
+            font_size: Some(28),
+            color: Some("#FFFFFF".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(style.as_deref(), Some("FontName=Inter,FontSize=28,PrimaryColour=&H00FFFFFF"));
+        assert_eq!(subtitle_force_style(&SubtitleOptions::default()).unwrap(), None);
+
+        let dir = std::env::temp_dir();
+        let srt = dir.join(format!("captions_{}.srt", uuid::Uuid::new_v4()));
+        fs::write(&srt, "1\n00:00:01,000 --> 00:00:02,500\nHello\n").unwrap();
+        assert!(check_subtitle_file(&srt.to_string_lossy()).is_ok());
+        fs::write(&srt, "not subtitles").unwrap();
+        assert!(check_subtitle_file(&srt.to_string_lossy()).is_err());
+        let _ = fs::remove_file(&srt);
+        assert!(check_subtitle_file("captions.txt").unwrap_err().contains("Unsupported"));
+    }
+
+    #[test]
+    fn test_timecode_overlay() {
+        assert_eq!(timecode_string(0, 25.0), "00:00:00:00");
+        assert_eq!(timecode_string(25 * 3661 + 7, 25.0), "01:01:01:07");
+        assert_eq!(timecode_string(30, 29.97), "00:00:01:00");
+
+        assert!(timecode_font(Some("/definitely/not/here.ttf")).is_err());
+        let options = RenderOptions {
+            timecode_overlay: Some(TimecodeOptions {
+                font_path: Some("/definitely/not/here.ttf".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(build_filter_args(&options, "<mlt/>").unwrap_err().contains("font"));
+    }
+
+    #[test]
+    fn test_validate_render_options() {
+        let fields = |options: RenderOptions| -> Vec<String> {
+            check_render_options(&options)
+                .into_iter()
+                .filter(|i| i.severity == IssueSeverity::Error)
+                .filter_map(|i| i.field)
+                .collect()
+        };
+
+        assert!(fields(RenderOptions::default()).is_empty());
+        assert_eq!(
+            fields(RenderOptions {
+                crf: Some(60),
+                frame_rate: Some(0),
+                width: Some(1280),
+                ..Default::default()
+            }),
+            vec!["crf", "height", "frame_rate"]
+        );
+        assert_eq!(
+            fields(RenderOptions {
+                output_format: Some(RenderFormat::ProRes),
+                crf: Some(18),
+                ..Default::default()
+            }),
+            vec!["crf"]
+        );
+
+        let unknown = check_render_options(&RenderOptions {
+            video_codec: Some("h264_magic".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(unknown[0].severity, IssueSeverity::Warning);
+        assert_eq!(unknown[0].field.as_deref(), Some("video_codec"));
+    }
+
+    #[test]
+    fn test_hls_output() {
+        let options = RenderOptions {
+            output_format: Some(RenderFormat::Hls { segment_secs: 6 }),
+            ..Default::default()
+        };
+        let args = build_consumer_args(&options).unwrap();
+        assert!(args.contains(&"f=hls".to_string()));
+        assert!(args.contains(&"hls_time=6".to_string()));
+        assert!(args.contains(&"acodec=aac".to_string()));
+
+        let zero = RenderOptions {
+            output_format: Some(RenderFormat::Hls { segment_secs: 0 }),
+            ..Default::default()
+        };
+        assert!(build_consumer_args(&zero).is_err());
+
+        let playlist = "#EXTM3U\n#EXT-X-TARGETDURATION:6\n#EXTINF:6.0,\nsegment_00000.ts\n#EXTINF:2.5,\nsegment_00001.ts\n#EXT-X-ENDLIST\n";
+        assert_eq!(count_hls_segments(playlist), 2);
+
+        let file = std::env::temp_dir().join(format!("hls_{}.mp4", uuid::Uuid::new_v4()));
+        fs::write(&file, b"x").unwrap();
+        assert!(prepare_hls_dir(&file.to_string_lossy()).is_err());
+        let _ = fs::remove_file(&file);
+    }
+
+    #[test]
+    fn test_audio_action() {
+        let muted = RenderOptions {
+            output_format: Some(RenderFormat::GifAnimated),
+            audio_action: Some(AudioAction::Mute),
+            ..Default::default()
+        };
+        let args = build_consumer_args(&muted).unwrap();
+        assert_eq!(args.iter().filter(|a| *a == "an=1").count(), 1);
+
+        let audio_only = RenderOptions {
+            audio_only: true,
+            audio_action: Some(AudioAction::Mute),
+            ..Default::default()
+        };
+        assert!(build_audio_consumer_args(&audio_only, "out.wav").is_err());
+
+        let replaced = RenderOptions {
+            audio_action: Some(AudioAction::ReplaceWith {
+                path: "/definitely/not/here.wav".to_string(),
+            }),
+            ..Default::default()
+        };
+        let err = build_audio_replacement_args(&replaced, "<mlt/>").unwrap_err();
+        assert!(err.contains("not found"));
+        assert!(build_audio_replacement_args(&RenderOptions::default(), "<mlt/>").unwrap().is_empty());
+    }
+
+    /// Two seconds of 8 kHz mono silence
+    fn write_test_wav(path: &std::path::Path) {
+        let data_len: u32 = 8000 * 2 * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&16000u32.to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        wav.resize(wav.len() + data_len as usize, 0);
+        fs::write(path, wav).unwrap();
+    }
+
+    #[test]
+    fn test_audio_replacement_mutes_only_the_project_track() {
+        let wav = std::env::temp_dir().join(format!("replacement-{}.wav", uuid::Uuid::new_v4()));
+        write_test_wav(&wav);
+        let path = wav.to_string_lossy().to_string();
+        let xml = r#"<mlt><profile frame_rate_num="25" frame_rate_den="1"/><tractor id="main" in="0" out="49"></tractor></mlt>"#;
+        let options = RenderOptions {
+            audio_action: Some(AudioAction::ReplaceWith { path: path.clone() }),
+            ..Default::default()
+        };
+        let args = build_audio_replacement_args(&options, xml);
+        let _ = fs::remove_file(&wav);
+
+        // The mute is attached to the project's track before the new track is added
+        assert_eq!(
+            args.unwrap(),
+            vec!["-attach-track", "volume", "gain=0", "-audio-track", path.as_str(), "out=49"]
+        );
+    }
+
+    #[test]
+    fn test_parse_melt_version() {
+        let parsed = parse_melt_version("melt 7.22.0\nCopyright (C) 2002-2024 Meltytech, LLC");
+        assert_eq!(parsed, Some(MeltVersion { major: 7, minor: 22, patch: 0 }));
+        assert_eq!(
+            parse_melt_version("melt v6.26-dirty"),
+            Some(MeltVersion { major: 6, minor: 26, patch: 0 })
+        );
+        assert_eq!(parse_melt_version("melt unknown"), None);
+    }
+
+    #[test]
+    fn test_melt_stderr_issues_capture_resource() {
+        let issues = parse_melt_stderr(
+            "[producer avformat] Failed to load \"missing.mp4\"\nCurrent Frame: 1\nwarning: odd fps\n",
+        );
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].severity, IssueSeverity::Error);
+        assert_eq!(issues[0].resource.as_deref(), Some("missing.mp4"));
+        assert_eq!(issues[1].severity, IssueSeverity::Warning);
+    }
+
+    #[test]
+    fn test_preflight_reports_missing_media_only() {
+        let xml = r#"<mlt>
+  <producer id="bg"><property name="resource">black</property><property name="mlt_service">color</property></producer>
+  <producer id="clip"><property name="resource">/nonexistent/clip.mp4</property></producer>
+</mlt>"#;
+        let result = preflight_resources(xml, None).unwrap();
+        assert!(!result.ok);
+        assert_eq!(result.missing.len(), 1);
+        assert_eq!(result.missing[0].producer_id, "clip");
+        assert_eq!(result.missing[0].reason, "missing");
+    }
+
+    #[test]
+    fn test_preflight_skips_prefixed_resources_and_uses_root() {
+        let dir = std::env::temp_dir().join(format!("preflight-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("clip.mp4"), b"").unwrap();
+        let xml = format!(
+            r#"<mlt root="{}">
+  <producer id="bg"><property name="resource">color:#000000</property></producer>
+  <producer id="text"><property name="resource">title</property><property name="mlt_service">kdenlivetitle</property></producer>
+  <producer id="clip"><property name="resource">clip.mp4</property></producer>
+</mlt>"#,
+            dir.display()
+        );
+        let result = preflight_resources(&xml, None).unwrap();
+        let missing: Vec<&str> = result.missing.iter().map(|m| m.resource.as_str()).collect();
+        assert!(result.ok, "{:?}", missing);
+
+        // Without a root, relative resources can't be judged and are skipped
+        let rootless = xml.replacen(&format!(r#" root="{}""#, dir.display()), "", 1);
+        assert!(preflight_resources(&rootless, None).unwrap().ok);
+
+        assert!(has_service_prefix("colour:red"));
+        assert!(!has_service_prefix("C:\\media\\clip.mp4"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_audio_only_codec_follows_extension() {
+        let options = RenderOptions {
+            audio_only: true,
+            ..Default::default()
+        };
+        let args = build_audio_consumer_args(&options, "/tmp/mix.mp3").unwrap();
+        assert!(args.contains(&"vn=1".to_string()));
+        assert!(args.contains(&"acodec=libmp3lame".to_string()));
+
+        let options = RenderOptions {
+            audio_only: true,
+            audio_codec: Some("aac".to_string()),
+            ..Default::default()
+        };
+        assert!(build_audio_consumer_args(&options, "/tmp/mix.flac").is_err());
+        assert!(build_audio_consumer_args(&options, "/tmp/mix.mp4").is_err());
+    }
+
+    #[test]
+    fn test_preview_options_follow_project_aspect() {
+        let xml = r#"<mlt><profile width="1440" height="1080" frame_rate_num="30" frame_rate_den="1"/></mlt>"#;
+        let options = preview_options(xml, None, None);
+        assert_eq!((options.width, options.height), (Some(640), Some(480)));
+        assert_eq!(options.preset.as_deref(), Some("ultrafast"));
+    }
+
+    #[test]
+    fn test_output_estimate_scales_with_duration() {
+        let xml = r#"<mlt><profile width="1920" height="1080" frame_rate_num="30" frame_rate_den="1"/><tractor id="t" out="299"/></mlt>"#;
+        let options = RenderOptions {
+            audio_bitrate: Some("192k".to_string()),
+            ..Default::default()
+        };
+        // 10 s of 1080p30 at 0.25 bpp plus 192 kbps audio
+        let expected = ((1920.0 * 1080.0 * 30.0 * 0.25 + 192_000.0) * 10.0 / 8.0) as u64;
+        assert_eq!(estimate_output_bytes(&options, xml), Some(expected));
+
+        let half = RenderOptions {
+            in_frame: Some(0),
+            out_frame: Some(149),
+            ..options
+        };
+        assert_eq!(estimate_output_bytes(&half, xml), Some(expected / 2));
+    }
+
+    #[test]
+    fn test_typical_bitrate_halves_every_six_crf() {
+        let default = typical_bits_per_pixel("libx264", None);
+        assert_eq!(typical_bits_per_pixel("libx264", Some(23)), default);
+        assert!((typical_bits_per_pixel("libx264", Some(29)) - default / 2.0).abs() < 1e-9);
+        assert!(typical_bits_per_pixel("libx264", Some(17)) > default);
+        assert_eq!(typical_bits_per_pixel("prores_ks", Some(10)), 3.5);
     }
 }
@@ -0,0 +1,463 @@
+//! Typed MLT document builder
+//!
+//! This module provides:
+//! - Timeline/Track/Clip/Transition/Filter structs mirroring the MLT model
+//! - Serialization of a Timeline to MLT XML for melt
+//!
+//! Building the XML here (instead of concatenating strings in the frontend)
+//! keeps document correctness in one tested place.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// ============================================
+// TYPES
+// ============================================
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Profile {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate_num: u32,
+    pub frame_rate_den: u32,
+    /// Defaults to width:height when omitted
+    pub display_aspect_num: Option<u32>,
+    pub display_aspect_den: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackKind {
+    Video,
+    Audio,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Filter {
+    /// MLT service name, e.g. "brightness" or "volume"
+    pub service: String,
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+    /// Restrict a timeline-level filter to one track
+    pub track: Option<usize>,
+    pub in_frame: Option<u64>,
+    pub out_frame: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Clip {
+    /// Media file path
+    pub resource: String,
+    /// First source frame used (inclusive)
+    pub in_frame: u64,
+    /// Last source frame used (inclusive)
+    pub out_frame: u64,
+    /// Position of the clip on its track, in project frames
+    pub start_frame: u64,
+    pub fade_in_frames: Option<u64>,
+    pub fade_out_frames: Option<u64>,
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+}
+
+impl Clip {
+    pub fn length(&self) -> u64 {
+        self.out_frame - self.in_frame + 1
+    }
+
+    /// First project frame after this clip
+    pub fn end_frame(&self) -> u64 {
+        self.start_frame + self.length()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Track {
+    pub kind: TrackKind,
+    pub clips: Vec<Clip>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Transition {
+    /// MLT service name, e.g. "luma" (dissolve) or "mix" (audio crossfade)
+    pub service: String,
+    pub a_track: usize,
+    pub b_track: usize,
+    pub in_frame: u64,
+    pub out_frame: u64,
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Timeline {
+    pub profile: Profile,
+    pub tracks: Vec<Track>,
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+}
+
+impl Timeline {
+    /// Project length in frames (end of the latest clip on any track)
+    pub fn length(&self) -> u64 {
+        self.tracks
+            .iter()
+            .flat_map(|t| t.clips.iter())
+            .map(|c| c.end_frame())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+// ============================================
+// VALIDATION
+// ============================================
+
+fn validate(timeline: &Timeline) -> Result<(), String> {
+    let profile = &timeline.profile;
+    if profile.width == 0 || profile.height == 0 {
+        return Err("Profile width and height must be positive".to_string());
+    }
+    if profile.frame_rate_num == 0 || profile.frame_rate_den == 0 {
+        return Err("Profile frame rate must be positive".to_string());
+    }
+    if timeline.tracks.is_empty() {
+        return Err("Timeline has no tracks".to_string());
+    }
+
+    for (t, track) in timeline.tracks.iter().enumerate() {
+        let mut previous_end = 0;
+        for (c, clip) in track.clips.iter().enumerate() {
+            if clip.out_frame < clip.in_frame {
+                return Err(format!(
+                    "Track {} clip {}: out_frame ({}) is before in_frame ({})",
+                    t, c, clip.out_frame, clip.in_frame
+                ));
+            }
+            if clip.start_frame < previous_end {
+                return Err(format!(
+                    "Track {} clip {}: overlaps the previous clip (starts at {}, previous ends at {})",
+                    t, c, clip.start_frame, previous_end
+                ));
+            }
+            let fades = clip.fade_in_frames.unwrap_or(0) + clip.fade_out_frames.unwrap_or(0);
+            if fades > clip.length() {
+                return Err(format!(
+                    "Track {} clip {}: fades ({} frames) are longer than the clip ({} frames)",
+                    t, c, fades, clip.length()
+                ));
+            }
+            previous_end = clip.end_frame();
+        }
+    }
+
+    for (i, transition) in timeline.transitions.iter().enumerate() {
+        for track in [transition.a_track, transition.b_track] {
+            if track >= timeline.tracks.len() {
+                return Err(format!("Transition {}: track {} does not exist", i, track));
+            }
+        }
+        if transition.out_frame < transition.in_frame {
+            return Err(format!("Transition {}: out_frame is before in_frame", i));
+        }
+    }
+
+    for (i, filter) in timeline.filters.iter().enumerate() {
+        if let Some(track) = filter.track {
+            if track >= timeline.tracks.len() {
+                return Err(format!("Filter {}: track {} does not exist", i, track));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================
+// XML OUTPUT
+// ============================================
+
+/// Escape text for use in XML attributes and element content
+pub fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn write_property(xml: &mut String, indent: &str, name: &str, value: &str) {
+    let _ = writeln!(
+        xml,
+        "{}<property name=\"{}\">{}</property>",
+        indent,
+        escape_xml(name),
+        escape_xml(value)
+    );
+}
+
+fn write_filter(xml: &mut String, indent: &str, filter: &Filter) {
+    let mut attrs = String::new();
+    if let Some(in_frame) = filter.in_frame {
+        let _ = write!(attrs, " in=\"{}\"", in_frame);
+    }
+    if let Some(out_frame) = filter.out_frame {
+        let _ = write!(attrs, " out=\"{}\"", out_frame);
+    }
+
+    let _ = writeln!(xml, "{}<filter{}>", indent, attrs);
+    let inner = format!("{}  ", indent);
+    write_property(xml, &inner, "mlt_service", &filter.service);
+    if let Some(track) = filter.track {
+        write_property(xml, &inner, "track", &track.to_string());
+    }
+    for (name, value) in &filter.properties {
+        write_property(xml, &inner, name, value);
+    }
+    let _ = writeln!(xml, "{}</filter>", indent);
+}
+
+/// Fade filters for a clip, positioned in source frames
+fn fade_filters(clip: &Clip, kind: TrackKind) -> Vec<Filter> {
+    let mut filters = Vec::new();
+
+    let mut fade = |in_frame: u64, out_frame: u64, rising: bool| {
+        let last = out_frame - in_frame;
+        let (service, name, low, high) = match kind {
+            TrackKind::Video => ("brightness", "level", "0", "1"),
+            TrackKind::Audio => ("volume", "level", "-60", "0"),
+        };
+        let (from, to) = if rising { (low, high) } else { (high, low) };
+
+        let mut properties = BTreeMap::new();
+        properties.insert(name.to_string(), format!("0={};{}={}", from, last, to));
+        if kind == TrackKind::Video {
+            properties.insert("alpha".to_string(), format!("0={};{}={}", from, last, to));
+        }
+
+        filters.push(Filter {
+            service: service.to_string(),
+            properties,
+            track: None,
+            in_frame: Some(in_frame),
+            out_frame: Some(out_frame),
+        });
+    };
+
+    if let Some(frames) = clip.fade_in_frames.filter(|f| *f > 0) {
+        fade(clip.in_frame, clip.in_frame + frames - 1, true);
+    }
+    if let Some(frames) = clip.fade_out_frames.filter(|f| *f > 0) {
+        fade(clip.out_frame + 1 - frames, clip.out_frame, false);
+    }
+
+    filters
+}
+
+impl Timeline {
+    /// Serialize the timeline as an MLT XML document
+    pub fn to_xml(&self) -> Result<String, String> {
+        validate(self)?;
+
+        let profile = &self.profile;
+        let aspect_num = profile.display_aspect_num.unwrap_or(profile.width);
+        let aspect_den = profile.display_aspect_den.unwrap_or(profile.height);
+        let length = self.length();
+
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml.push_str("<mlt LC_NUMERIC=\"C\" producer=\"main_tractor\">\n");
+        let _ = writeln!(
+            xml,
+            "  <profile width=\"{}\" height=\"{}\" progressive=\"1\" sample_aspect_num=\"1\" sample_aspect_den=\"1\" display_aspect_num=\"{}\" display_aspect_den=\"{}\" frame_rate_num=\"{}\" frame_rate_den=\"{}\"/>",
+            profile.width,
+            profile.height,
+            aspect_num,
+            aspect_den,
+            profile.frame_rate_num,
+            profile.frame_rate_den
+        );
+
+        // One producer per clip so per-clip filters don't leak between uses
+        for (t, track) in self.tracks.iter().enumerate() {
+            for (c, clip) in track.clips.iter().enumerate() {
+                let _ = writeln!(
+                    xml,
+                    "  <producer id=\"producer_{}_{}\" in=\"{}\" out=\"{}\">",
+                    t, c, clip.in_frame, clip.out_frame
+                );
+                write_property(&mut xml, "    ", "resource", &clip.resource);
+                for filter in fade_filters(clip, track.kind).iter().chain(clip.filters.iter()) {
+                    write_filter(&mut xml, "    ", filter);
+                }
+                xml.push_str("  </producer>\n");
+            }
+        }
+
+        // One playlist per track, with blanks for gaps
+        for (t, track) in self.tracks.iter().enumerate() {
+            let _ = writeln!(xml, "  <playlist id=\"playlist_{}\">", t);
+            let mut position = 0;
+            for (c, clip) in track.clips.iter().enumerate() {
+                if clip.start_frame > position {
+                    let _ = writeln!(xml, "    <blank length=\"{}\"/>", clip.start_frame - position);
+                }
+                let _ = writeln!(
+                    xml,
+                    "    <entry producer=\"producer_{}_{}\" in=\"{}\" out=\"{}\"/>",
+                    t, c, clip.in_frame, clip.out_frame
+                );
+                position = clip.end_frame();
+            }
+            xml.push_str("  </playlist>\n");
+        }
+
+        let _ = writeln!(
+            xml,
+            "  <tractor id=\"main_tractor\" in=\"0\" out=\"{}\">",
+            length.saturating_sub(1)
+        );
+        for (t, track) in self.tracks.iter().enumerate() {
+            let hide = match track.kind {
+                TrackKind::Video => "",
+                TrackKind::Audio => " hide=\"video\"",
+            };
+            let _ = writeln!(xml, "    <track producer=\"playlist_{}\"{}/>", t, hide);
+        }
+
+        // Composite every upper track onto the base track so all layers
+        // are visible/audible, like an NLE would
+        for (t, track) in self.tracks.iter().enumerate().skip(1) {
+            let service = match track.kind {
+                TrackKind::Video => "qtblend",
+                TrackKind::Audio => "mix",
+            };
+            xml.push_str("    <transition>\n");
+            write_property(&mut xml, "      ", "mlt_service", service);
+            write_property(&mut xml, "      ", "a_track", "0");
+            write_property(&mut xml, "      ", "b_track", &t.to_string());
+            write_property(&mut xml, "      ", "always_active", "1");
+            if track.kind == TrackKind::Audio {
+                write_property(&mut xml, "      ", "sum", "1");
+            }
+            xml.push_str("    </transition>\n");
+        }
+
+        for transition in &self.transitions {
+            let _ = writeln!(
+                xml,
+                "    <transition in=\"{}\" out=\"{}\">",
+                transition.in_frame, transition.out_frame
+            );
+            write_property(&mut xml, "      ", "mlt_service", &transition.service);
+            write_property(&mut xml, "      ", "a_track", &transition.a_track.to_string());
+            write_property(&mut xml, "      ", "b_track", &transition.b_track.to_string());
+            for (name, value) in &transition.properties {
+                write_property(&mut xml, "      ", name, value);
+            }
+            xml.push_str("    </transition>\n");
+        }
+
+        for filter in &self.filters {
+            write_filter(&mut xml, "    ", filter);
+        }
+
+        xml.push_str("  </tractor>\n");
+        xml.push_str("</mlt>\n");
+
+        Ok(xml)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(resource: &str, in_frame: u64, out_frame: u64, start_frame: u64) -> Clip {
+        Clip {
+            resource: resource.to_string(),
+            in_frame,
+            out_frame,
+            start_frame,
+            fade_in_frames: None,
+            fade_out_frames: None,
+            filters: Vec::new(),
+        }
+    }
+
+    fn timeline(tracks: Vec<Track>) -> Timeline {
+        Timeline {
+            profile: Profile {
+                width: 1920,
+                height: 1080,
+                frame_rate_num: 30,
+                frame_rate_den: 1,
+                display_aspect_num: Some(16),
+                display_aspect_den: Some(9),
+            },
+            tracks,
+            transitions: Vec::new(),
+            filters: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_multitrack_with_gap_and_fade() {
+        let mut first = clip("/media/a & b.mp4", 0, 59, 0);
+        first.fade_in_frames = Some(15);
+        let tl = timeline(vec![
+            Track {
+                kind: TrackKind::Video,
+                clips: vec![first, clip("/media/c.mp4", 10, 39, 90)],
+            },
+            Track {
+                kind: TrackKind::Audio,
+                clips: vec![clip("/media/music.wav", 0, 119, 0)],
+            },
+        ]);
+
+        let xml = tl.to_xml().unwrap();
+        assert!(xml.contains("/media/a &amp; b.mp4"));
+        assert!(xml.contains("<blank length=\"30\"/>"));
+        assert!(xml.contains("<tractor id=\"main_tractor\" in=\"0\" out=\"119\">"));
+        assert!(xml.contains("<track producer=\"playlist_1\" hide=\"video\"/>"));
+        assert!(xml.contains("<property name=\"level\">0=0;14=1</property>"));
+        assert!(xml.contains("<property name=\"mlt_service\">mix</property>"));
+    }
+
+    #[test]
+    fn test_overlapping_clips_rejected() {
+        let tl = timeline(vec![Track {
+            kind: TrackKind::Video,
+            clips: vec![clip("a.mp4", 0, 59, 0), clip("b.mp4", 0, 59, 30)],
+        }]);
+        assert!(tl.to_xml().is_err());
+    }
+
+    #[test]
+    fn test_transition_track_must_exist() {
+        let mut tl = timeline(vec![Track {
+            kind: TrackKind::Video,
+            clips: vec![clip("a.mp4", 0, 59, 0)],
+        }]);
+        tl.transitions.push(Transition {
+            service: "luma".to_string(),
+            a_track: 0,
+            b_track: 1,
+            in_frame: 0,
+            out_frame: 10,
+            properties: BTreeMap::new(),
+        });
+        assert!(tl.to_xml().is_err());
+    }
+}
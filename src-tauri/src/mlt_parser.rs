@@ -0,0 +1,550 @@
+//! MLT XML parsing
+//!
+//! This module provides:
+//! - Parsing a saved .mlt document back into structured project metadata
+//! - Structured errors that point at the offending element
+//! - A well-formedness check reporting line:column
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+// ============================================
+// TYPES
+// ============================================
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MltProfile {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate_num: Option<u32>,
+    pub frame_rate_den: Option<u32>,
+    pub display_aspect_num: Option<u32>,
+    pub display_aspect_den: Option<u32>,
+    /// frame_rate_num / frame_rate_den when both are present
+    pub fps: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MltProducer {
+    pub id: String,
+    pub resource: Option<String>,
+    pub service: Option<String>,
+    pub in_frame: Option<u64>,
+    pub out_frame: Option<u64>,
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MltPlaylistItem {
+    Entry {
+        producer: String,
+        in_frame: Option<u64>,
+        out_frame: Option<u64>,
+    },
+    Blank {
+        length: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MltPlaylist {
+    pub id: String,
+    pub items: Vec<MltPlaylistItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MltTrack {
+    /// Id of the playlist/producer on this track
+    pub producer: String,
+    /// "video", "audio" or "both" when the track is hidden
+    pub hide: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MltTransition {
+    pub service: Option<String>,
+    pub a_track: Option<usize>,
+    pub b_track: Option<usize>,
+    pub in_frame: Option<u64>,
+    pub out_frame: Option<u64>,
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MltProject {
+    /// `<mlt root>`: directory melt resolves relative resources against
+    pub root: Option<String>,
+    pub profile: Option<MltProfile>,
+    pub producers: Vec<MltProducer>,
+    pub playlists: Vec<MltPlaylist>,
+    pub tracks: Vec<MltTrack>,
+    pub transitions: Vec<MltTransition>,
+}
+
+/// Error pointing at the part of the document that couldn't be understood
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MltParseError {
+    pub message: String,
+    /// Element being read when the error occurred
+    pub element: Option<String>,
+    /// Byte offset into the XML
+    pub position: u64,
+}
+
+impl std::fmt::Display for MltParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.element {
+            Some(el) => write!(f, "<{}> at byte {}: {}", el, self.position, self.message),
+            None => write!(f, "byte {}: {}", self.position, self.message),
+        }
+    }
+}
+
+// ============================================
+// PARSING
+// ============================================
+
+/// Which element `<property>` values currently belong to
+enum PropertyOwner {
+    Producer(usize),
+    Transition(usize),
+    Ignored,
+}
+
+struct ElementContext<'a> {
+    name: String,
+    position: u64,
+    start: &'a BytesStart<'a>,
+}
+
+impl ElementContext<'_> {
+    fn error(&self, message: String) -> MltParseError {
+        MltParseError {
+            message,
+            element: Some(self.name.clone()),
+            position: self.position,
+        }
+    }
+
+    fn attr(&self, key: &str) -> Result<Option<String>, MltParseError> {
+        for attr in self.start.attributes() {
+            let attr = attr.map_err(|e| self.error(format!("Invalid attribute: {}", e)))?;
+            if attr.key.as_ref() == key.as_bytes() {
+                let value = attr
+                    .unescape_value()
+                    .map_err(|e| self.error(format!("Invalid attribute value: {}", e)))?;
+                return Ok(Some(value.into_owned()));
+            }
+        }
+        Ok(None)
+    }
+
+    fn parsed_attr<T: std::str::FromStr>(&self, key: &str) -> Result<Option<T>, MltParseError> {
+        match self.attr(key)? {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|_| self.error(format!("Attribute {}=\"{}\" is not a valid number", key, value))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Parse an MLT XML document into its profile, producers, playlists,
+/// tracks and transitions.
+pub fn parse_mlt(xml: &str) -> Result<MltProject, MltParseError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut project = MltProject::default();
+    let mut seen_root = false;
+    let mut owners: Vec<PropertyOwner> = Vec::new();
+    let mut current_property: Option<(String, String)> = None;
+    let mut current_playlist: Option<MltPlaylist> = None;
+    // Tractors can also be referenced by playlist entries
+    let mut tractor_ids: HashSet<String> = HashSet::new();
+
+    loop {
+        let position = reader.buffer_position();
+        let event = reader.read_event().map_err(|e| MltParseError {
+            message: format!("Malformed XML: {}", e),
+            element: None,
+            position: reader.error_position(),
+        })?;
+
+        let (start, is_empty) = match &event {
+            Event::Start(e) => (Some(e), false),
+            Event::Empty(e) => (Some(e), true),
+            _ => (None, false),
+        };
+
+        if let Some(start) = start {
+            let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+            let ctx = ElementContext {
+                name: name.clone(),
+                position,
+                start,
+            };
+
+            if !seen_root {
+                if name != "mlt" {
+                    return Err(ctx.error("Not an MLT document: root element must be <mlt>".to_string()));
+                }
+                seen_root = true;
+                project.root = ctx.attr("root")?.filter(|root| !root.is_empty());
+                continue;
+            }
+
+            match name.as_str() {
+                "profile" => {
+                    let mut profile = MltProfile {
+                        width: ctx.parsed_attr("width")?,
+                        height: ctx.parsed_attr("height")?,
+                        frame_rate_num: ctx.parsed_attr("frame_rate_num")?,
+                        frame_rate_den: ctx.parsed_attr("frame_rate_den")?,
+                        display_aspect_num: ctx.parsed_attr("display_aspect_num")?,
+                        display_aspect_den: ctx.parsed_attr("display_aspect_den")?,
+                        fps: None,
+                    };
+                    if let (Some(num), Some(den)) = (profile.frame_rate_num, profile.frame_rate_den) {
+                        if den != 0 {
+                            profile.fps = Some(num as f64 / den as f64);
+                        }
+                    }
+                    project.profile = Some(profile);
+                }
+                "producer" | "chain" => {
+                    let id = ctx
+                        .attr("id")?
+                        .ok_or_else(|| ctx.error("Producer is missing an id".to_string()))?;
+                    project.producers.push(MltProducer {
+                        id,
+                        in_frame: ctx.parsed_attr("in")?,
+                        out_frame: ctx.parsed_attr("out")?,
+                        ..Default::default()
+                    });
+                    if !is_empty {
+                        owners.push(PropertyOwner::Producer(project.producers.len() - 1));
+                    }
+                }
+                "transition" => {
+                    project.transitions.push(MltTransition {
+                        in_frame: ctx.parsed_attr("in")?,
+                        out_frame: ctx.parsed_attr("out")?,
+                        ..Default::default()
+                    });
+                    if !is_empty {
+                        owners.push(PropertyOwner::Transition(project.transitions.len() - 1));
+                    }
+                }
+                "filter" | "link" | "consumer" => {
+                    if !is_empty {
+                        owners.push(PropertyOwner::Ignored);
+                    }
+                }
+                "property" => {
+                    let prop_name = ctx
+                        .attr("name")?
+                        .ok_or_else(|| ctx.error("Property is missing a name".to_string()))?;
+                    if is_empty {
+                        assign_property(&mut project, &owners, prop_name, String::new());
+                    } else {
+                        current_property = Some((prop_name, String::new()));
+                    }
+                }
+                "playlist" => {
+                    let id = ctx
+                        .attr("id")?
+                        .ok_or_else(|| ctx.error("Playlist is missing an id".to_string()))?;
+                    let playlist = MltPlaylist {
+                        id,
+                        items: Vec::new(),
+                    };
+                    if is_empty {
+                        project.playlists.push(playlist);
+                    } else {
+                        current_playlist = Some(playlist);
+                        owners.push(PropertyOwner::Ignored);
+                    }
+                }
+                "entry" => {
+                    let producer = ctx
+                        .attr("producer")?
+                        .ok_or_else(|| ctx.error("Entry is missing a producer".to_string()))?;
+                    let item = MltPlaylistItem::Entry {
+                        producer,
+                        in_frame: ctx.parsed_attr("in")?,
+                        out_frame: ctx.parsed_attr("out")?,
+                    };
+                    match current_playlist.as_mut() {
+                        Some(playlist) => playlist.items.push(item),
+                        None => return Err(ctx.error("<entry> outside of a <playlist>".to_string())),
+                    }
+                    if !is_empty {
+                        owners.push(PropertyOwner::Ignored);
+                    }
+                }
+                "blank" => {
+                    let length = ctx
+                        .parsed_attr("length")?
+                        .ok_or_else(|| ctx.error("Blank is missing a length".to_string()))?;
+                    match current_playlist.as_mut() {
+                        Some(playlist) => playlist.items.push(MltPlaylistItem::Blank { length }),
+                        None => return Err(ctx.error("<blank> outside of a <playlist>".to_string())),
+                    }
+                }
+                "track" => {
+                    let producer = ctx
+                        .attr("producer")?
+                        .ok_or_else(|| ctx.error("Track is missing a producer".to_string()))?;
+                    project.tracks.push(MltTrack {
+                        producer,
+                        hide: ctx.attr("hide")?,
+                    });
+                }
+                "tractor" => {
+                    if let Some(id) = ctx.attr("id")? {
+                        tractor_ids.insert(id);
+                    }
+                }
+                // multitrack and unknown elements only matter for their children
+                _ => {}
+            }
+            continue;
+        }
+
+        match event {
+            Event::Text(text) => {
+                if let Some((_, value)) = current_property.as_mut() {
+                    let unescaped = text.unescape().map_err(|e| MltParseError {
+                        message: format!("Invalid property text: {}", e),
+                        element: Some("property".to_string()),
+                        position,
+                    })?;
+                    value.push_str(&unescaped);
+                }
+            }
+            Event::CData(data) => {
+                if let Some((_, value)) = current_property.as_mut() {
+                    value.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::End(end) => match end.name().as_ref() {
+                b"property" => {
+                    if let Some((name, value)) = current_property.take() {
+                        assign_property(&mut project, &owners, name, value);
+                    }
+                }
+                b"producer" | b"chain" | b"transition" | b"filter" | b"link" | b"consumer"
+                | b"entry" => {
+                    owners.pop();
+                }
+                b"playlist" => {
+                    owners.pop();
+                    if let Some(playlist) = current_playlist.take() {
+                        project.playlists.push(playlist);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !seen_root {
+        return Err(MltParseError {
+            message: "Not an MLT document: no <mlt> root element".to_string(),
+            element: None,
+            position: 0,
+        });
+    }
+
+    check_references(&project, &tractor_ids)?;
+
+    Ok(project)
+}
+
+/// Route a `<property>` to the producer or transition that owns it
+fn assign_property(project: &mut MltProject, owners: &[PropertyOwner], name: String, value: String) {
+    match owners.last() {
+        Some(PropertyOwner::Producer(i)) => {
+            let producer = &mut project.producers[*i];
+            match name.as_str() {
+                "resource" => producer.resource = Some(value.clone()),
+                "mlt_service" => producer.service = Some(value.clone()),
+                _ => {}
+            }
+            producer.properties.insert(name, value);
+        }
+        Some(PropertyOwner::Transition(i)) => {
+            let transition = &mut project.transitions[*i];
+            match name.as_str() {
+                "mlt_service" => transition.service = Some(value.clone()),
+                "a_track" => transition.a_track = value.trim().parse().ok(),
+                "b_track" => transition.b_track = value.trim().parse().ok(),
+                _ => {}
+            }
+            transition.properties.insert(name, value);
+        }
+        _ => {}
+    }
+}
+
+/// Make sure entries and tracks point at things that exist
+fn check_references(project: &MltProject, tractor_ids: &HashSet<String>) -> Result<(), MltParseError> {
+    let producer_ids: HashSet<&str> = project.producers.iter().map(|p| p.id.as_str()).collect();
+    let playlist_ids: HashSet<&str> = project.playlists.iter().map(|p| p.id.as_str()).collect();
+
+    for playlist in &project.playlists {
+        for item in &playlist.items {
+            if let MltPlaylistItem::Entry { producer, .. } = item {
+                if !producer_ids.contains(producer.as_str())
+                    && !playlist_ids.contains(producer.as_str())
+                    && !tractor_ids.contains(producer)
+                {
+                    return Err(MltParseError {
+                        message: format!(
+                            "Playlist '{}' references unknown producer '{}'",
+                            playlist.id, producer
+                        ),
+                        element: Some("entry".to_string()),
+                        position: 0,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================
+// WELL-FORMEDNESS
+// ============================================
+
+/// 1-based line and column (in characters) of a byte offset
+pub fn line_col(text: &str, offset: u64) -> (usize, usize) {
+    let mut end = (offset as usize).min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let before = &text[..end];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// Read through the whole document, returning the first XML syntax error
+/// (unclosed or mismatched tags, bad attributes, ...)
+pub fn check_well_formed(xml: &str) -> Result<(), MltParseError> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) => return Ok(()),
+            Ok(Event::Start(start)) | Ok(Event::Empty(start)) => {
+                // Attributes are parsed lazily, so force them here
+                for attr in start.attributes() {
+                    attr.map_err(|e| MltParseError {
+                        message: format!("Invalid attribute: {}", e),
+                        element: Some(String::from_utf8_lossy(start.name().as_ref()).to_string()),
+                        position: reader.buffer_position(),
+                    })?;
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                return Err(MltParseError {
+                    message: format!("Malformed XML: {}", e),
+                    element: None,
+                    position: reader.error_position(),
+                })
+            }
+        }
+    }
+}
+
+// ============================================
+// TAURI COMMANDS
+// ============================================
+
+/// Parse an MLT XML document into structured project metadata
+#[tauri::command]
+pub fn parse_mlt_xml(xml: String) -> Result<MltProject, MltParseError> {
+    parse_mlt(&xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mlt_builder::{Clip, Profile, Timeline, Track, TrackKind};
+
+    #[test]
+    fn test_round_trip_builder_output() {
+        let timeline = Timeline {
+            profile: Profile {
+                width: 1280,
+                height: 720,
+                frame_rate_num: 25,
+                frame_rate_den: 1,
+                display_aspect_num: None,
+                display_aspect_den: None,
+            },
+            tracks: vec![Track {
+                kind: TrackKind::Video,
+                clips: vec![Clip {
+                    resource: "/media/clip.mp4".to_string(),
+                    in_frame: 10,
+                    out_frame: 59,
+                    start_frame: 25,
+                    fade_in_frames: Some(5),
+                    fade_out_frames: None,
+                    filters: Vec::new(),
+                }],
+            }],
+            transitions: Vec::new(),
+            filters: Vec::new(),
+        };
+
+        let project = parse_mlt(&timeline.to_xml().unwrap()).unwrap();
+        let profile = project.profile.unwrap();
+        assert_eq!(profile.width, Some(1280));
+        assert_eq!(profile.fps, Some(25.0));
+
+        assert_eq!(project.producers.len(), 1);
+        assert_eq!(project.producers[0].resource.as_deref(), Some("/media/clip.mp4"));
+        assert_eq!(project.producers[0].in_frame, Some(10));
+
+        assert_eq!(project.playlists[0].items.len(), 2);
+        assert!(matches!(project.playlists[0].items[0], MltPlaylistItem::Blank { length: 25 }));
+        assert_eq!(project.tracks.len(), 1);
+    }
+
+    #[test]
+    fn test_rejects_non_mlt_document() {
+        let err = parse_mlt("<html><body/></html>").unwrap_err();
+        assert_eq!(err.element.as_deref(), Some("html"));
+    }
+
+    #[test]
+    fn test_reports_bad_attribute() {
+        let err = parse_mlt(r#"<mlt><producer id="p0" in="abc"/></mlt>"#).unwrap_err();
+        assert_eq!(err.element.as_deref(), Some("producer"));
+        assert!(err.message.contains("in=\"abc\""));
+    }
+
+    #[test]
+    fn test_well_formedness_error_has_line_and_column() {
+        let xml = "<mlt>\n  <producer id=\"p0\">\n  </playlist>\n</mlt>";
+        let err = check_well_formed(xml).unwrap_err();
+        let (line, _) = line_col(xml, err.position);
+        assert_eq!(line, 3);
+
+        assert!(check_well_formed("<mlt><producer id=\"p0\"/></mlt>").is_ok());
+        assert_eq!(line_col("ab\ncd", 4), (2, 2));
+    }
+}
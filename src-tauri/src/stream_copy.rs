@@ -0,0 +1,631 @@
+//! Stream-copy edits that never re-encode
+//!
+//! This module provides:
+//! - Lossless trimming of a clip to a time range
+//! - Joining clips that share codec and geometry
+//! - Remuxing into another container (e.g. MKV to MP4)
+//!
+//! These are instant, quality-preserving alternatives to a melt render for
+//! simple edits. Cuts can only land on keyframes.
+
+use std::fs;
+use std::path::Path;
+
+use ffmpeg_next as ffmpeg;
+use ffmpeg_next::media::Type;
+
+use crate::video_decoder::{self, VideoError, VideoInfo};
+
+/// Once any stream is this far past the end of a trim, every interleaved
+/// stream is too, so reading stops
+const TRIM_READ_MARGIN_SECS: f64 = 1.0;
+
+// ============================================
+// HELPERS
+// ============================================
+
+fn open_source(path: &str) -> Result<ffmpeg::format::context::Input, VideoError> {
+    ffmpeg::format::input(&path).map_err(|e| VideoError {
+        message: format!("Failed to open media file '{}': {}", path, e),
+        code: "OPEN_ERROR".to_string(),
+    })
+}
+
+/// Create the output context (container chosen by extension), creating the
+/// parent directory if needed
+fn create_output(out_path: &str) -> Result<ffmpeg::format::context::Output, VideoError> {
+    if let Some(parent) = Path::new(out_path).parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    ffmpeg::format::output(&out_path).map_err(|e| VideoError {
+        message: format!("Failed to create output '{}': {}", out_path, e),
+        code: "OUTPUT_ERROR".to_string(),
+    })
+}
+
+/// Refuse an `out_path` that is the same file as `source_path`: creating the
+/// output would truncate the source while it is still being read
+fn check_distinct_output(source_path: &str, out_path: &str) -> Result<(), VideoError> {
+    // An output that doesn't exist yet can't be the source
+    let Ok(out) = Path::new(out_path).canonicalize() else {
+        return Ok(());
+    };
+    if Path::new(source_path).canonicalize().is_ok_and(|source| source == out) {
+        return Err(VideoError {
+            message: format!("Output '{}' would overwrite its source", out_path),
+            code: "SAME_FILE".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Create the output at `out_path` and fill it with `write`, removing the
+/// partial file if any step fails
+fn write_output<T>(
+    out_path: &str,
+    write: impl FnOnce(&mut ffmpeg::format::context::Output) -> Result<T, VideoError>,
+) -> Result<T, VideoError> {
+    let mut output = create_output(out_path)?;
+    let result = write(&mut output);
+    if result.is_err() {
+        drop(output);
+        let _ = fs::remove_file(out_path);
+    }
+    result
+}
+
+/// Stream types carried over by stream-copy operations
+fn is_copyable(medium: Type) -> bool {
+    matches!(medium, Type::Video | Type::Audio | Type::Subtitle)
+}
+
+/// Add an output stream copying each video, audio and subtitle stream of
+/// `input`. Returns the output index for each input stream (None = dropped).
+fn add_copy_streams(
+    input: &ffmpeg::format::context::Input,
+    output: &mut ffmpeg::format::context::Output,
+) -> Result<Vec<Option<usize>>, VideoError> {
+    let mut mapping = Vec::new();
+    for stream in input.streams() {
+        if !is_copyable(stream.parameters().medium()) {
+            mapping.push(None);
+            continue;
+        }
+        let mut out_stream = output.add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))?;
+        out_stream.set_parameters(stream.parameters());
+        // SAFETY: codecpar is owned by the stream. Clearing the tag lets the
+        // output muxer pick its own instead of rejecting the source's.
+        unsafe {
+            (*out_stream.parameters().as_mut_ptr()).codec_tag = 0;
+        }
+        mapping.push(Some(out_stream.index()));
+    }
+    if mapping.iter().all(Option::is_none) {
+        return Err(VideoError {
+            message: "No audio or video streams to copy".to_string(),
+            code: "NO_STREAMS".to_string(),
+        });
+    }
+    Ok(mapping)
+}
+
+/// Container start time in seconds (0 when unknown)
+fn container_start_secs(input: &ffmpeg::format::context::Input) -> f64 {
+    match input.start_time() {
+        ts if ts == ffmpeg::ffi::AV_NOPTS_VALUE => 0.0,
+        ts => ts as f64 / ffmpeg::ffi::AV_TIME_BASE as f64,
+    }
+}
+
+// ============================================
+// TRIM
+// ============================================
+
+/// Copy `start_secs..end_secs` of `source_path` to `out_path` without
+/// re-encoding. Returns the output path.
+///
+/// The start snaps back to the keyframe at or before `start_secs` (a copy
+/// can't begin mid-GOP), so the output may begin slightly early.
+pub fn lossless_trim(
+    source_path: &str,
+    start_secs: f64,
+    end_secs: f64,
+    out_path: &str,
+) -> Result<String, VideoError> {
+    if !(start_secs >= 0.0 && end_secs > start_secs) {
+        return Err(VideoError {
+            message: format!("Invalid trim range {}..{}", start_secs, end_secs),
+            code: "INVALID_RANGE".to_string(),
+        });
+    }
+
+    check_distinct_output(source_path, out_path)?;
+
+    let mut input = open_source(source_path)?;
+    let time_bases: Vec<ffmpeg::Rational> = input.streams().map(|s| s.time_base()).collect();
+    let video_index = input.streams().best(Type::Video).map(|s| s.index());
+
+    // Packet times are absolute; the requested range is relative to the start
+    let origin = container_start_secs(&input);
+    let end = origin + end_secs;
+    let seek_ts = ((origin + start_secs) * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
+    input.seek(seek_ts, ..=seek_ts)?;
+
+    write_output(out_path, |output| {
+        let mapping = add_copy_streams(&input, output)?;
+        output.set_metadata(input.metadata().to_owned());
+        output.write_header()?;
+
+        // The cut starts at the first video packet after the seek (the keyframe);
+        // sources without video cut exactly
+        let mut cut_start = video_index.is_none().then_some(origin + start_secs);
+
+        for (stream, mut packet) in input.packets() {
+            let index = stream.index();
+            let Some(out_index) = mapping.get(index).copied().flatten() else {
+                continue;
+            };
+            let time_base = time_bases[index];
+            let Some(ts) = packet.pts().or(packet.dts()) else {
+                continue;
+            };
+            let secs = ts as f64 * f64::from(time_base);
+
+            if cut_start.is_none() {
+                // Wait for the keyframe before copying other streams
+                if Some(index) != video_index {
+                    continue;
+                }
+                cut_start = Some(secs);
+            }
+            let cut = cut_start.unwrap_or(secs);
+            if secs > end + TRIM_READ_MARGIN_SECS {
+                break;
+            }
+            if secs >= end {
+                continue;
+            }
+            // Video packets after the keyframe may be reordered before it; keep
+            // them so the GOP still decodes
+            if secs < cut && Some(index) != video_index {
+                continue;
+            }
+
+            let offset = (cut / f64::from(time_base)).round() as i64;
+            packet.set_pts(packet.pts().map(|ts| ts - offset));
+            packet.set_dts(packet.dts().map(|ts| ts - offset));
+            let out_time_base = output.stream(out_index).map(|s| s.time_base()).unwrap_or(time_base);
+            packet.rescale_ts(time_base, out_time_base);
+            packet.set_position(-1);
+            packet.set_stream(out_index);
+            packet.write_interleaved(output)?;
+        }
+
+        output.write_trailer()?;
+        Ok(out_path.to_string())
+    })
+}
+
+// ============================================
+// CONCAT
+// ============================================
+
+/// Codec, sample rate, sample format and channel layout of an audio stream
+fn audio_signature(stream: &ffmpeg::format::stream::Stream) -> Option<String> {
+    let params = stream.parameters();
+    if params.medium() != Type::Audio {
+        return None;
+    }
+    let name = params.id().name();
+    let audio = ffmpeg::codec::context::Context::from_parameters(params)
+        .ok()?
+        .decoder()
+        .audio()
+        .ok()?;
+    Some(format!(
+        "{} {} Hz {:?} {}ch {:?}",
+        name,
+        audio.rate(),
+        audio.format(),
+        audio.channels(),
+        audio.channel_layout()
+    ))
+}
+
+/// What must match between clips for a stream-copy join: the video's codec
+/// and geometry and every audio stream's parameters
+fn concat_signature(info: &VideoInfo, input: &ffmpeg::format::context::Input) -> String {
+    let mut signature = format!(
+        "{} {}x{} {} {:.3} fps",
+        info.codec, info.width, info.height, info.pixel_format, info.fps
+    );
+    for audio in input.streams().filter_map(|stream| audio_signature(&stream)) {
+        signature.push_str(", ");
+        signature.push_str(&audio);
+    }
+    signature
+}
+
+/// Media types of the streams a stream-copy carries over, in order
+fn copied_media(input: &ffmpeg::format::context::Input) -> Vec<Type> {
+    input
+        .streams()
+        .map(|stream| stream.parameters().medium())
+        .filter(|&medium| is_copyable(medium))
+        .collect()
+}
+
+/// Map `input`'s streams onto the output streams created for the first
+/// clip, whose copied media types are `layout`. None if the layout differs.
+fn match_stream_layout(
+    input: &ffmpeg::format::context::Input,
+    layout: &[Type],
+) -> Option<Vec<Option<usize>>> {
+    let mut next = 0;
+    let mut mapping = Vec::new();
+    for stream in input.streams() {
+        let medium = stream.parameters().medium();
+        if !is_copyable(medium) {
+            mapping.push(None);
+            continue;
+        }
+        if layout.get(next) != Some(&medium) {
+            return None;
+        }
+        mapping.push(Some(next));
+        next += 1;
+    }
+    (next == layout.len()).then_some(mapping)
+}
+
+/// Join `paths` end to end into `out_path` without re-encoding. Every clip
+/// must match the first one's video codec, resolution, pixel format, frame
+/// rate, audio parameters and stream layout; otherwise nothing is written
+/// and the error lists the mismatched files. Returns the output path.
+pub fn concat_clips(paths: &[String], out_path: &str) -> Result<String, VideoError> {
+    if paths.len() < 2 {
+        return Err(VideoError {
+            message: "concat needs at least two clips".to_string(),
+            code: "INVALID_INPUT".to_string(),
+        });
+    }
+    for path in paths {
+        check_distinct_output(path, out_path)?;
+    }
+
+    let inputs = paths
+        .iter()
+        .map(|path| open_source(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut signatures = Vec::with_capacity(paths.len());
+    for (path, input) in paths.iter().zip(&inputs) {
+        let info = video_decoder::get_video_info(path)?;
+        signatures.push(concat_signature(&info, input));
+    }
+    let mismatched: Vec<String> = paths
+        .iter()
+        .zip(&signatures)
+        .skip(1)
+        .filter(|(_, signature)| **signature != signatures[0])
+        .map(|(path, signature)| format!("{} ({})", path, signature))
+        .collect();
+    if !mismatched.is_empty() {
+        return Err(VideoError {
+            message: format!(
+                "Clips must match {} ({}): {}",
+                paths[0],
+                signatures[0],
+                mismatched.join(", ")
+            ),
+            code: "INCOMPATIBLE_CLIPS".to_string(),
+        });
+    }
+
+    // Validate every clip before anything is written
+    let layout = copied_media(&inputs[0]);
+    let mut mappings = Vec::with_capacity(inputs.len());
+    for (path, input) in paths.iter().zip(&inputs) {
+        mappings.push(match_stream_layout(input, &layout).ok_or_else(|| VideoError {
+            message: format!("{} has different audio/video streams than {}", path, paths[0]),
+            code: "INCOMPATIBLE_CLIPS".to_string(),
+        })?);
+    }
+
+    write_output(out_path, |output| {
+        add_copy_streams(&inputs[0], output)?;
+        output.write_header()?;
+
+        // Each clip starts where the longest stream of the previous one ended
+        let mut offset_secs = 0.0;
+        for (mut input, mapping) in inputs.into_iter().zip(mappings) {
+            let origin = container_start_secs(&input);
+            let time_bases: Vec<ffmpeg::Rational> = input.streams().map(|s| s.time_base()).collect();
+            let mut clip_end_secs = offset_secs;
+
+            for (stream, mut packet) in input.packets() {
+                let index = stream.index();
+                let Some(out_index) = mapping.get(index).copied().flatten() else {
+                    continue;
+                };
+                let time_base = time_bases[index];
+                let shift = ((offset_secs - origin) / f64::from(time_base)).round() as i64;
+                packet.set_pts(packet.pts().map(|ts| ts + shift));
+                packet.set_dts(packet.dts().map(|ts| ts + shift));
+                if let Some(ts) = packet.pts().or(packet.dts()) {
+                    let end = (ts + packet.duration().max(0)) as f64 * f64::from(time_base);
+                    clip_end_secs = clip_end_secs.max(end);
+                }
+
+                let out_time_base =
+                    output.stream(out_index).map(|s| s.time_base()).unwrap_or(time_base);
+                packet.rescale_ts(time_base, out_time_base);
+                packet.set_position(-1);
+                packet.set_stream(out_index);
+                packet.write_interleaved(output)?;
+            }
+            offset_secs = clip_end_secs;
+        }
+
+        output.write_trailer()?;
+        Ok(out_path.to_string())
+    })
+}
+
+// ============================================
+// REMUX
+// ============================================
+
+/// Streams of `input` whose codec the output container can't hold
+fn incompatible_streams(
+    input: &ffmpeg::format::context::Input,
+    output: &ffmpeg::format::context::Output,
+) -> Vec<String> {
+    input
+        .streams()
+        .filter(|stream| is_copyable(stream.parameters().medium()))
+        .filter_map(|stream| {
+            let params = stream.parameters();
+            let id = params.id();
+            // SAFETY: the output format is static muxer data owned by FFmpeg.
+            // 0 means unsupported; negative means the muxer doesn't say, so
+            // let it try.
+            let supported = unsafe {
+                ffmpeg::ffi::avformat_query_codec(
+                    output.format().as_ptr(),
+                    id.into(),
+                    ffmpeg::ffi::FF_COMPLIANCE_NORMAL as i32,
+                )
+            };
+            (supported == 0).then(|| {
+                let medium = format!("{:?}", params.medium()).to_lowercase();
+                format!("stream #{} ({} {})", stream.index(), medium, id.name())
+            })
+        })
+        .collect()
+}
+
+/// Copy every video, audio and subtitle stream of `source_path` into the
+/// container implied by `out_path`'s extension, without re-encoding. Fails
+/// with INCOMPATIBLE_CODEC if any stream's codec isn't allowed in that
+/// container. Nothing is left at `out_path` on failure. Returns the output
+/// path.
+pub fn remux(source_path: &str, out_path: &str) -> Result<String, VideoError> {
+    check_distinct_output(source_path, out_path)?;
+    let mut input = open_source(source_path)?;
+
+    write_output(out_path, |output| {
+        let incompatible = incompatible_streams(&input, output);
+        if !incompatible.is_empty() {
+            return Err(VideoError {
+                message: format!(
+                    "{} can't hold {}; re-encode instead or pick another container",
+                    output.format().name(),
+                    incompatible.join(", ")
+                ),
+                code: "INCOMPATIBLE_CODEC".to_string(),
+            });
+        }
+
+        let mapping = add_copy_streams(&input, output)?;
+        let time_bases: Vec<ffmpeg::Rational> = input.streams().map(|s| s.time_base()).collect();
+        output.set_metadata(input.metadata().to_owned());
+        output.write_header()?;
+
+        for (stream, mut packet) in input.packets() {
+            let index = stream.index();
+            let Some(out_index) = mapping.get(index).copied().flatten() else {
+                continue;
+            };
+            let time_base = time_bases[index];
+            let out_time_base = output.stream(out_index).map(|s| s.time_base()).unwrap_or(time_base);
+            packet.rescale_ts(time_base, out_time_base);
+            packet.set_position(-1);
+            packet.set_stream(out_index);
+            packet.write_interleaved(output)?;
+        }
+
+        output.write_trailer()?;
+        Ok(out_path.to_string())
+    })
+}
+
+// ============================================
+// TAURI COMMANDS
+// ============================================
+
+/// Tauri command to trim a clip without re-encoding (start snaps to a keyframe)
+#[tauri::command]
+pub async fn cmd_lossless_trim(
+    source_path: String,
+    start_secs: f64,
+    end_secs: f64,
+    out_path: String,
+) -> Result<String, VideoError> {
+    tokio::task::spawn_blocking(move || lossless_trim(&source_path, start_secs, end_secs, &out_path))
+        .await?
+}
+
+/// Tauri command to join same-codec clips without re-encoding
+#[tauri::command]
+pub async fn cmd_concat_clips(paths: Vec<String>, out_path: String) -> Result<String, VideoError> {
+    tokio::task::spawn_blocking(move || concat_clips(&paths, &out_path)).await?
+}
+
+/// Tauri command to change a file's container without re-encoding
+#[tauri::command]
+pub async fn cmd_remux(source_path: String, out_path: String) -> Result<String, VideoError> {
+    tokio::task::spawn_blocking(move || remux(&source_path, &out_path)).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::video_decoder::tests::write_test_clip;
+
+    #[test]
+    fn test_trim_rejects_invalid_range() {
+        for (start, end) in [(5.0, 2.0), (-1.0, 2.0), (3.0, 3.0), (f64::NAN, 1.0)] {
+            let err = lossless_trim("clip.mp4", start, end, "out.mp4").unwrap_err();
+            assert_eq!(err.code, "INVALID_RANGE");
+        }
+    }
+
+    #[test]
+    fn test_concat_needs_two_clips() {
+        let err = concat_clips(&["only.mp4".to_string()], "out.mp4").unwrap_err();
+        assert_eq!(err.code, "INVALID_INPUT");
+    }
+
+    /// Size of a file, 0 if missing
+    fn file_len(path: &Path) -> u64 {
+        fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+    }
+
+    #[test]
+    fn test_trim_copies_the_requested_range() {
+        // 4 s at 25 fps with a keyframe every 12 frames (0.48 s)
+        let pts: Vec<i64> = (0..100).collect();
+        let source = write_test_clip("trim_source", 25, &pts);
+        let out = source.with_file_name(format!("trimmed_{}.mp4", uuid::Uuid::new_v4()));
+        let result = lossless_trim(source.to_str().unwrap(), 1.0, 3.0, out.to_str().unwrap());
+        let info = video_decoder::get_video_info(out.to_str().unwrap());
+        let _ = fs::remove_file(&source);
+        let _ = fs::remove_file(&out);
+
+        result.unwrap();
+        // The start snaps back to the keyframe at 0.96 s
+        let duration = info.unwrap().duration_secs;
+        assert!((duration - 2.04).abs() < 0.1, "duration {}", duration);
+    }
+
+    #[test]
+    fn test_trim_failure_leaves_no_output() {
+        let pts: Vec<i64> = (0..50).collect();
+        let source = write_test_clip("trim_fail", 25, &pts);
+        // The WebM muxer rejects MPEG-4 video when writing the header
+        let out = source.with_extension("webm");
+        let result = lossless_trim(source.to_str().unwrap(), 0.0, 1.0, out.to_str().unwrap());
+        let out_exists = out.exists();
+        let _ = fs::remove_file(&source);
+        let _ = fs::remove_file(&out);
+
+        assert!(result.is_err());
+        assert!(!out_exists);
+    }
+
+    #[test]
+    fn test_concat_joins_matching_clips() {
+        let pts: Vec<i64> = (0..50).collect();
+        let first = write_test_clip("concat_a", 25, &pts);
+        let second = write_test_clip("concat_b", 25, &pts);
+        let out = first.with_file_name(format!("joined_{}.mp4", uuid::Uuid::new_v4()));
+        let paths = [first.to_string_lossy().to_string(), second.to_string_lossy().to_string()];
+        let result = concat_clips(&paths, out.to_str().unwrap());
+        let info = video_decoder::get_video_info(out.to_str().unwrap());
+        for path in [&first, &second, &out] {
+            let _ = fs::remove_file(path);
+        }
+
+        result.unwrap();
+        let duration = info.unwrap().duration_secs;
+        assert!((duration - 4.0).abs() < 0.1, "duration {}", duration);
+    }
+
+    #[test]
+    fn test_concat_mismatch_writes_nothing() {
+        let first = write_test_clip("concat_25", 25, &(0..25).collect::<Vec<i64>>());
+        let second = write_test_clip("concat_30", 30, &(0..30).collect::<Vec<i64>>());
+        let out = first.with_file_name(format!("joined_{}.mp4", uuid::Uuid::new_v4()));
+        let paths = [first.to_string_lossy().to_string(), second.to_string_lossy().to_string()];
+        let result = concat_clips(&paths, out.to_str().unwrap());
+        let out_exists = out.exists();
+        for path in [&first, &second, &out] {
+            let _ = fs::remove_file(path);
+        }
+
+        assert_eq!(result.unwrap_err().code, "INCOMPATIBLE_CLIPS");
+        assert!(!out_exists);
+    }
+
+    #[test]
+    fn test_remux_copies_into_another_container() {
+        let pts: Vec<i64> = (0..50).collect();
+        let source = write_test_clip("remux_source", 25, &pts);
+        let out = source.with_extension("mkv");
+        let result = remux(source.to_str().unwrap(), out.to_str().unwrap());
+        let source_info = video_decoder::get_video_info(source.to_str().unwrap());
+        let out_info = video_decoder::get_video_info(out.to_str().unwrap());
+        let _ = fs::remove_file(&source);
+        let _ = fs::remove_file(&out);
+
+        result.unwrap();
+        let (source_info, out_info) = (source_info.unwrap(), out_info.unwrap());
+        assert_eq!(out_info.codec, source_info.codec);
+        assert_eq!((out_info.width, out_info.height), (source_info.width, source_info.height));
+        assert!((out_info.duration_secs - source_info.duration_secs).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_remux_incompatible_codec_writes_nothing() {
+        let pts: Vec<i64> = (0..25).collect();
+        let source = write_test_clip("remux_webm", 25, &pts);
+        // WebM only carries VP8/VP9/AV1 video, not MPEG-4
+        let out = source.with_extension("webm");
+        let err = remux(source.to_str().unwrap(), out.to_str().unwrap()).unwrap_err();
+        let out_exists = out.exists();
+        let _ = fs::remove_file(&source);
+        let _ = fs::remove_file(&out);
+
+        assert_eq!(err.code, "INCOMPATIBLE_CODEC");
+        assert!(!out_exists);
+    }
+
+    #[test]
+    fn test_output_over_source_rejected() {
+        let pts: Vec<i64> = (0..25).collect();
+        let source = write_test_clip("same_file", 25, &pts);
+        let path = source.to_str().unwrap();
+        let len = file_len(&source);
+
+        let remux_err = remux(path, path).unwrap_err();
+        let trim_err = lossless_trim(path, 0.0, 0.5, path).unwrap_err();
+        let concat_err = concat_clips(&[path.to_string(), path.to_string()], path).unwrap_err();
+        let len_after = file_len(&source);
+        let _ = fs::remove_file(&source);
+
+        for err in [remux_err, trim_err, concat_err] {
+            assert_eq!(err.code, "SAME_FILE");
+        }
+        assert_eq!(len_after, len);
+    }
+
+    #[test]
+    fn test_remux_missing_source_writes_nothing() {
+        let out = std::env::temp_dir().join(format!("remux_{}.mp4", uuid::Uuid::new_v4()));
+        let err = remux("does-not-exist.mkv", out.to_str().unwrap()).unwrap_err();
+        assert_eq!(err.code, "OPEN_ERROR");
+        assert!(!out.exists());
+    }
+}
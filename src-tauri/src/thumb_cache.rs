@@ -0,0 +1,304 @@
+//! On-disk thumbnail cache
+//!
+//! This module provides:
+//! - A cache under `~/.dreamcloud/thumb-cache/` keyed by source content
+//!   hash, timestamp, width and quality, so thumbnails survive across
+//!   sessions and renamed/moved sources still hit
+//! - Least-recently-used eviction once the cache outgrows `MAX_CACHE_BYTES`
+//! - Size accounting and clearing
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::video_decoder::VideoError;
+
+// ============================================
+// TYPES
+// ============================================
+
+/// Files and bytes currently held by the cache
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ThumbCacheStats {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Identifies one cached thumbnail
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThumbKey {
+    /// `content_hash` of the source
+    pub hash: String,
+    pub timestamp_ms: u64,
+    /// Output width, or None for the source's native width
+    pub width: Option<u32>,
+    pub quality: u8,
+}
+
+impl ThumbKey {
+    fn file_name(&self) -> String {
+        let width = self.width.map_or_else(|| "native".to_string(), |w| w.to_string());
+        format!("{}_{}_{}_q{}.jpg", self.hash, self.timestamp_ms, width, self.quality)
+    }
+}
+
+// ============================================
+// CONTENT HASH
+// ============================================
+
+/// Bytes read from each of the start, middle and end of a source
+const HASH_SAMPLE_BYTES: u64 = 64 * 1024;
+
+lazy_static::lazy_static! {
+    /// Hashes by (path, size, mtime), so each source is read once per session
+    static ref HASH_MEMO: Mutex<HashMap<(PathBuf, u64, SystemTime), String>> = Mutex::new(HashMap::new());
+}
+
+/// Hex SHA-256 over the file size, mtime and samples from its start, middle
+/// and end. Sampling keeps hashing multi-gigabyte sources instant; the mtime
+/// catches in-place edits that leave the sampled regions untouched. Renames
+/// and moves keep the mtime, so they still hit.
+fn hash_file_samples(path: &Path, len: u64, modified: SystemTime) -> std::io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    hasher.update(len.to_le_bytes());
+    let mtime = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+    hasher.update(mtime.as_nanos().to_le_bytes());
+
+    let last = len.saturating_sub(HASH_SAMPLE_BYTES);
+    let mut sample = Vec::with_capacity(HASH_SAMPLE_BYTES as usize);
+    for offset in [0, last / 2, last] {
+        file.seek(SeekFrom::Start(offset))?;
+        sample.clear();
+        (&mut file).take(HASH_SAMPLE_BYTES).read_to_end(&mut sample)?;
+        hasher.update(&sample);
+    }
+
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Content hash of a source file, or None if it isn't a readable file
+/// (image sequence patterns, URLs)
+pub fn content_hash(path: &str) -> Option<String> {
+    let path = Path::new(path);
+    let meta = fs::metadata(path).ok().filter(|m| m.is_file())?;
+    let memo_key = (path.to_path_buf(), meta.len(), meta.modified().ok()?);
+
+    if let Some(hash) = HASH_MEMO.lock().ok().and_then(|memo| memo.get(&memo_key).cloned()) {
+        return Some(hash);
+    }
+    let hash = hash_file_samples(path, memo_key.1, memo_key.2).ok()?;
+    if let Ok(mut memo) = HASH_MEMO.lock() {
+        memo.insert(memo_key, hash.clone());
+    }
+    Some(hash)
+}
+
+// ============================================
+// CACHE
+// ============================================
+
+/// Cache size above which the least recently used thumbnails are evicted
+const MAX_CACHE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Eviction scans the whole directory, so only every this many stores run it
+const PUTS_PER_EVICTION: usize = 64;
+
+static PUTS: AtomicUsize = AtomicUsize::new(0);
+
+fn cache_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".dreamcloud").join("thumb-cache"))
+}
+
+/// Cached thumbnail as base64 JPEG
+pub fn get(key: &ThumbKey) -> Option<String> {
+    let path = cache_dir()?.join(key.file_name());
+    let bytes = fs::read(&path).ok()?;
+    // The mtime doubles as the last-use time for eviction
+    if let Ok(file) = fs::File::options().write(true).open(&path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+    Some(BASE64.encode(bytes))
+}
+
+/// Delete the least recently used thumbnails in `dir` until the rest fit in
+/// `max_bytes`. In-flight temp files (dot-prefixed) are left alone.
+/// Returns the bytes freed.
+fn evict_lru(dir: &Path, max_bytes: u64) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some((meta.modified().unwrap_or(UNIX_EPOCH), meta.len(), entry.path()))
+        })
+        .collect();
+    files.sort();
+
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    let mut freed = 0;
+    for (_, len, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= len;
+            freed += len;
+        }
+    }
+    freed
+}
+
+/// Store a base64 JPEG thumbnail. Failures are ignored; the cache is only
+/// an optimisation.
+pub fn put(key: &ThumbKey, image_base64: &str) {
+    let (Some(dir), Ok(bytes)) = (cache_dir(), BASE64.decode(image_base64)) else {
+        return;
+    };
+    if fs::create_dir_all(&dir).is_err() {
+        return;
+    }
+    // Write then rename so a concurrent reader never sees a partial file
+    let path = dir.join(key.file_name());
+    let temp = dir.join(format!(".{}.{}", key.file_name(), uuid::Uuid::new_v4()));
+    if fs::write(&temp, bytes).is_ok() && fs::rename(&temp, &path).is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+
+    if PUTS.fetch_add(1, Ordering::Relaxed) % PUTS_PER_EVICTION == 0 {
+        evict_lru(&dir, MAX_CACHE_BYTES);
+    }
+}
+
+/// Files and bytes in the cache
+pub fn stats() -> Result<ThumbCacheStats, VideoError> {
+    let mut stats = ThumbCacheStats::default();
+    let Some(dir) = cache_dir().filter(|dir| dir.is_dir()) else {
+        return Ok(stats);
+    };
+    for entry in fs::read_dir(dir)?.flatten() {
+        if let Ok(meta) = entry.metadata() {
+            if meta.is_file() {
+                stats.files += 1;
+                stats.bytes += meta.len();
+            }
+        }
+    }
+    Ok(stats)
+}
+
+/// Delete every cached thumbnail. Returns what was freed.
+pub fn clear() -> Result<ThumbCacheStats, VideoError> {
+    let freed = stats()?;
+    if let Some(dir) = cache_dir().filter(|dir| dir.is_dir()) {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(freed)
+}
+
+// ============================================
+// TAURI COMMANDS
+// ============================================
+
+/// Tauri command reporting the thumbnail cache's size
+#[tauri::command]
+pub async fn cmd_thumbnail_cache_size() -> Result<ThumbCacheStats, VideoError> {
+    tokio::task::spawn_blocking(stats).await?
+}
+
+/// Tauri command to empty the thumbnail cache
+#[tauri::command]
+pub async fn cmd_clear_thumbnail_cache() -> Result<ThumbCacheStats, VideoError> {
+    tokio::task::spawn_blocking(clear).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_file_name() {
+        let key = ThumbKey {
+            hash: "abc".to_string(),
+            timestamp_ms: 1500,
+            width: None,
+            quality: 70,
+        };
+        assert_eq!(key.file_name(), "abc_1500_native_q70.jpg");
+        assert_eq!(
+            ThumbKey { width: Some(320), ..key }.file_name(),
+            "abc_1500_320_q70.jpg"
+        );
+    }
+
+    #[test]
+    fn test_content_hash_follows_content_not_path() {
+        let dir = std::env::temp_dir();
+        let a = dir.join(format!("hash_a_{}.bin", uuid::Uuid::new_v4()));
+        let b = dir.join(format!("hash_b_{}.bin", uuid::Uuid::new_v4()));
+        fs::write(&a, vec![7u8; 300_000]).unwrap();
+        fs::write(&b, vec![7u8; 300_000]).unwrap();
+        // Same mtime, as a moved copy keeps
+        let mtime = fs::metadata(&a).unwrap().modified().unwrap();
+        fs::File::options().write(true).open(&b).unwrap().set_modified(mtime).unwrap();
+
+        let hash_a = content_hash(a.to_str().unwrap()).unwrap();
+        assert_eq!(hash_a.len(), 64);
+        assert_eq!(Some(hash_a.clone()), content_hash(b.to_str().unwrap()));
+
+        // A different size also changes the memo key, whatever the mtime
+        fs::write(&b, vec![8u8; 300_001]).unwrap();
+        assert_ne!(Some(hash_a), content_hash(b.to_str().unwrap()));
+        assert_eq!(content_hash("/definitely/not/here.mp4"), None);
+
+        let _ = fs::remove_file(&a);
+        let _ = fs::remove_file(&b);
+    }
+
+    #[test]
+    fn test_content_hash_sees_edits_outside_samples() {
+        let path = std::env::temp_dir().join(format!("hash_edit_{}.bin", uuid::Uuid::new_v4()));
+        fs::write(&path, vec![7u8; 300_000]).unwrap();
+        let before = content_hash(path.to_str().unwrap()).unwrap();
+
+        // Byte 100_000 lies between the start and middle samples
+        let mut edited = vec![7u8; 300_000];
+        edited[100_000] = 8;
+        fs::write(&path, edited).unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(10);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+
+        assert_ne!(content_hash(path.to_str().unwrap()), Some(before));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_evict_lru_removes_oldest_first() {
+        let dir = std::env::temp_dir().join(format!("thumb-evict-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let now = SystemTime::now();
+        for (name, age_secs) in [("old.jpg", 30), ("mid.jpg", 20), ("new.jpg", 10), (".partial", 40)] {
+            let path = dir.join(name);
+            fs::write(&path, vec![0u8; 100]).unwrap();
+            let mtime = now - std::time::Duration::from_secs(age_secs);
+            fs::File::options().write(true).open(&path).unwrap().set_modified(mtime).unwrap();
+        }
+
+        assert_eq!(evict_lru(&dir, 250), 100);
+        assert!(!dir.join("old.jpg").exists());
+        assert!(dir.join("mid.jpg").exists() && dir.join("new.jpg").exists());
+        assert!(dir.join(".partial").exists());
+        assert_eq!(evict_lru(&dir, 1000), 0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,8 +1,12 @@
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
 
 use ffmpeg_next as ffmpeg;
 use ffmpeg_next::format::{input, Pixel};
@@ -10,6 +14,9 @@ use ffmpeg_next::media::Type;
 use ffmpeg_next::software::scaling::{context::Context as ScalingContext, flag::Flags};
 use ffmpeg_next::util::frame::video::Video as VideoFrame;
 
+use crate::lut::Lut3d;
+use crate::thumb_cache;
+
 /// Video metadata information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VideoInfo {
@@ -17,18 +24,287 @@ pub struct VideoInfo {
     pub duration_secs: f64,
     /// Frames per second
     pub fps: f64,
+    /// True when the container reported no usable frame rate and `fps` is a
+    /// default guess (pass `assumed_fps` to override it)
+    #[serde(default)]
+    pub fps_is_estimated: bool,
     /// Video width in pixels
     pub width: u32,
     /// Video height in pixels
     pub height: u32,
+    /// Clockwise display rotation in degrees (0, 90, 180 or 270)
+    #[serde(default)]
+    pub rotation: u32,
+    /// Width as displayed, after rotation (use this for layout)
+    #[serde(default)]
+    pub display_width: u32,
+    /// Height as displayed, after rotation (use this for layout)
+    #[serde(default)]
+    pub display_height: u32,
     /// Total number of frames (estimated)
     pub frame_count: u64,
     /// Video codec name
     pub codec: String,
     /// Bitrate in bits per second (if available)
     pub bitrate: Option<u64>,
+    /// Hardware accelerator in use for decoding (None = software decode)
+    pub hw_accel: Option<HwAccel>,
+    /// Pixel format name, e.g. "yuv420p10le"
+    pub pixel_format: String,
+    /// Bits per luma sample (8 for most SDR content, 10+ for HDR)
+    pub bit_depth: u8,
+    /// Color primaries, e.g. "bt709" or "bt2020" (None if unspecified)
+    pub color_primaries: Option<String>,
+    /// Transfer characteristics, e.g. "smpte2084" for PQ HDR (None if unspecified)
+    pub transfer_characteristics: Option<String>,
+    /// "tv" (limited) or "pc" (full) range (None if unspecified)
+    pub color_range: Option<String>,
+    /// Variable frame rate (screen recordings, some phone clips): frame
+    /// indexes derived from `fps` are approximate, so address frames by
+    /// timestamp instead. Handles from `open_video` don't sample frames,
+    /// so theirs only reflects the container's rates.
+    #[serde(default)]
+    pub is_vfr: bool,
+    /// Slowest instantaneous frame rate among the sampled frames
+    #[serde(default)]
+    pub min_fps: Option<f64>,
+    /// Fastest instantaneous frame rate among the sampled frames
+    #[serde(default)]
+    pub max_fps: Option<f64>,
+    /// Mean frame rate over the sampled frames
+    #[serde(default)]
+    pub avg_fps: Option<f64>,
+}
+
+/// Per-stream details from probing a media file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamInfo {
+    /// Stream index within the container
+    pub index: usize,
+    /// "video", "audio", "subtitle", "data", "attachment" or "unknown"
+    pub media_type: String,
+    /// Codec name
+    pub codec: String,
+    /// Language tag from the stream metadata (e.g. "eng")
+    pub language: Option<String>,
+    /// Title from the stream metadata (e.g. "Director's commentary")
+    pub title: Option<String>,
+    /// Whether the stream is flagged as the default for its type
+    pub is_default: bool,
+    /// Stream duration in seconds (if known)
+    pub duration_secs: Option<f64>,
+    /// Bitrate in bits per second (if known)
+    pub bitrate: Option<u64>,
+    /// Video only: width in pixels
+    pub width: Option<u32>,
+    /// Video only: height in pixels
+    pub height: Option<u32>,
+    /// Video only: frames per second
+    pub fps: Option<f64>,
+    /// Audio only: sample rate in Hz
+    pub sample_rate: Option<u32>,
+    /// Audio only: channel count
+    pub channels: Option<u16>,
+}
+
+/// Hardware decode backends that can be requested for frame extraction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HwAccel {
+    Vaapi,
+    Videotoolbox,
+    Cuda,
+}
+
+impl HwAccel {
+    fn device_type(&self) -> ffmpeg::ffi::AVHWDeviceType {
+        use ffmpeg::ffi::AVHWDeviceType::*;
+        match self {
+            HwAccel::Vaapi => AV_HWDEVICE_TYPE_VAAPI,
+            HwAccel::Videotoolbox => AV_HWDEVICE_TYPE_VIDEOTOOLBOX,
+            HwAccel::Cuda => AV_HWDEVICE_TYPE_CUDA,
+        }
+    }
+}
+
+/// swscale interpolation used when converting frames to RGB
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalingAlgorithm {
+    /// Fast but slightly soft; right for scrubbing
+    #[default]
+    Bilinear,
+    Bicubic,
+    /// Sharpest, for export-quality stills
+    Lanczos,
+    /// Nearest neighbor, for pixel-exact inspection
+    Neighbor,
+}
+
+impl ScalingAlgorithm {
+    fn flags(&self) -> Flags {
+        match self {
+            ScalingAlgorithm::Bilinear => Flags::BILINEAR,
+            // Full-resolution chroma interpolation and accurate rounding are
+            // what make the slower filters worth it at 1:1 size
+            ScalingAlgorithm::Bicubic => {
+                Flags::BICUBIC | Flags::FULL_CHR_H_INT | Flags::ACCURATE_RND
+            }
+            ScalingAlgorithm::Lanczos => {
+                Flags::LANCZOS | Flags::FULL_CHR_H_INT | Flags::ACCURATE_RND
+            }
+            ScalingAlgorithm::Neighbor => Flags::POINT,
+        }
+    }
+}
+
+/// Decoder tuning chosen when a video is opened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenOptions {
+    /// Decoder threads; None or 0 lets FFmpeg pick one per core
+    pub threads: Option<usize>,
+    /// Output frames as soon as possible (slice threading only, no frame
+    /// reordering delay)
+    #[serde(default)]
+    pub low_delay: bool,
+    /// Skip the in-loop deblocking filter: faster, slightly blockier frames
+    #[serde(default)]
+    pub skip_loop_filter: bool,
+}
+
+/// Options shared by the frame extraction functions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FrameOptions {
+    /// Hardware decoder to try before falling back to software
+    pub hwaccel: Option<HwAccel>,
+    /// Tone-map HDR (PQ/HLG) frames to SDR; SDR frames are left untouched
+    #[serde(default)]
+    pub tonemap: bool,
+    /// `.cube` 3D LUT applied after tone-mapping, matching melt's lut3d filter
+    pub lut_path: Option<String>,
+    /// Decoder threading and speed flags
+    #[serde(default)]
+    pub decoder: OpenOptions,
+    /// Interpolation for the conversion to RGB
+    #[serde(default)]
+    pub scaling: ScalingAlgorithm,
+}
+
+/// Image encoding for extracted frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// JPEG with quality 1-100
+    Jpeg { quality: u8 },
+    /// Lossless PNG, for clean poster frames and screenshots
+    Png,
+    /// WebP with quality 0-100 (ignored when lossless), for small thumbnails
+    #[serde(rename = "webp")]
+    WebP { quality: u8, lossless: bool },
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Jpeg { quality: 85 }
+    }
+}
+
+impl OutputFormat {
+    fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg { .. } => "JPEG",
+            OutputFormat::Png => "PNG",
+            OutputFormat::WebP { .. } => "WEBP",
+        }
+    }
+
+    /// MIME type of the encoded image
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg { .. } => "image/jpeg",
+            OutputFormat::Png => "image/png",
+            OutputFormat::WebP { .. } => "image/webp",
+        }
+    }
+
+    /// `data:` URL for base64 image data in this format
+    pub fn data_url(&self, image_base64: &str) -> String {
+        format!("data:{};base64,{}", self.mime_type(), image_base64)
+    }
+}
+
+/// Embedded cover art (album art / attached picture)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverArt {
+    /// Base64-encoded image bytes
+    pub data: String,
+    /// MIME type of `data`, e.g. "image/jpeg"
+    pub mime_type: String,
+}
+
+/// An encoded frame together with its pixel dimensions, so callers can
+/// size a canvas without decoding the image first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameWithDimensions {
+    pub width: u32,
+    pub height: u32,
+    /// Base64-encoded image in the requested output format
+    pub base64: String,
+}
+
+/// A keyframe decoded for coarse scrubbing and the time it actually shows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyframeFrame {
+    pub timestamp_secs: f64,
+    /// Base64-encoded image in the requested output format
+    pub image_base64: String,
+}
+
+/// One video packet in a GOP listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GopFrame {
+    pub pts_secs: f64,
+    /// "I", "P" or "B" ("S"/"SI"/"SP"/"BI" for rarer types, "?" if undecodable)
+    pub frame_type: String,
+    pub is_keyframe: bool,
+    /// Compressed packet size
+    pub size_bytes: usize,
+}
+
+/// A detected scene change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneCut {
+    /// Timestamp of the first frame of the new scene
+    pub timestamp_secs: f64,
+    /// Luma difference from the previous sampled frame, 0.0 - 1.0
+    pub score: f64,
+}
+
+/// Container for animated hover-preview loops
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewLoopFormat {
+    #[default]
+    Gif,
+    #[serde(rename = "webp")]
+    WebP,
+}
+
+/// A numbered image sequence (e.g. `shot/frame%04d.png`) used as a clip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageSequence {
+    /// printf-style pattern understood by FFmpeg's image2 demuxer
+    pub pattern: String,
+    /// Number of the first frame on disk
+    pub start_number: u64,
+    /// Frames in the contiguous run starting at `start_number`
+    pub frame_count: u64,
+    pub fps: f64,
 }
 
+/// Frame rate assumed for sequences registered without one
+const DEFAULT_SEQUENCE_FPS: f64 = 24.0;
+
 /// Handle for an opened video file
 #[derive(Debug)]
 pub struct VideoHandle {
@@ -36,6 +312,149 @@ pub struct VideoHandle {
     pub info: VideoInfo,
     pub stream_index: usize,
     pub time_base: ffmpeg::Rational,
+    /// Hardware accelerator requested when the video was opened
+    pub hwaccel: Option<HwAccel>,
+    /// Decoder tuning reused by every frame grab through this handle
+    pub open_options: OpenOptions,
+    /// Last time the handle was accessed, used to evict leaked handles
+    pub last_used: Mutex<Instant>,
+    /// Demuxer and decoder kept open for frame reads through the handle
+    decoder: Mutex<Option<HandleDecoder>>,
+}
+
+impl VideoHandle {
+    fn touch(&self) {
+        if let Ok(mut last_used) = self.last_used.lock() {
+            *last_used = Instant::now();
+        }
+    }
+
+    fn idle_secs(&self) -> f64 {
+        self.last_used
+            .lock()
+            .map(|t| t.elapsed().as_secs_f64())
+            .unwrap_or(0.0)
+    }
+}
+
+/// Forward reads no further than this past the last frame keep decoding
+/// instead of seeking
+const HANDLE_SEQUENTIAL_WINDOW_SECS: f64 = 2.0;
+
+/// Demuxer and decoder a handle keeps open, so reading forward through a
+/// clip continues decoding instead of reopening and seeking for every frame
+struct HandleDecoder {
+    input: ffmpeg::format::context::Input,
+    decoder: ffmpeg::decoder::Video,
+    stream_index: usize,
+    start_pts: i64,
+    /// Last frame returned and its timestamp; None when the next read must seek
+    last: Option<(i64, VideoFrame)>,
+}
+
+// SAFETY: the FFmpeg contexts are owned by this struct alone and only used
+// while the owning handle's mutex is held
+unsafe impl Send for HandleDecoder {}
+
+impl std::fmt::Debug for HandleDecoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HandleDecoder")
+            .field("stream_index", &self.stream_index)
+            .field("last_ts", &self.last.as_ref().map(|(ts, _)| *ts))
+            .finish_non_exhaustive()
+    }
+}
+
+impl HandleDecoder {
+    fn open(path: &str, hwaccel: Option<HwAccel>, tuning: OpenOptions) -> Result<Self, VideoError> {
+        let input = open_input(path)?;
+        let stream = input.streams().best(Type::Video).ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+        let stream_index = stream.index();
+        let start_pts = stream_start_pts(&stream);
+        let (decoder, _) = create_tuned_video_decoder(stream.parameters(), hwaccel, tuning)?;
+        Ok(Self {
+            input,
+            decoder,
+            stream_index,
+            start_pts,
+            last: None,
+        })
+    }
+
+    /// The first frame at or after `timestamp_secs`, like `decode_frame_at_time`.
+    /// Short forward steps continue from the last frame without seeking.
+    fn frame_at(&mut self, path: &str, timestamp_secs: f64) -> Result<VideoFrame, VideoError> {
+        let stream = self.input.stream(self.stream_index).ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+        let time_base = f64::from(stream.time_base());
+        let timestamp_secs = clamp_to_stream(&self.input, &stream, timestamp_secs);
+        let target_ts = self.start_pts + (timestamp_secs / time_base).round() as i64;
+        let window = (HANDLE_SEQUENTIAL_WINDOW_SECS / time_base) as i64;
+
+        let mut previous = match self.last.take() {
+            Some((ts, frame)) if ts == target_ts => {
+                self.last = Some((ts, frame.clone()));
+                return Ok(frame);
+            }
+            Some((ts, frame)) if target_ts > ts && target_ts - ts <= window => Some((ts, frame)),
+            _ => {
+                seek_stream_before(&mut self.input, self.stream_index, target_ts)
+                    .or_else(|_| self.input.seek(0, ..).map_err(VideoError::from))?;
+                self.decoder.flush();
+                None
+            }
+        };
+
+        let mut decoded = VideoFrame::empty();
+        for (stream, packet) in self.input.packets() {
+            if stream.index() != self.stream_index {
+                continue;
+            }
+            self.decoder
+                .send_packet(&packet)
+                .map_err(|e| corrupt_video_error(path, &e.to_string()))?;
+            while self.decoder.receive_frame(&mut decoded).is_ok() {
+                let ts = decoded.timestamp().or(decoded.pts()).unwrap_or(target_ts);
+                if ts >= target_ts {
+                    self.last = Some((ts, decoded.clone()));
+                    return Ok(decoded);
+                }
+                previous = Some((ts, decoded.clone()));
+            }
+        }
+
+        // Drained to the end: the decoder has to be reset before reuse, so
+        // the next read seeks
+        self.decoder.send_eof()?;
+        let mut found = None;
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            let ts = decoded.timestamp().or(decoded.pts()).unwrap_or(target_ts);
+            if ts >= target_ts {
+                found = Some(decoded.clone());
+                break;
+            }
+            previous = Some((ts, decoded.clone()));
+        }
+        self.decoder.flush();
+
+        found.or(previous.map(|(_, frame)| frame)).ok_or_else(|| VideoError {
+            message: format!("Could not find frame at timestamp {}", timestamp_secs),
+            code: "FRAME_NOT_FOUND".to_string(),
+        })
+    }
+}
+
+/// Summary of an open handle, for debugging leaks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenVideoInfo {
+    pub handle_id: String,
+    pub path: String,
+    pub idle_secs: f64,
 }
 
 /// Thread-safe storage for video handles
@@ -43,6 +462,46 @@ lazy_static::lazy_static! {
     static ref VIDEO_HANDLES: Mutex<HashMap<String, Arc<VideoHandle>>> = Mutex::new(HashMap::new());
 }
 
+/// Registered image sequences, keyed by printf-style pattern
+lazy_static::lazy_static! {
+    static ref IMAGE_SEQUENCES: Mutex<HashMap<String, ImageSequence>> = Mutex::new(HashMap::new());
+}
+
+/// Cancellation flags for long-running decode jobs (job_id -> is_cancelled)
+lazy_static::lazy_static! {
+    static ref DECODE_JOBS: Mutex<HashMap<String, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+}
+
+/// Register a decode job and return its cancellation flag
+fn register_decode_job(job_id: &str) -> Arc<AtomicBool> {
+    let flag = Arc::new(AtomicBool::new(false));
+    if let Ok(mut jobs) = DECODE_JOBS.lock() {
+        jobs.insert(job_id.to_string(), flag.clone());
+    }
+    flag
+}
+
+/// Remove a finished decode job from the registry
+fn finish_decode_job(job_id: &str) {
+    if let Ok(mut jobs) = DECODE_JOBS.lock() {
+        jobs.remove(job_id);
+    }
+}
+
+/// Signal a running decode job to stop. Returns false if no such job is running.
+pub fn cancel_decode_job(job_id: &str) -> bool {
+    match DECODE_JOBS.lock() {
+        Ok(jobs) => match jobs.get(job_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        },
+        Err(_) => false,
+    }
+}
+
 /// Error type for video operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VideoError {
@@ -67,6 +526,15 @@ impl From<ffmpeg::Error> for VideoError {
     }
 }
 
+impl From<tokio::task::JoinError> for VideoError {
+    fn from(err: tokio::task::JoinError) -> Self {
+        VideoError {
+            message: format!("Task join error: {}", err),
+            code: "TASK_JOIN_ERROR".to_string(),
+        }
+    }
+}
+
 impl From<std::io::Error> for VideoError {
     fn from(err: std::io::Error) -> Self {
         VideoError {
@@ -76,6 +544,151 @@ impl From<std::io::Error> for VideoError {
     }
 }
 
+/// Split a printf-style frame pattern into (prefix, zero-pad width, suffix).
+/// Only `%d` and `%0Nd` are recognized.
+fn parse_sequence_pattern(pattern: &str) -> Option<(&str, usize, &str)> {
+    let percent = pattern.rfind('%')?;
+    let rest = &pattern[percent + 1..];
+    let d = rest.find('d')?;
+    let spec = &rest[..d];
+    if !spec.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let width = if spec.is_empty() { 0 } else { spec.parse().ok()? };
+    Some((&pattern[..percent], width, &rest[d + 1..]))
+}
+
+pub(crate) fn is_sequence_pattern(path: &str) -> bool {
+    parse_sequence_pattern(path).is_some()
+}
+
+/// Find the first frame number and length of the contiguous run of files
+/// matching `pattern` (image2 stops at the first gap)
+pub(crate) fn scan_sequence(pattern: &str) -> Option<(u64, u64)> {
+    let (prefix, width, suffix) = parse_sequence_pattern(pattern)?;
+    let prefix_path = Path::new(prefix);
+    let (dir, name_prefix) = match prefix_path.file_name() {
+        Some(name) if !prefix.ends_with(std::path::MAIN_SEPARATOR) && !prefix.ends_with('/') => (
+            prefix_path.parent().unwrap_or_else(|| Path::new(".")),
+            name.to_string_lossy().to_string(),
+        ),
+        _ => (prefix_path, String::new()),
+    };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+    let mut numbers: Vec<u64> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let digits = name.strip_prefix(&name_prefix)?.strip_suffix(suffix)?;
+            let valid = !digits.is_empty()
+                && digits.chars().all(|c| c.is_ascii_digit())
+                && (width == 0 || digits.len() == width);
+            if valid {
+                digits.parse().ok()
+            } else {
+                None
+            }
+        })
+        .collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+
+    let start = *numbers.first()?;
+    let run = numbers
+        .iter()
+        .enumerate()
+        .take_while(|(i, n)| **n == start + *i as u64)
+        .count() as u64;
+    Some((start, run))
+}
+
+/// Turn a single frame path such as `shot/frame0001.png` into the pattern
+/// `shot/frame%04d.png`, if it ends in a frame number
+fn pattern_from_frame_path(path: &str) -> Option<String> {
+    let file_name = Path::new(path).file_name()?.to_string_lossy().to_string();
+    let (stem, ext) = match file_name.rfind('.') {
+        Some(dot) => (&file_name[..dot], &file_name[dot..]),
+        None => (file_name.as_str(), ""),
+    };
+    let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+
+    let pattern_name = format!("{}%0{}d{}", &stem[..stem.len() - digits], digits, ext);
+    Some(match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => {
+            parent.join(pattern_name).to_string_lossy().to_string()
+        }
+        _ => pattern_name,
+    })
+}
+
+/// Register an image sequence from either a pattern (`frame%04d.png`) or any
+/// one of its frames (`frame0001.png`). Requires at least two frames.
+pub fn register_image_sequence(path: &str, fps: Option<f64>) -> Result<ImageSequence, VideoError> {
+    let pattern = if is_sequence_pattern(path) {
+        path.to_string()
+    } else {
+        pattern_from_frame_path(path).ok_or_else(|| VideoError {
+            message: format!("'{}' does not look like a numbered frame", path),
+            code: "NOT_A_SEQUENCE".to_string(),
+        })?
+    };
+
+    let (start_number, frame_count) = scan_sequence(&pattern)
+        .filter(|(_, count)| *count >= 2)
+        .ok_or_else(|| VideoError {
+            message: format!("No image sequence found for '{}'", pattern),
+            code: "NOT_A_SEQUENCE".to_string(),
+        })?;
+
+    let fps = fps.filter(|f| *f > 0.0).unwrap_or(DEFAULT_SEQUENCE_FPS);
+    let sequence = ImageSequence {
+        pattern: pattern.clone(),
+        start_number,
+        frame_count,
+        fps,
+    };
+
+    if let Ok(mut sequences) = IMAGE_SEQUENCES.lock() {
+        sequences.insert(pattern, sequence.clone());
+    }
+    Ok(sequence)
+}
+
+/// Registered (or freshly scanned) sequence for a pattern path
+fn image_sequence_for(path: &str) -> Option<ImageSequence> {
+    if !is_sequence_pattern(path) {
+        return None;
+    }
+    if let Some(sequence) = IMAGE_SEQUENCES.lock().ok()?.get(path) {
+        return Some(sequence.clone());
+    }
+    let (start_number, frame_count) = scan_sequence(path)?;
+    Some(ImageSequence {
+        pattern: path.to_string(),
+        start_number,
+        frame_count,
+        fps: DEFAULT_SEQUENCE_FPS,
+    })
+}
+
+/// Open a media file, or an image sequence pattern through the image2
+/// demuxer so the rest of the pipeline treats it like any other clip
+fn open_input(path: &str) -> Result<ffmpeg::format::context::Input, ffmpeg::Error> {
+    let Some(sequence) = image_sequence_for(path) else {
+        return input(&path);
+    };
+
+    let mut options = ffmpeg::Dictionary::new();
+    options.set("framerate", &sequence.fps.to_string());
+    options.set("start_number", &sequence.start_number.to_string());
+    ffmpeg::format::input_with_dictionary(&path, options)
+}
+
 /// Initialize FFmpeg (call once at startup)
 pub fn init_ffmpeg() -> Result<(), VideoError> {
     ffmpeg::init().map_err(|e| VideoError {
@@ -86,11 +699,124 @@ pub fn init_ffmpeg() -> Result<(), VideoError> {
 
 /// Get information about a video file without fully opening it
 pub fn get_video_info(path: &str) -> Result<VideoInfo, VideoError> {
-    let input_ctx = input(&path).map_err(|e| VideoError {
+    get_video_info_with_fps(path, None)
+}
+
+/// Frame rate of a stream rate, or None if it's degenerate (0/x or x/0)
+fn rational_fps(rate: ffmpeg::Rational) -> Option<f64> {
+    (rate.numerator() > 0 && rate.denominator() > 0)
+        .then(|| rate.numerator() as f64 / rate.denominator() as f64)
+}
+
+/// Video packets sampled for VFR detection
+const VFR_SAMPLE_PACKETS: usize = 120;
+
+/// Relative spread between frame durations (or between the average and
+/// base frame rates) beyond which a stream counts as variable frame rate
+const VFR_TOLERANCE: f64 = 0.05;
+
+/// Instantaneous frame rates of a stream sample
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FrameRateSample {
+    min_fps: f64,
+    max_fps: f64,
+    avg_fps: f64,
+    /// Frame durations differ by more than timestamp rounding allows
+    variable: bool,
+}
+
+/// Frame rate statistics from presentation timestamps (any order; B-frames
+/// arrive out of order). None with fewer than three distinct timestamps.
+fn frame_rate_sample(mut pts: Vec<i64>, time_base: ffmpeg::Rational) -> Option<FrameRateSample> {
+    pts.sort_unstable();
+    pts.dedup();
+    if pts.len() < 3 || time_base.denominator() == 0 {
+        return None;
+    }
+    let deltas: Vec<i64> = pts.windows(2).map(|w| w[1] - w[0]).collect();
+    let (min_delta, max_delta) = (*deltas.iter().min()?, *deltas.iter().max()?);
+    let tick = f64::from(time_base);
+    let span = (pts[pts.len() - 1] - pts[0]) as f64 * tick;
+
+    // A one-tick difference is rounding in coarse time bases (33 vs 34 ms)
+    let variable =
+        max_delta - min_delta > 1 && max_delta as f64 > min_delta as f64 * (1.0 + VFR_TOLERANCE);
+    Some(FrameRateSample {
+        min_fps: 1.0 / (max_delta as f64 * tick),
+        max_fps: 1.0 / (min_delta as f64 * tick),
+        avg_fps: deltas.len() as f64 / span,
+        variable,
+    })
+}
+
+/// Sample the first video packets' timestamps
+fn sample_frame_rate(
+    input_ctx: &mut ffmpeg::format::context::Input,
+    stream_index: usize,
+    time_base: ffmpeg::Rational,
+) -> Option<FrameRateSample> {
+    let pts: Vec<i64> = input_ctx
+        .packets()
+        .take(VFR_SAMPLE_PACKETS * 4)
+        .filter(|(stream, _)| stream.index() == stream_index)
+        .filter_map(|(_, packet)| packet.pts())
+        .take(VFR_SAMPLE_PACKETS)
+        .collect();
+    frame_rate_sample(pts, time_base)
+}
+
+/// Whether the average and base (r_frame_rate) rates disagree
+fn rates_disagree(avg: Option<f64>, base: Option<f64>) -> bool {
+    match (avg, base) {
+        (Some(avg), Some(base)) => (avg - base).abs() > base * VFR_TOLERANCE,
+        _ => false,
+    }
+}
+
+/// Get video metadata, using `assumed_fps` (when positive) in place of the
+/// detected frame rate for files whose timing is unreliable, such as VFR
+/// screen recordings
+pub fn get_video_info_with_fps(
+    path: &str,
+    assumed_fps: Option<f64>,
+) -> Result<VideoInfo, VideoError> {
+    read_video_info(path, assumed_fps, true)
+}
+
+/// `get_video_info` without VFR sampling, for callers that only need the
+/// duration, frame rate and frame count
+fn get_video_info_unsampled(path: &str) -> Result<VideoInfo, VideoError> {
+    read_video_info(path, None, false)
+}
+
+fn read_video_info(
+    path: &str,
+    assumed_fps: Option<f64>,
+    sample_vfr: bool,
+) -> Result<VideoInfo, VideoError> {
+    let sequence = image_sequence_for(path);
+    if sequence.is_none() && !Path::new(path).exists() {
+        return Err(VideoError {
+            message: format!("Video file not found: '{}'", path),
+            code: "FILE_NOT_FOUND".to_string(),
+        });
+    }
+
+    let mut input_ctx = open_input(path).map_err(|e| VideoError {
         message: format!("Failed to open video file '{}': {}", path, e),
         code: "OPEN_ERROR".to_string(),
     })?;
+    video_info_from_input(&mut input_ctx, sequence, assumed_fps, sample_vfr)
+}
 
+/// Video metadata from an opened input. Without `sample_vfr`, VFR detection
+/// relies on the container's rates instead of reading packets ahead.
+fn video_info_from_input(
+    input_ctx: &mut ffmpeg::format::context::Input,
+    sequence: Option<ImageSequence>,
+    assumed_fps: Option<f64>,
+    sample_vfr: bool,
+) -> Result<VideoInfo, VideoError> {
     // Find the best video stream
     let video_stream = input_ctx
         .streams()
@@ -114,18 +840,15 @@ pub fn get_video_info(path: &str) -> Result<VideoInfo, VideoError> {
         code: "DECODER_ERROR".to_string(),
     })?;
 
-    // Calculate FPS
-    let frame_rate = video_stream.avg_frame_rate();
-    let fps = if frame_rate.denominator() != 0 {
-        frame_rate.numerator() as f64 / frame_rate.denominator() as f64
-    } else {
-        // Fallback to r_frame_rate
-        let r_frame_rate = video_stream.rate();
-        if r_frame_rate.denominator() != 0 {
-            r_frame_rate.numerator() as f64 / r_frame_rate.denominator() as f64
-        } else {
-            30.0 // Default fallback
-        }
+    // Calculate FPS, falling back to r_frame_rate and then a default guess
+    let avg_rate = rational_fps(video_stream.avg_frame_rate());
+    let base_rate = rational_fps(video_stream.rate());
+    let detected_fps = avg_rate.or(base_rate);
+    let assumed_fps = assumed_fps.filter(|f| *f > 0.0);
+    let (fps, fps_is_estimated) = match (assumed_fps, detected_fps) {
+        (Some(assumed), _) => (assumed, false),
+        (None, Some(detected)) => (detected, false),
+        (None, None) => (30.0, true),
     };
 
     // Calculate duration
@@ -143,18 +866,36 @@ pub fn get_video_info(path: &str) -> Result<VideoInfo, VideoError> {
     };
 
     // Estimate frame count
-    let frame_count = if video_stream.frames() > 0 {
+    // An assumed fps defines the frame grid, so count against it
+    let frame_count = if video_stream.frames() > 0 && assumed_fps.is_none() {
         video_stream.frames() as u64
     } else {
         (duration_secs * fps).round() as u64
     };
 
+    // Image sequences have no meaningful container duration; derive it from the files
+    let (duration_secs, fps, frame_count) = match &sequence {
+        Some(sequence) => (
+            sequence.frame_count as f64 / sequence.fps,
+            sequence.fps,
+            sequence.frame_count,
+        ),
+        None => (duration_secs, fps, frame_count),
+    };
+
     // Get codec name
     let codec_name = decoder
         .codec()
         .map(|c| c.name().to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
+    let (pixel_format, bit_depth) = pixel_format_details(decoder.format());
+
+    // Phone footage is stored landscape with a rotation flag
+    let rotation = stream_rotation(&video_stream);
+    let (display_width, display_height) =
+        display_dimensions(decoder.width(), decoder.height(), rotation);
+
     // Get bitrate
     let bitrate = if input_ctx.bit_rate() > 0 {
         Some(input_ctx.bit_rate() as u64)
@@ -162,144 +903,241 @@ pub fn get_video_info(path: &str) -> Result<VideoInfo, VideoError> {
         None
     };
 
+    // VFR: measured frame durations vary, or (when too few packets could be
+    // sampled) the container's average and base rates disagree
+    let time_base = video_stream.time_base();
+    let rate_sample = if sequence.is_some() || !sample_vfr {
+        None
+    } else {
+        sample_frame_rate(input_ctx, video_stream_index, time_base)
+    };
+    let is_vfr = match rate_sample {
+        Some(sample) => sample.variable,
+        None => sequence.is_none() && rates_disagree(avg_rate, base_rate),
+    };
+
     Ok(VideoInfo {
         duration_secs,
         fps,
+        fps_is_estimated: fps_is_estimated && sequence.is_none(),
         width: decoder.width(),
         height: decoder.height(),
+        rotation,
+        display_width,
+        display_height,
         frame_count,
         codec: codec_name,
         bitrate,
+        hw_accel: None,
+        pixel_format,
+        bit_depth,
+        color_primaries: match decoder.color_primaries() {
+            ffmpeg::color::Primaries::Unspecified => None,
+            primaries => primaries.name().map(str::to_string),
+        },
+        transfer_characteristics: match decoder.color_transfer_characteristic() {
+            ffmpeg::color::TransferCharacteristic::Unspecified => None,
+            transfer => transfer.name().map(str::to_string),
+        },
+        color_range: match decoder.color_range() {
+            ffmpeg::color::Range::Unspecified => None,
+            range => range.name().map(str::to_string),
+        },
+        is_vfr,
+        min_fps: rate_sample.map(|s| s.min_fps),
+        max_fps: rate_sample.map(|s| s.max_fps),
+        avg_fps: rate_sample.map(|s| s.avg_fps),
     })
 }
 
-/// Open a video file and return a handle for subsequent operations
-pub fn open_video(path: &str) -> Result<String, VideoError> {
-    // Verify the file exists
-    if !Path::new(path).exists() {
-        return Err(VideoError {
-            message: format!("Video file not found: {}", path),
-            code: "FILE_NOT_FOUND".to_string(),
-        });
-    }
+/// Clockwise display rotation in degrees, snapped to 0/90/180/270, from the
+/// stream's display matrix or the legacy "rotate" tag
+fn stream_rotation(stream: &ffmpeg::format::stream::Stream) -> u32 {
+    // SAFETY: codecpar lives as long as the stream; display matrix side data
+    // is a 3x3 i32 matrix (36 bytes), checked before reading
+    let matrix_degrees = unsafe {
+        let par = (*stream.as_ptr()).codecpar;
+        let side_data = ffmpeg::ffi::av_packet_side_data_get(
+            (*par).coded_side_data,
+            (*par).nb_coded_side_data,
+            ffmpeg::ffi::AVPacketSideDataType::AV_PKT_DATA_DISPLAYMATRIX,
+        );
+        if side_data.is_null() || (*side_data).size < 36 {
+            None
+        } else {
+            // av_display_rotation_get is counterclockwise
+            let angle = ffmpeg::ffi::av_display_rotation_get((*side_data).data as *const i32);
+            (!angle.is_nan()).then_some(-angle)
+        }
+    };
 
-    // Get video info
-    let info = get_video_info(path)?;
+    let degrees = matrix_degrees
+        .or_else(|| stream.metadata().get("rotate").and_then(|v| v.parse().ok()))
+        .unwrap_or(0.0);
+    ((degrees / 90.0).round() as i64).rem_euclid(4) as u32 * 90
+}
 
-    // Open input to get stream info
-    let input_ctx = input(&path)?;
-    let video_stream = input_ctx.streams().best(Type::Video).ok_or_else(|| VideoError {
-        message: "No video stream found".to_string(),
-        code: "NO_VIDEO_STREAM".to_string(),
-    })?;
+/// Displayed (width, height) for coded dimensions under a rotation
+fn display_dimensions(width: u32, height: u32, rotation: u32) -> (u32, u32) {
+    if rotation % 180 == 90 {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
 
-    let stream_index = video_stream.index();
-    let time_base = video_stream.time_base();
+/// Pixel format name and bits per luma sample
+fn pixel_format_details(format: Pixel) -> (String, u8) {
+    let name = format
+        .descriptor()
+        .map(|d| d.name().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
 
-    let handle = VideoHandle {
-        path: path.to_string(),
-        info,
-        stream_index,
-        time_base,
+    // SAFETY: av_pix_fmt_desc_get returns a pointer into a static table or null
+    let depth = unsafe {
+        let desc = ffmpeg::ffi::av_pix_fmt_desc_get(format.into());
+        if desc.is_null() {
+            8
+        } else {
+            (*desc).comp[0].depth as u8
+        }
     };
 
-    // Generate a unique handle ID
-    let handle_id = format!("video_{}_{}", uuid::Uuid::new_v4(), path.len());
+    (name, depth)
+}
 
-    // Store the handle
-    let mut handles = VIDEO_HANDLES.lock().map_err(|_| VideoError {
-        message: "Failed to acquire lock on video handles".to_string(),
-        code: "LOCK_ERROR".to_string(),
+/// Index of the audio stream ffmpeg would pick by default
+pub fn best_audio_stream(path: &str) -> Result<usize, VideoError> {
+    let input_ctx = open_input(path).map_err(|e| VideoError {
+        message: format!("Failed to open media file '{}': {}", path, e),
+        code: "OPEN_ERROR".to_string(),
     })?;
-    handles.insert(handle_id.clone(), Arc::new(handle));
-
-    Ok(handle_id)
+    input_ctx
+        .streams()
+        .best(Type::Audio)
+        .map(|stream| stream.index())
+        .ok_or_else(|| VideoError {
+            message: "No audio stream found in file".to_string(),
+            code: "NO_AUDIO_STREAM".to_string(),
+        })
 }
 
-/// Close a video handle and free resources
-pub fn close_video(handle_id: &str) -> Result<(), VideoError> {
-    let mut handles = VIDEO_HANDLES.lock().map_err(|_| VideoError {
-        message: "Failed to acquire lock on video handles".to_string(),
-        code: "LOCK_ERROR".to_string(),
+/// List every stream in a file (video, audio, subtitle, ...) without decoding
+pub fn probe_streams(path: &str) -> Result<Vec<StreamInfo>, VideoError> {
+    let input_ctx = open_input(path).map_err(|e| VideoError {
+        message: format!("Failed to open media file '{}': {}", path, e),
+        code: "OPEN_ERROR".to_string(),
     })?;
 
-    handles.remove(handle_id);
-    Ok(())
-}
-
-/// Encode a video frame as JPEG and return base64 string
-fn encode_frame_as_base64_jpeg(frame: &VideoFrame, quality: u8) -> Result<String, VideoError> {
-    let width = frame.width();
-    let height = frame.height();
+    let mut streams = Vec::new();
+    for stream in input_ctx.streams() {
+        let parameters = stream.parameters();
+        let media_type = match parameters.medium() {
+            Type::Video => "video",
+            Type::Audio => "audio",
+            Type::Subtitle => "subtitle",
+            Type::Data => "data",
+            Type::Attachment => "attachment",
+            _ => "unknown",
+        };
 
-    // Create a scaler to convert to RGB24
-    let mut scaler = ScalingContext::get(
-        frame.format(),
-        width,
-        height,
-        Pixel::RGB24,
-        width,
-        height,
-        Flags::BILINEAR,
-    )
-    .map_err(|e| VideoError {
-        message: format!("Failed to create scaler: {}", e),
-        code: "SCALER_ERROR".to_string(),
-    })?;
+        let metadata = stream.metadata();
+        let time_base = f64::from(stream.time_base());
+        let duration_secs = (stream.duration() > 0).then(|| stream.duration() as f64 * time_base);
+        let bitrate = match unsafe { (*parameters.as_ptr()).bit_rate } {
+            rate if rate > 0 => Some(rate as u64),
+            _ => None,
+        };
 
-    // Scale/convert the frame to RGB
-    let mut rgb_frame = VideoFrame::empty();
-    scaler.run(frame, &mut rgb_frame).map_err(|e| VideoError {
-        message: format!("Failed to scale frame: {}", e),
-        code: "SCALE_ERROR".to_string(),
-    })?;
+        let mut info = StreamInfo {
+            index: stream.index(),
+            media_type: media_type.to_string(),
+            codec: parameters.id().name().to_string(),
+            language: metadata.get("language").map(str::to_string),
+            title: metadata.get("title").map(str::to_string),
+            is_default: stream
+                .disposition()
+                .contains(ffmpeg::format::stream::Disposition::DEFAULT),
+            duration_secs,
+            bitrate,
+            width: None,
+            height: None,
+            fps: None,
+            sample_rate: None,
+            channels: None,
+        };
 
-    // Get the RGB data
-    let rgb_data = rgb_frame.data(0);
-    let stride = rgb_frame.stride(0);
+        // Type-specific fields come from an unopened codec context, so no decoding
+        let codec_ctx = ffmpeg::codec::context::Context::from_parameters(parameters)?;
+        match media_type {
+            "video" => {
+                if let Ok(video) = codec_ctx.decoder().video() {
+                    info.width = Some(video.width());
+                    info.height = Some(video.height());
+                }
+                let rate = stream.avg_frame_rate();
+                if rate.denominator() != 0 && rate.numerator() != 0 {
+                    info.fps = Some(f64::from(rate));
+                }
+            }
+            "audio" => {
+                if let Ok(audio) = codec_ctx.decoder().audio() {
+                    info.sample_rate = Some(audio.rate());
+                    info.channels = Some(audio.channels());
+                }
+            }
+            _ => {}
+        }
 
-    // Create image buffer - handle stride properly
-    let mut img_buffer = Vec::with_capacity((width * height * 3) as usize);
-    for y in 0..height as usize {
-        let row_start = y * stride;
-        let row_end = row_start + (width as usize * 3);
-        img_buffer.extend_from_slice(&rgb_data[row_start..row_end]);
+        streams.push(info);
     }
 
-    // Create image from raw RGB data
-    let img = image::RgbImage::from_raw(width, height, img_buffer).ok_or_else(|| VideoError {
-        message: "Failed to create image from frame data".to_string(),
-        code: "IMAGE_ERROR".to_string(),
-    })?;
-
-    // Encode as JPEG
-    let mut jpeg_buffer = Vec::new();
-    let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg_buffer, quality);
-    encoder
-        .encode_image(&img)
-        .map_err(|e| VideoError {
-            message: format!("Failed to encode JPEG: {}", e),
-            code: "JPEG_ENCODE_ERROR".to_string(),
-        })?;
+    Ok(streams)
+}
 
-    // Convert to base64
-    Ok(BASE64.encode(&jpeg_buffer))
+/// Whether `codec` can decode through a hardware device of type `accel`
+fn codec_supports_hwaccel(codec: &ffmpeg::Codec, accel: HwAccel) -> bool {
+    let mut i = 0;
+    loop {
+        // SAFETY: avcodec_get_hw_config returns null past the last config
+        let config = unsafe { ffmpeg::ffi::avcodec_get_hw_config(codec.as_ptr(), i) };
+        if config.is_null() {
+            return false;
+        }
+        let (methods, device_type) = unsafe { ((*config).methods, (*config).device_type) };
+        if methods & ffmpeg::ffi::AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX as i32 != 0
+            && device_type == accel.device_type()
+        {
+            return true;
+        }
+        i += 1;
+    }
 }
 
-/// Extract a frame at a specific timestamp (in seconds)
-pub fn get_frame_at_time(path: &str, timestamp_secs: f64) -> Result<String, VideoError> {
-    get_frame_at_time_with_quality(path, timestamp_secs, 85)
+/// Error for files that open but can't be decoded, typically because a
+/// download was interrupted
+fn corrupt_video_error(path: &str, detail: &str) -> VideoError {
+    VideoError {
+        message: format!(
+            "'{}' appears to be truncated or corrupt ({}). Try downloading or exporting it again.",
+            path, detail
+        ),
+        code: "CORRUPT_OR_INCOMPLETE".to_string(),
+    }
 }
 
-/// Extract a frame at a specific timestamp with custom JPEG quality (1-100)
-pub fn get_frame_at_time_with_quality(
-    path: &str,
-    timestamp_secs: f64,
-    quality: u8,
-) -> Result<String, VideoError> {
-    let mut input_ctx = input(&path)?;
+/// Maximum packets read while looking for a first decodable frame
+const INTEGRITY_PROBE_PACKETS: usize = 500;
 
-    // Find video stream
+/// Check that a video has a real duration and that at least one frame
+/// decodes, returning CORRUPT_OR_INCOMPLETE otherwise
+pub fn check_video_integrity(path: &str) -> Result<(), VideoError> {
+    let info = get_video_info_unsampled(path)?;
+    if info.duration_secs <= 0.0 {
+        return Err(corrupt_video_error(path, "duration is zero"));
+    }
+
+    let mut input_ctx = open_input(path).map_err(|e| corrupt_video_error(path, &e.to_string()))?;
     let video_stream = input_ctx
         .streams()
         .best(Type::Video)
@@ -307,255 +1145,3469 @@ pub fn get_frame_at_time_with_quality(
             message: "No video stream found".to_string(),
             code: "NO_VIDEO_STREAM".to_string(),
         })?;
-
     let video_stream_index = video_stream.index();
-    let time_base = video_stream.time_base();
-
-    // Create decoder
-    let codec_ctx = ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())?;
-    let mut decoder = codec_ctx.decoder().video()?;
-
-    // Calculate target timestamp in stream time base
-    let target_ts = (timestamp_secs * time_base.denominator() as f64 / time_base.numerator() as f64)
-        as i64;
-
-    // Seek to the nearest keyframe before the target timestamp
-    input_ctx
-        .seek(timestamp_secs as i64 * 1_000_000, ..timestamp_secs as i64 * 1_000_000 + 1_000_000)
-        .or_else(|_| {
-            // If precise seek fails, try seeking to start
-            input_ctx.seek(0, ..)
-        })?;
+    let (mut decoder, _) = create_video_decoder(video_stream.parameters(), None)?;
+    decode_first_frame(path, &mut input_ctx, video_stream_index, &mut decoder)
+}
 
-    // Decode frames until we reach or pass the target timestamp
-    let mut closest_frame: Option<VideoFrame> = None;
-    let mut closest_diff = i64::MAX;
+/// Check that a frame decodes within the first `INTEGRITY_PROBE_PACKETS`.
+/// Leaves the input and decoder at EOF-or-later; seek and flush before reuse.
+fn decode_first_frame(
+    path: &str,
+    input_ctx: &mut ffmpeg::format::context::Input,
+    video_stream_index: usize,
+    decoder: &mut ffmpeg::decoder::Video,
+) -> Result<(), VideoError> {
+    let mut decoded_frame = VideoFrame::empty();
+    let mut last_error = "no packets could be read".to_string();
 
-    for (stream, packet) in input_ctx.packets() {
+    for (stream, packet) in input_ctx.packets().take(INTEGRITY_PROBE_PACKETS) {
         if stream.index() != video_stream_index {
             continue;
         }
+        match decoder.send_packet(&packet) {
+            Ok(()) => {
+                if decoder.receive_frame(&mut decoded_frame).is_ok() {
+                    return Ok(());
+                }
+            }
+            Err(e) => last_error = e.to_string(),
+        }
+    }
 
-        decoder.send_packet(&packet)?;
+    // Some decoders only emit frames once flushed
+    if decoder.send_eof().is_ok() && decoder.receive_frame(&mut decoded_frame).is_ok() {
+        return Ok(());
+    }
 
-        let mut decoded_frame = VideoFrame::empty();
-        while decoder.receive_frame(&mut decoded_frame).is_ok() {
-            let frame_ts = decoded_frame.pts().unwrap_or(0);
-            let diff = (frame_ts - target_ts).abs();
+    Err(corrupt_video_error(path, &format!("no frame could be decoded: {}", last_error)))
+}
 
-            if diff < closest_diff {
-                closest_diff = diff;
-                closest_frame = Some(decoded_frame.clone());
-            }
+/// Whether this FFmpeg build can decode a file's main stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodeSupport {
+    pub decodable: bool,
+    /// Codec of the stream that was tried (video, else audio)
+    pub codec: Option<String>,
+    /// Why decoding isn't possible, when it isn't
+    pub reason: Option<String>,
+}
 
-            // If we've passed the target and have a frame, we're done
-            if frame_ts >= target_ts && closest_frame.is_some() {
-                let frame = closest_frame.unwrap();
-                return encode_frame_as_base64_jpeg(&frame, quality);
-            }
-        }
+/// Check that a decoder exists for the file's main stream (video, else
+/// audio) and that a first frame actually decodes. Read-only and stops at
+/// the first frame, so it is cheap enough to run on import.
+pub fn can_decode(path: &str) -> DecodeSupport {
+    let unsupported = |codec: Option<String>, reason: String| DecodeSupport {
+        decodable: false,
+        codec,
+        reason: Some(reason),
+    };
 
-        // Safety limit - don't decode too many frames past target
-        if let Some(pts) = packet.pts() {
-            if pts > target_ts + (time_base.denominator() as i64 * 2) {
-                break;
-            }
-        }
+    let mut input_ctx = match open_input(path) {
+        Ok(ctx) => ctx,
+        Err(e) => return unsupported(None, format!("Could not open file: {}", e)),
+    };
+
+    let Some(stream) = input_ctx
+        .streams()
+        .best(Type::Video)
+        .or_else(|| input_ctx.streams().best(Type::Audio))
+    else {
+        return unsupported(None, "No audio or video stream found".to_string());
+    };
+    let stream_index = stream.index();
+    let parameters = stream.parameters();
+    let codec = parameters.id().name().to_string();
+
+    if ffmpeg::decoder::find(parameters.id()).is_none() {
+        return unsupported(
+            Some(codec.clone()),
+            format!("No {} decoder in this FFmpeg build", codec),
+        );
     }
 
-    // Flush decoder
-    decoder.send_eof()?;
-    let mut decoded_frame = VideoFrame::empty();
-    while decoder.receive_frame(&mut decoded_frame).is_ok() {
-        let frame_ts = decoded_frame.pts().unwrap_or(0);
-        let diff = (frame_ts - target_ts).abs();
+    let decoder = ffmpeg::codec::context::Context::from_parameters(parameters)
+        .and_then(|ctx| ctx.decoder().open());
+    let mut decoder = match decoder {
+        Ok(decoder) => decoder,
+        Err(e) => return unsupported(Some(codec), format!("Decoder failed to open: {}", e)),
+    };
 
-        if diff < closest_diff {
-            closest_frame = Some(decoded_frame.clone());
+    // Any frame type works here; only whether a frame arrives matters
+    let mut decoded_frame = VideoFrame::empty();
+    let mut last_error = "no packets could be read".to_string();
+    for (stream, packet) in input_ctx.packets().take(INTEGRITY_PROBE_PACKETS) {
+        if stream.index() != stream_index {
+            continue;
+        }
+        match decoder.send_packet(&packet) {
+            Ok(()) => {
+                if decoder.receive_frame(&mut decoded_frame).is_ok() {
+                    return DecodeSupport {
+                        decodable: true,
+                        codec: Some(codec),
+                        reason: None,
+                    };
+                }
+            }
+            Err(e) => last_error = e.to_string(),
         }
     }
 
-    // Return the closest frame we found
-    if let Some(frame) = closest_frame {
-        encode_frame_as_base64_jpeg(&frame, quality)
-    } else {
-        Err(VideoError {
-            message: format!("Could not find frame at timestamp {}", timestamp_secs),
-            code: "FRAME_NOT_FOUND".to_string(),
-        })
+    if decoder.send_eof().is_ok() && decoder.receive_frame(&mut decoded_frame).is_ok() {
+        return DecodeSupport {
+            decodable: true,
+            codec: Some(codec),
+            reason: None,
+        };
     }
-}
 
-/// Generate multiple thumbnail frames at regular intervals
-pub fn generate_thumbnails(path: &str, interval_secs: f64) -> Result<Vec<String>, VideoError> {
-    generate_thumbnails_with_options(path, interval_secs, 60, None)
+    unsupported(Some(codec), format!("No frame could be decoded: {}", last_error))
 }
 
-/// Generate thumbnails with custom options
-pub fn generate_thumbnails_with_options(
-    path: &str,
-    interval_secs: f64,
-    quality: u8,
-    max_thumbnails: Option<usize>,
-) -> Result<Vec<String>, VideoError> {
-    let info = get_video_info(path)?;
-
-    if info.duration_secs <= 0.0 {
-        return Err(VideoError {
-            message: "Cannot generate thumbnails for video with zero duration".to_string(),
-            code: "ZERO_DURATION".to_string(),
-        });
+/// Create a hardware device and attach it to an unopened codec context
+fn attach_hw_device(codec_ctx: &mut ffmpeg::codec::context::Context, accel: HwAccel) -> bool {
+    let codec = match ffmpeg::decoder::find(codec_ctx.id()) {
+        Some(codec) => codec,
+        None => return false,
+    };
+    if !codec_supports_hwaccel(&codec, accel) {
+        return false;
     }
 
-    // Calculate how many thumbnails to generate
-    let mut count = (info.duration_secs / interval_secs).ceil() as usize;
-    if count == 0 {
-        count = 1;
+    // SAFETY: the device reference is handed to the codec context, which
+    // owns (and frees) its own reference; ours is released right after.
+    unsafe {
+        let mut device_ref: *mut ffmpeg::ffi::AVBufferRef = std::ptr::null_mut();
+        let ret = ffmpeg::ffi::av_hwdevice_ctx_create(
+            &mut device_ref,
+            accel.device_type(),
+            std::ptr::null(),
+            std::ptr::null_mut(),
+            0,
+        );
+        if ret < 0 || device_ref.is_null() {
+            return false;
+        }
+        (*codec_ctx.as_mut_ptr()).hw_device_ctx = ffmpeg::ffi::av_buffer_ref(device_ref);
+        ffmpeg::ffi::av_buffer_unref(&mut device_ref);
     }
 
-    // Apply max limit if specified
-    if let Some(max) = max_thumbnails {
-        count = count.min(max);
+    true
+}
+
+/// Apply threading and speed flags to a codec context before it is opened
+fn apply_open_options(codec_ctx: &mut ffmpeg::codec::context::Context, tuning: OpenOptions) {
+    // SAFETY: the context is not opened yet, so these fields may be written
+    unsafe {
+        let ctx = codec_ctx.as_mut_ptr();
+        (*ctx).thread_count = tuning.threads.unwrap_or(0) as i32;
+        // Frame threading buffers one frame per thread before output
+        (*ctx).thread_type = if tuning.low_delay {
+            ffmpeg::ffi::FF_THREAD_SLICE as i32
+        } else {
+            (ffmpeg::ffi::FF_THREAD_FRAME | ffmpeg::ffi::FF_THREAD_SLICE) as i32
+        };
+        if tuning.low_delay {
+            (*ctx).flags |= ffmpeg::ffi::AV_CODEC_FLAG_LOW_DELAY as i32;
+        }
+        if tuning.skip_loop_filter {
+            (*ctx).skip_loop_filter = ffmpeg::ffi::AVDiscard::AVDISCARD_ALL;
+        }
     }
+}
 
-    // Cap at reasonable maximum
-    count = count.min(100);
+/// Create a video decoder, using `hwaccel` when the device and codec support
+/// it and silently falling back to software decode otherwise.
+/// Returns the decoder and the accelerator actually in use.
+fn create_video_decoder(
+    parameters: ffmpeg::codec::Parameters,
+    hwaccel: Option<HwAccel>,
+) -> Result<(ffmpeg::decoder::Video, Option<HwAccel>), VideoError> {
+    create_tuned_video_decoder(parameters, hwaccel, OpenOptions::default())
+}
 
-    let mut thumbnails = Vec::with_capacity(count);
+/// `create_video_decoder` with explicit threading and speed flags
+fn create_tuned_video_decoder(
+    parameters: ffmpeg::codec::Parameters,
+    hwaccel: Option<HwAccel>,
+    tuning: OpenOptions,
+) -> Result<(ffmpeg::decoder::Video, Option<HwAccel>), VideoError> {
+    if let Some(accel) = hwaccel {
+        let mut codec_ctx = ffmpeg::codec::context::Context::from_parameters(parameters.clone())?;
+        if attach_hw_device(&mut codec_ctx, accel) {
+            apply_open_options(&mut codec_ctx, tuning);
+            if let Ok(decoder) = codec_ctx.decoder().video() {
+                return Ok((decoder, Some(accel)));
+            }
+        }
+    }
 
-    for i in 0..count {
-        let timestamp = i as f64 * interval_secs;
-        if timestamp >= info.duration_secs {
-            break;
+    let mut codec_ctx = ffmpeg::codec::context::Context::from_parameters(parameters)?;
+    apply_open_options(&mut codec_ctx, tuning);
+    Ok((codec_ctx.decoder().video()?, None))
+}
+
+/// Copy a hardware-surface frame back to system memory.
+/// Returns None for frames that already live in system memory.
+fn download_hw_frame(frame: &VideoFrame) -> Result<Option<VideoFrame>, VideoError> {
+    // SAFETY: hw_frames_ctx is only set on frames produced by a hardware decoder
+    unsafe {
+        if (*frame.as_ptr()).hw_frames_ctx.is_null() {
+            return Ok(None);
         }
 
-        match get_frame_at_time_with_quality(path, timestamp, quality) {
-            Ok(frame) => thumbnails.push(frame),
-            Err(e) => {
-                // Log error but continue with other frames
-                eprintln!("Warning: Failed to extract frame at {}: {}", timestamp, e);
-            }
+        let mut sw_frame = VideoFrame::empty();
+        let ret = ffmpeg::ffi::av_hwframe_transfer_data(sw_frame.as_mut_ptr(), frame.as_ptr(), 0);
+        if ret < 0 {
+            return Err(VideoError {
+                message: format!("Failed to download hardware frame: {}", ffmpeg::Error::from(ret)),
+                code: "HWACCEL_ERROR".to_string(),
+            });
         }
+        sw_frame.set_pts(frame.pts());
+        Ok(Some(sw_frame))
     }
+}
 
-    if thumbnails.is_empty() {
+/// Open a video file and return a handle for subsequent operations.
+/// When `hwaccel` is given, the handle's info reports whether it is usable.
+/// `open_options` tunes the decoder for every frame grabbed via the handle.
+pub fn open_video(
+    path: &str,
+    hwaccel: Option<HwAccel>,
+    open_options: OpenOptions,
+) -> Result<String, VideoError> {
+    // Verify the file exists (image sequences are matched by pattern)
+    if image_sequence_for(path).is_none() && !Path::new(path).exists() {
         return Err(VideoError {
-            message: "Failed to generate any thumbnails".to_string(),
-            code: "NO_THUMBNAILS".to_string(),
+            message: format!("Video file not found: {}", path),
+            code: "FILE_NOT_FOUND".to_string(),
         });
     }
 
-    Ok(thumbnails)
-}
+    // One open serves the metadata, the integrity check and the handle's
+    // decoder. VFR sampling would read packets ahead, so the handle's info
+    // relies on the container's rates instead.
+    let mut input_ctx = open_input(path).map_err(|e| VideoError {
+        message: format!("Failed to open video file '{}': {}", path, e),
+        code: "OPEN_ERROR".to_string(),
+    })?;
+    let mut info = video_info_from_input(&mut input_ctx, image_sequence_for(path), None, false)?;
 
-/// Generate a single thumbnail at a specific percentage through the video
-pub fn get_thumbnail_at_percent(path: &str, percent: f64) -> Result<String, VideoError> {
-    let info = get_video_info(path)?;
-    let timestamp = info.duration_secs * (percent / 100.0).clamp(0.0, 1.0);
-    get_frame_at_time_with_quality(path, timestamp, 70)
-}
+    // Reject truncated/corrupt downloads up front
+    if info.duration_secs <= 0.0 {
+        return Err(corrupt_video_error(path, "duration is zero"));
+    }
 
-/// Extract the first frame of a video (useful for poster/thumbnail)
-pub fn get_first_frame(path: &str) -> Result<String, VideoError> {
-    get_frame_at_time_with_quality(path, 0.0, 85)
-}
+    let video_stream = input_ctx.streams().best(Type::Video).ok_or_else(|| VideoError {
+        message: "No video stream found".to_string(),
+        code: "NO_VIDEO_STREAM".to_string(),
+    })?;
+    let stream_index = video_stream.index();
+    let time_base = video_stream.time_base();
+    let start_pts = stream_start_pts(&video_stream);
 
-// ============================================================================
-// Tauri Commands
-// ============================================================================
+    // The caller learns whether the accelerator will be used
+    let (mut decoder, active) =
+        create_tuned_video_decoder(video_stream.parameters(), hwaccel, open_options)?;
+    if hwaccel.is_some() {
+        info.hw_accel = active;
+    }
+    decode_first_frame(path, &mut input_ctx, stream_index, &mut decoder)?;
 
-/// Tauri command to get video information
-#[tauri::command]
-pub async fn cmd_get_video_info(path: String) -> Result<VideoInfo, String> {
-    get_video_info(&path).map_err(|e| e.message)
-}
+    // The first read through the handle seeks and flushes, since `last` is None
+    let handle_decoder = HandleDecoder {
+        input: input_ctx,
+        decoder,
+        stream_index,
+        start_pts,
+        last: None,
+    };
 
-/// Tauri command to open a video and get a handle
-#[tauri::command]
-pub async fn cmd_open_video(path: String) -> Result<String, String> {
-    open_video(&path).map_err(|e| e.message)
-}
+    let handle = VideoHandle {
+        path: path.to_string(),
+        info,
+        stream_index,
+        time_base,
+        hwaccel,
+        open_options,
+        last_used: Mutex::new(Instant::now()),
+        decoder: Mutex::new(Some(handle_decoder)),
+    };
 
-/// Tauri command to close a video handle
-#[tauri::command]
-pub async fn cmd_close_video(handle_id: String) -> Result<(), String> {
-    close_video(&handle_id).map_err(|e| e.message)
-}
+    // Generate a unique handle ID
+    let handle_id = format!("video_{}_{}", uuid::Uuid::new_v4(), path.len());
 
-/// Tauri command to get a frame at a specific timestamp
-#[tauri::command]
-pub async fn cmd_get_frame_at_time(path: String, timestamp_secs: f64) -> Result<String, String> {
-    // Run in blocking task since FFmpeg operations are CPU-intensive
-    tokio::task::spawn_blocking(move || get_frame_at_time(&path, timestamp_secs))
-        .await
-        .map_err(|e| format!("Task join error: {}", e))?
-        .map_err(|e| e.message)
+    // Store the handle
+    let mut handles = lock_handles()?;
+    handles.insert(handle_id.clone(), Arc::new(handle));
+
+    Ok(handle_id)
 }
 
-/// Tauri command to get a frame with custom quality
-#[tauri::command]
-pub async fn cmd_get_frame_at_time_with_quality(
-    path: String,
+/// Extract a frame through an open handle, decoding with the hardware
+/// accelerator and `OpenOptions` it was opened with. The handle keeps its
+/// decoder between calls, so stepping forward doesn't reopen or seek.
+pub fn get_frame_from_handle(
+    handle_id: &str,
     timestamp_secs: f64,
-    quality: u8,
-) -> Result<String, String> {
-    tokio::task::spawn_blocking(move || {
-        get_frame_at_time_with_quality(&path, timestamp_secs, quality)
-    })
-    .await
-    .map_err(|e| format!("Task join error: {}", e))?
-    .map_err(|e| e.message)
-}
+    format: OutputFormat,
+) -> Result<String, VideoError> {
+    let handle = get_video_handle(handle_id)?;
+    let mut slot = handle.decoder.lock().map_err(|_| VideoError {
+        message: "Failed to acquire lock on video decoder".to_string(),
+        code: "LOCK_ERROR".to_string(),
+    })?;
+    let mut decoder = match slot.take() {
+        Some(decoder) => decoder,
+        None => HandleDecoder::open(&handle.path, handle.hwaccel, handle.open_options)?,
+    };
+    // A decoder that failed is reopened on the next read
+    let frame = decoder.frame_at(&handle.path, timestamp_secs)?;
+    *slot = Some(decoder);
+    drop(slot);
 
-/// Tauri command to generate thumbnails at regular intervals
-#[tauri::command]
-pub async fn cmd_generate_thumbnails(
-    path: String,
-    interval_secs: f64,
-) -> Result<Vec<String>, String> {
-    tokio::task::spawn_blocking(move || generate_thumbnails(&path, interval_secs))
-        .await
-        .map_err(|e| format!("Task join error: {}", e))?
-        .map_err(|e| e.message)
+    encode_frame_as_base64(&frame, format)
 }
 
-/// Tauri command to generate thumbnails with options
-#[tauri::command]
-pub async fn cmd_generate_thumbnails_with_options(
-    path: String,
-    interval_secs: f64,
-    quality: u8,
-    max_thumbnails: Option<usize>,
-) -> Result<Vec<String>, String> {
-    tokio::task::spawn_blocking(move || {
-        generate_thumbnails_with_options(&path, interval_secs, quality, max_thumbnails)
-    })
-    .await
-    .map_err(|e| format!("Task join error: {}", e))?
-    .map_err(|e| e.message)
-}
+/// Close a video handle and free resources
+pub fn close_video(handle_id: &str) -> Result<(), VideoError> {
+    let mut handles = lock_handles()?;
 
-/// Tauri command to get the first frame of a video
-#[tauri::command]
-pub async fn cmd_get_first_frame(path: String) -> Result<String, String> {
-    tokio::task::spawn_blocking(move || get_first_frame(&path))
-        .await
-        .map_err(|e| format!("Task join error: {}", e))?
-        .map_err(|e| e.message)
+    handles.remove(handle_id);
+    Ok(())
 }
 
-/// Tauri command to get a thumbnail at a percentage through the video
-#[tauri::command]
-pub async fn cmd_get_thumbnail_at_percent(path: String, percent: f64) -> Result<String, String> {
-    tokio::task::spawn_blocking(move || get_thumbnail_at_percent(&path, percent))
-        .await
-        .map_err(|e| format!("Task join error: {}", e))?
-        .map_err(|e| e.message)
+/// Convert a decoded video frame to a packed RGB image
+fn frame_to_rgb_image(frame: &VideoFrame) -> Result<image::RgbImage, VideoError> {
+    frame_to_rgb_image_scaled(frame, frame.width(), frame.height(), ScalingAlgorithm::default())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Convert a decoded frame to an RGB image of `width` x `height`
+fn frame_to_rgb_image_scaled(
+    frame: &VideoFrame,
+    width: u32,
+    height: u32,
+    scaling: ScalingAlgorithm,
+) -> Result<image::RgbImage, VideoError> {
+    let downloaded = download_hw_frame(frame)?;
+    let frame = downloaded.as_ref().unwrap_or(frame);
+
+    // Create a scaler to convert to RGB24
+    let mut scaler = ScalingContext::get(
+        frame.format(),
+        frame.width(),
+        frame.height(),
+        Pixel::RGB24,
+        width,
+        height,
+        scaling.flags(),
+    )
+    .map_err(|e| VideoError {
+        message: format!("Failed to create scaler: {}", e),
+        code: "SCALER_ERROR".to_string(),
+    })?;
+
+    // Scale/convert the frame to RGB
+    let mut rgb_frame = VideoFrame::empty();
+    scaler.run(frame, &mut rgb_frame).map_err(|e| VideoError {
+        message: format!("Failed to scale frame: {}", e),
+        code: "SCALE_ERROR".to_string(),
+    })?;
+
+    // Get the RGB data
+    let rgb_data = rgb_frame.data(0);
+    let stride = rgb_frame.stride(0);
+
+    // Create image buffer - handle stride properly
+    let mut img_buffer = Vec::with_capacity((width * height * 3) as usize);
+    for y in 0..height as usize {
+        let row_start = y * stride;
+        let row_end = row_start + (width as usize * 3);
+        img_buffer.extend_from_slice(&rgb_data[row_start..row_end]);
+    }
+
+    // Create image from raw RGB data
+    image::RgbImage::from_raw(width, height, img_buffer).ok_or_else(|| VideoError {
+        message: "Failed to create image from frame data".to_string(),
+        code: "IMAGE_ERROR".to_string(),
+    })
+}
+
+fn lock_handles() -> Result<std::sync::MutexGuard<'static, HashMap<String, Arc<VideoHandle>>>, VideoError> {
+    VIDEO_HANDLES.lock().map_err(|_| VideoError {
+        message: "Failed to acquire lock on video handles".to_string(),
+        code: "LOCK_ERROR".to_string(),
+    })
+}
+
+/// Look up an open handle, marking it as recently used
+pub fn get_video_handle(handle_id: &str) -> Result<Arc<VideoHandle>, VideoError> {
+    let handles = lock_handles()?;
+    let handle = handles.get(handle_id).cloned().ok_or_else(|| VideoError {
+        message: format!("Unknown video handle: {}", handle_id),
+        code: "INVALID_HANDLE".to_string(),
+    })?;
+    handle.touch();
+    Ok(handle)
+}
+
+/// List open handles with their paths and idle time
+pub fn list_open_videos() -> Result<Vec<OpenVideoInfo>, VideoError> {
+    let handles = lock_handles()?;
+    Ok(handles
+        .iter()
+        .map(|(handle_id, handle)| OpenVideoInfo {
+            handle_id: handle_id.clone(),
+            path: handle.path.clone(),
+            idle_secs: handle.idle_secs(),
+        })
+        .collect())
+}
+
+/// Close every handle idle for longer than `max_idle_secs`.
+/// Returns the number of handles evicted.
+pub fn prune_video_handles(max_idle_secs: f64) -> Result<usize, VideoError> {
+    let mut handles = lock_handles()?;
+    let before = handles.len();
+    handles.retain(|_, handle| handle.idle_secs() <= max_idle_secs);
+    Ok(before - handles.len())
+}
+
+/// Handles idle this long are evicted by the background sweeper
+const HANDLE_MAX_IDLE_SECS: f64 = 30.0 * 60.0;
+/// How often the background sweeper runs
+const HANDLE_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Start a background thread that evicts handles the frontend never closed
+pub fn start_handle_sweeper() {
+    std::thread::spawn(|| loop {
+        std::thread::sleep(HANDLE_SWEEP_INTERVAL);
+        if let Err(e) = prune_video_handles(HANDLE_MAX_IDLE_SECS) {
+            eprintln!("Warning: Failed to prune video handles: {}", e);
+        }
+    });
+}
+
+/// Encode a video frame in the requested image format
+fn encode_frame(frame: &VideoFrame, format: OutputFormat) -> Result<Vec<u8>, VideoError> {
+    encode_rgb_image(&frame_to_rgb_image(frame)?, format)
+}
+
+/// Encode an RGB image in the requested image format
+fn encode_rgb_image(img: &image::RgbImage, format: OutputFormat) -> Result<Vec<u8>, VideoError> {
+    let encode_error = |e: image::ImageError| VideoError {
+        message: format!("Failed to encode {}: {}", format.name(), e),
+        code: format!("{}_ENCODE_ERROR", format.name()),
+    };
+
+    let mut buffer = Vec::new();
+    match format {
+        OutputFormat::Jpeg { quality } => img
+            .write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(
+                &mut buffer,
+                quality.clamp(1, 100),
+            ))
+            .map_err(encode_error)?,
+        OutputFormat::Png => img
+            .write_with_encoder(image::codecs::png::PngEncoder::new(&mut buffer))
+            .map_err(encode_error)?,
+        OutputFormat::WebP { quality, lossless } => {
+            // The image crate only writes lossless WebP, so use libwebp
+            let encoder = webp::Encoder::from_rgb(img.as_raw(), img.width(), img.height());
+            let memory = if lossless {
+                encoder.encode_lossless()
+            } else {
+                encoder.encode(quality.min(100) as f32)
+            };
+            buffer.extend_from_slice(&memory);
+        }
+    }
+
+    Ok(buffer)
+}
+
+/// Encode a video frame and return base64 string
+fn encode_frame_as_base64(frame: &VideoFrame, format: OutputFormat) -> Result<String, VideoError> {
+    Ok(BASE64.encode(encode_frame(frame, format)?))
+}
+
+/// Encode a video frame in `format` and write it to `out_path`
+fn write_frame_to_file(
+    frame: &VideoFrame,
+    out_path: &Path,
+    format: OutputFormat,
+) -> Result<(), VideoError> {
+    let bytes = encode_frame(frame, format)?;
+    fs::write(out_path, bytes).map_err(|e| VideoError {
+        message: format!("Failed to write {} '{}': {}", format.name(), out_path.display(), e),
+        code: "IO_ERROR".to_string(),
+    })
+}
+
+/// Extract a frame at a specific timestamp (in seconds)
+pub fn get_frame_at_time(path: &str, timestamp_secs: f64) -> Result<String, VideoError> {
+    get_frame_at_time_with_quality(path, timestamp_secs, 85)
+}
+
+/// Extract a frame at a specific timestamp with custom JPEG quality (1-100)
+pub fn get_frame_at_time_with_quality(
+    path: &str,
+    timestamp_secs: f64,
+    quality: u8,
+) -> Result<String, VideoError> {
+    get_frame_at_time_with_options(
+        path,
+        timestamp_secs,
+        OutputFormat::Jpeg { quality },
+        &FrameOptions::default(),
+    )
+}
+
+/// `get_frame_at_time_with_quality` through the on-disk thumbnail cache:
+/// decodes only on a miss, keyed by the source's content hash
+fn cached_thumbnail(path: &str, timestamp_secs: f64, quality: u8) -> Result<String, VideoError> {
+    let key = thumb_cache::content_hash(path).map(|hash| thumb_cache::ThumbKey {
+        hash,
+        timestamp_ms: (timestamp_secs.max(0.0) * 1000.0).round() as u64,
+        width: None,
+        quality,
+    });
+    if let Some(thumbnail) = key.as_ref().and_then(thumb_cache::get) {
+        return Ok(thumbnail);
+    }
+
+    let thumbnail = get_frame_at_time_with_quality(path, timestamp_secs, quality)?;
+    if let Some(key) = &key {
+        thumb_cache::put(key, &thumbnail);
+    }
+    Ok(thumbnail)
+}
+
+/// Extract a frame at a specific timestamp with a custom output format and decode options
+pub fn get_frame_at_time_with_options(
+    path: &str,
+    timestamp_secs: f64,
+    format: OutputFormat,
+    options: &FrameOptions,
+) -> Result<String, VideoError> {
+    let frame = apply_frame_options(decode_frame_at_time(path, timestamp_secs, options)?, options)?;
+    encode_frame_as_base64(&frame, format)
+}
+
+/// `get_frame_at_time_with_options`, also returning the encoded frame's
+/// width and height as encoded
+pub fn get_frame_with_dimensions(
+    path: &str,
+    timestamp_secs: f64,
+    format: OutputFormat,
+    options: &FrameOptions,
+) -> Result<FrameWithDimensions, VideoError> {
+    let frame = apply_frame_options(decode_frame_at_time(path, timestamp_secs, options)?, options)?;
+    Ok(FrameWithDimensions {
+        width: frame.width(),
+        height: frame.height(),
+        base64: encode_frame_as_base64(&frame, format)?,
+    })
+}
+
+/// Extract a frame as a `data:` URL whose MIME type matches `format`, ready
+/// for an `<img src>`. Plain JPEG frames go through the thumbnail cache.
+pub fn get_frame_data_url(
+    path: &str,
+    timestamp_secs: f64,
+    format: OutputFormat,
+    options: &FrameOptions,
+) -> Result<String, VideoError> {
+    let image_base64 = match format {
+        // The cache holds unprocessed frames only
+        OutputFormat::Jpeg { quality }
+            if !options.tonemap
+                && options.lut_path.is_none()
+                && options.scaling == ScalingAlgorithm::default() =>
+        {
+            cached_thumbnail(path, timestamp_secs, quality)?
+        }
+        _ => get_frame_at_time_with_options(path, timestamp_secs, format, options)?,
+    };
+    Ok(format.data_url(&image_base64))
+}
+
+/// Extract a frame at a specific timestamp and write it straight to
+/// `out_path`, creating the parent directory if needed. Returns the path.
+pub fn extract_frame_to_file(
+    path: &str,
+    timestamp_secs: f64,
+    out_path: &str,
+    format: OutputFormat,
+    options: &FrameOptions,
+) -> Result<String, VideoError> {
+    let out = Path::new(out_path);
+    if let Some(parent) = out.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    let frame = apply_frame_options(decode_frame_at_time(path, timestamp_secs, options)?, options)?;
+    write_frame_to_file(&frame, out, format)?;
+
+    Ok(out.to_string_lossy().to_string())
+}
+
+/// HDR transfer functions we know how to tone-map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HdrTransfer {
+    /// SMPTE ST 2084 perceptual quantizer
+    Pq,
+    /// ARIB STD-B67 hybrid log-gamma
+    Hlg,
+}
+
+impl HdrTransfer {
+    fn of(frame: &VideoFrame) -> Option<Self> {
+        match frame.color_transfer_characteristic() {
+            ffmpeg::color::TransferCharacteristic::SMPTE2084 => Some(HdrTransfer::Pq),
+            ffmpeg::color::TransferCharacteristic::ARIB_STD_B67 => Some(HdrTransfer::Hlg),
+            _ => None,
+        }
+    }
+
+    /// Decode a normalized signal value to absolute luminance in nits
+    fn to_nits(self, e: f64) -> f64 {
+        let e = e.clamp(0.0, 1.0);
+        match self {
+            HdrTransfer::Pq => {
+                const M1: f64 = 2610.0 / 16384.0;
+                const M2: f64 = 2523.0 / 4096.0 * 128.0;
+                const C1: f64 = 3424.0 / 4096.0;
+                const C2: f64 = 2413.0 / 4096.0 * 32.0;
+                const C3: f64 = 2392.0 / 4096.0 * 32.0;
+                let p = e.powf(1.0 / M2);
+                ((p - C1).max(0.0) / (C2 - C3 * p)).powf(1.0 / M1) * 10_000.0
+            }
+            HdrTransfer::Hlg => {
+                const A: f64 = 0.178_832_77;
+                const B: f64 = 1.0 - 4.0 * A;
+                let c = 0.5 - A * (4.0 * A).ln();
+                let scene = if e <= 0.5 {
+                    e * e / 3.0
+                } else {
+                    (((e - c) / A).exp() + B) / 12.0
+                };
+                // Nominal 1000 nit HLG display, OOTF ignored
+                scene * 1000.0
+            }
+        }
+    }
+}
+
+/// Luminance of SDR reference white (ITU-R BT.2408)
+const SDR_WHITE_NITS: f64 = 203.0;
+
+/// BT.2020 -> BT.709 primaries conversion (linear light)
+const BT2020_TO_BT709: [[f64; 3]; 3] = [
+    [1.6605, -0.5876, -0.0728],
+    [-0.1246, 1.1329, -0.0083],
+    [-0.0182, -0.1006, 1.1187],
+];
+
+/// Tone-map one HDR RGB pixel (normalized BT.2020 signal values) to 8-bit sRGB
+/// with luminance-based Reinhard, which keeps hues stable in highlights
+fn tonemap_pixel(rgb: [f64; 3], transfer: HdrTransfer) -> [u8; 3] {
+    let linear = rgb.map(|e| transfer.to_nits(e) / SDR_WHITE_NITS);
+
+    let mut mapped = [0.0; 3];
+    for (out, row) in mapped.iter_mut().zip(BT2020_TO_BT709.iter()) {
+        *out = (row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2]).max(0.0);
+    }
+
+    let luma = 0.2126 * mapped[0] + 0.7152 * mapped[1] + 0.0722 * mapped[2];
+    let scale = if luma > 0.0 { (luma / (1.0 + luma)) / luma } else { 0.0 };
+
+    mapped.map(|c| {
+        let c = (c * scale).clamp(0.0, 1.0);
+        let srgb = if c <= 0.003_130_8 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        };
+        (srgb * 255.0).round() as u8
+    })
+}
+
+/// Convert an HDR frame to an SDR RGB24 frame
+fn tonemap_frame(
+    frame: &VideoFrame,
+    transfer: HdrTransfer,
+    scaling: ScalingAlgorithm,
+) -> Result<VideoFrame, VideoError> {
+    let downloaded = download_hw_frame(frame)?;
+    let frame = downloaded.as_ref().unwrap_or(frame);
+    let (width, height) = (frame.width(), frame.height());
+
+    let mut scaler = ScalingContext::get(
+        frame.format(),
+        width,
+        height,
+        Pixel::RGB48LE,
+        width,
+        height,
+        scaling.flags(),
+    )
+    .map_err(|e| VideoError {
+        message: format!("Failed to create scaler: {}", e),
+        code: "SCALER_ERROR".to_string(),
+    })?;
+
+    // HDR content is BT.2020; swscale assumes BT.601 unless told otherwise
+    // SAFETY: the scaler context is valid and the coefficient tables are static
+    unsafe {
+        let table = ffmpeg::ffi::sws_getCoefficients(ffmpeg::ffi::SWS_CS_BT2020 as i32);
+        let full_range = frame.color_range() == ffmpeg::color::Range::JPEG;
+        ffmpeg::ffi::sws_setColorspaceDetails(
+            scaler.as_mut_ptr(),
+            table,
+            full_range as i32,
+            table,
+            1,
+            0,
+            1 << 16,
+            1 << 16,
+        );
+    }
+
+    let mut rgb48 = VideoFrame::empty();
+    scaler.run(frame, &mut rgb48).map_err(|e| VideoError {
+        message: format!("Failed to scale frame: {}", e),
+        code: "SCALE_ERROR".to_string(),
+    })?;
+
+    let mut sdr = VideoFrame::new(Pixel::RGB24, width, height);
+    let src_stride = rgb48.stride(0);
+    let dst_stride = sdr.stride(0);
+    let src = rgb48.data(0);
+    let dst = sdr.data_mut(0);
+
+    for y in 0..height as usize {
+        let src_row = &src[y * src_stride..y * src_stride + width as usize * 6];
+        let dst_row = &mut dst[y * dst_stride..y * dst_stride + width as usize * 3];
+        for (px, out) in src_row.chunks_exact(6).zip(dst_row.chunks_exact_mut(3)) {
+            let channel = |i: usize| u16::from_le_bytes([px[i], px[i + 1]]) as f64 / 65535.0;
+            out.copy_from_slice(&tonemap_pixel([channel(0), channel(2), channel(4)], transfer));
+        }
+    }
+
+    Ok(sdr)
+}
+
+/// Convert a frame to an RGB24 `VideoFrame` with the given interpolation
+fn rgb24_frame(frame: &VideoFrame, scaling: ScalingAlgorithm) -> Result<VideoFrame, VideoError> {
+    let img = frame_to_rgb_image_scaled(frame, frame.width(), frame.height(), scaling)?;
+    let width = img.width() as usize;
+    let mut rgb = VideoFrame::new(Pixel::RGB24, img.width(), img.height());
+    let stride = rgb.stride(0);
+    let dst = rgb.data_mut(0);
+    for (y, row) in img.as_raw().chunks_exact(width * 3).enumerate() {
+        dst[y * stride..y * stride + width * 3].copy_from_slice(row);
+    }
+    Ok(rgb)
+}
+
+/// Convert a frame to RGB24 and run it through a `.cube` LUT
+fn lut_frame(
+    frame: &VideoFrame,
+    lut_path: &str,
+    scaling: ScalingAlgorithm,
+) -> Result<VideoFrame, VideoError> {
+    let lut = Lut3d::load(Path::new(lut_path)).map_err(|message| VideoError {
+        message,
+        code: "LUT_ERROR".to_string(),
+    })?;
+
+    let mut graded = rgb24_frame(frame, scaling)?;
+    let (width, height) = (graded.width() as usize, graded.height() as usize);
+    let stride = graded.stride(0);
+    lut.apply_rgb24(graded.data_mut(0), width, height, stride);
+
+    Ok(graded)
+}
+
+/// Apply post-decode `FrameOptions` (HDR tone-mapping, then the LUT). A
+/// non-default scaling algorithm converts to RGB here, so the later
+/// encode is a plain copy.
+fn apply_frame_options(frame: VideoFrame, options: &FrameOptions) -> Result<VideoFrame, VideoError> {
+    let mut frame = frame;
+    if options.tonemap {
+        if let Some(transfer) = HdrTransfer::of(&frame) {
+            frame = tonemap_frame(&frame, transfer, options.scaling)?;
+        }
+    }
+    if let Some(lut_path) = &options.lut_path {
+        frame = lut_frame(&frame, lut_path, options.scaling)?;
+    }
+    if options.scaling != ScalingAlgorithm::default() && frame.format() != Pixel::RGB24 {
+        frame = rgb24_frame(&frame, options.scaling)?;
+    }
+    Ok(frame)
+}
+
+/// Stream start time in its own time base (0 when unknown)
+fn stream_start_pts(stream: &ffmpeg::format::stream::Stream) -> i64 {
+    match stream.start_time() {
+        ts if ts == ffmpeg::ffi::AV_NOPTS_VALUE => 0,
+        ts => ts,
+    }
+}
+
+/// Seek a stream to the keyframe at or before `target_pts`, expressed in
+/// the stream's own time base (no rounding to whole seconds)
+fn seek_stream_before(
+    input_ctx: &mut ffmpeg::format::context::Input,
+    stream_index: usize,
+    target_pts: i64,
+) -> Result<(), VideoError> {
+    // SAFETY: the context pointer is valid for the lifetime of input_ctx
+    let ret = unsafe {
+        ffmpeg::ffi::av_seek_frame(
+            input_ctx.as_mut_ptr(),
+            stream_index as i32,
+            target_pts,
+            ffmpeg::ffi::AVSEEK_FLAG_BACKWARD as i32,
+        )
+    };
+    if ret < 0 {
+        return Err(ffmpeg::Error::from(ret).into());
+    }
+    Ok(())
+}
+
+/// Decode the first frame whose PTS is at or after `timestamp_secs`.
+///
+/// Seeks in the stream time base to the keyframe before the exact target,
+/// then decodes forward, so the result never comes from the previous GOP.
+/// Negative timestamps are clamped to 0 and timestamps past the duration to
+/// the end, which returns the last decodable frame rather than an error.
+fn decode_frame_at_time(
+    path: &str,
+    timestamp_secs: f64,
+    options: &FrameOptions,
+) -> Result<VideoFrame, VideoError> {
+    let input_ctx = open_input(path)?;
+    decode_frame_from_input(input_ctx, path, timestamp_secs, options)
+}
+
+/// Clamp a timestamp to [0, duration] of `stream` (NaN becomes 0)
+fn clamp_to_stream(
+    input_ctx: &ffmpeg::format::context::Input,
+    stream: &ffmpeg::format::stream::Stream,
+    timestamp_secs: f64,
+) -> f64 {
+    let duration_secs = if stream.duration() > 0 {
+        stream.duration() as f64 * f64::from(stream.time_base())
+    } else if input_ctx.duration() > 0 {
+        input_ctx.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64
+    } else {
+        f64::INFINITY
+    };
+    if timestamp_secs.is_nan() {
+        0.0
+    } else {
+        timestamp_secs.clamp(0.0, duration_secs)
+    }
+}
+
+/// `decode_frame_at_time` on an already opened input (`path` is only used
+/// in error messages)
+fn decode_frame_from_input(
+    mut input_ctx: ffmpeg::format::context::Input,
+    path: &str,
+    timestamp_secs: f64,
+    options: &FrameOptions,
+) -> Result<VideoFrame, VideoError> {
+    // Find video stream
+    let video_stream = input_ctx
+        .streams()
+        .best(Type::Video)
+        .ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+
+    let video_stream_index = video_stream.index();
+    let time_base = video_stream.time_base();
+    let start_pts = stream_start_pts(&video_stream);
+
+    // Create decoder
+    let (mut decoder, _) =
+        create_tuned_video_decoder(video_stream.parameters(), options.hwaccel, options.decoder)?;
+
+    // Clamp to [0, duration] so out-of-range requests seek near the boundary
+    // instead of failing the seek and decoding from the start
+    let timestamp_secs = clamp_to_stream(&input_ctx, &video_stream, timestamp_secs);
+
+    // Target timestamp in the stream time base
+    let target_ts = start_pts + (timestamp_secs / f64::from(time_base)).round() as i64;
+
+    // Seek to the keyframe before the exact target
+    seek_stream_before(&mut input_ctx, video_stream_index, target_ts).or_else(|_| {
+        // If precise seek fails, try seeking to start
+        input_ctx.seek(0, ..).map_err(VideoError::from)
+    })?;
+
+    let mut last_frame: Option<VideoFrame> = None;
+    let mut decoded_frame = VideoFrame::empty();
+
+    for (stream, packet) in input_ctx.packets() {
+        if stream.index() != video_stream_index {
+            continue;
+        }
+
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| corrupt_video_error(path, &e.to_string()))?;
+
+        while decoder.receive_frame(&mut decoded_frame).is_ok() {
+            let frame_ts = decoded_frame.timestamp().or(decoded_frame.pts()).unwrap_or(target_ts);
+            if frame_ts >= target_ts {
+                return Ok(decoded_frame);
+            }
+            last_frame = Some(decoded_frame.clone());
+        }
+    }
+
+    // Flush decoder
+    decoder.send_eof()?;
+    while decoder.receive_frame(&mut decoded_frame).is_ok() {
+        let frame_ts = decoded_frame.timestamp().or(decoded_frame.pts()).unwrap_or(target_ts);
+        if frame_ts >= target_ts {
+            return Ok(decoded_frame);
+        }
+        last_frame = Some(decoded_frame.clone());
+    }
+
+    last_frame.ok_or_else(|| VideoError {
+        message: format!("Could not find frame at timestamp {}", timestamp_secs),
+        code: "FRAME_NOT_FOUND".to_string(),
+    })
+}
+
+/// Decode only the keyframe at or before `timestamp_secs`, with no forward
+/// decoding to the exact target. Returns the frame and its own timestamp.
+fn decode_keyframe_before(
+    path: &str,
+    timestamp_secs: f64,
+    options: &FrameOptions,
+) -> Result<(VideoFrame, f64), VideoError> {
+    let mut input_ctx = open_input(path)?;
+    let video_stream = input_ctx
+        .streams()
+        .best(Type::Video)
+        .ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+
+    let video_stream_index = video_stream.index();
+    let time_base = video_stream.time_base();
+    let start_pts = stream_start_pts(&video_stream);
+    let (mut decoder, _) =
+        create_tuned_video_decoder(video_stream.parameters(), options.hwaccel, options.decoder)?;
+
+    let timestamp_secs = clamp_to_stream(&input_ctx, &video_stream, timestamp_secs);
+    let target_ts = start_pts + (timestamp_secs / f64::from(time_base)).round() as i64;
+    seek_stream_before(&mut input_ctx, video_stream_index, target_ts)
+        .or_else(|_| input_ctx.seek(0, ..).map_err(VideoError::from))?;
+
+    let frame_secs = |frame: &VideoFrame| {
+        frame
+            .timestamp()
+            .or(frame.pts())
+            .map_or(timestamp_secs, |ts| ((ts - start_pts) as f64 * f64::from(time_base)).max(0.0))
+    };
+
+    // The first frame out of the decoder after the seek is the keyframe
+    let mut decoded_frame = VideoFrame::empty();
+    for (stream, packet) in input_ctx.packets() {
+        if stream.index() != video_stream_index {
+            continue;
+        }
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| corrupt_video_error(path, &e.to_string()))?;
+        if decoder.receive_frame(&mut decoded_frame).is_ok() {
+            let secs = frame_secs(&decoded_frame);
+            return Ok((decoded_frame, secs));
+        }
+    }
+
+    decoder.send_eof()?;
+    if decoder.receive_frame(&mut decoded_frame).is_ok() {
+        let secs = frame_secs(&decoded_frame);
+        return Ok((decoded_frame, secs));
+    }
+
+    Err(VideoError {
+        message: format!("Could not find a keyframe before timestamp {}", timestamp_secs),
+        code: "FRAME_NOT_FOUND".to_string(),
+    })
+}
+
+/// Extract the keyframe at or before `timestamp_secs` for a fast, coarse
+/// scrubbing preview; refine with `get_frame_at_time` afterwards
+pub fn get_nearest_keyframe_frame(
+    path: &str,
+    timestamp_secs: f64,
+    format: OutputFormat,
+    options: &FrameOptions,
+) -> Result<KeyframeFrame, VideoError> {
+    let (frame, actual_secs) = decode_keyframe_before(path, timestamp_secs, options)?;
+    let frame = apply_frame_options(frame, options)?;
+    Ok(KeyframeFrame {
+        timestamp_secs: actual_secs,
+        image_base64: encode_frame_as_base64(&frame, format)?,
+    })
+}
+
+/// Socket/read timeout for remote frame extraction
+const REMOTE_TIMEOUT_SECS: u64 = 15;
+
+/// Open an http(s) URL through FFmpeg's network protocols with a timeout
+fn open_remote_input(url: &str) -> Result<ffmpeg::format::context::Input, VideoError> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(VideoError {
+            message: format!("Only http(s) URLs are supported: '{}'", url),
+            code: "INVALID_URL".to_string(),
+        });
+    }
+
+    let timeout_us = (REMOTE_TIMEOUT_SECS * 1_000_000).to_string();
+    let mut options = ffmpeg::Dictionary::new();
+    options.set("timeout", &timeout_us);
+    options.set("rw_timeout", &timeout_us);
+    options.set("reconnect", "1");
+
+    ffmpeg::format::input_with_dictionary(&url, options).map_err(|e| VideoError {
+        message: format!("Failed to open '{}': {}", url, e),
+        code: "NETWORK_ERROR".to_string(),
+    })
+}
+
+/// Extract a frame from remote media without downloading the whole file.
+///
+/// FFmpeg's http protocol fetches only the byte ranges the demuxer and
+/// seek need. Servers without range support would stream everything up to
+/// the target, so they are rejected unless the frame is at the very start.
+pub fn get_frame_from_url(
+    url: &str,
+    timestamp_secs: f64,
+    format: OutputFormat,
+) -> Result<String, VideoError> {
+    let input_ctx = open_remote_input(url)?;
+
+    // SAFETY: the context and its AVIOContext are valid while input_ctx lives
+    let seekable = unsafe {
+        let pb = (*input_ctx.as_ptr()).pb;
+        !pb.is_null() && (*pb).seekable != 0
+    };
+    if !seekable && timestamp_secs > 0.0 {
+        return Err(VideoError {
+            message: format!("Server for '{}' does not support HTTP range requests", url),
+            code: "RANGE_NOT_SUPPORTED".to_string(),
+        });
+    }
+
+    let frame = decode_frame_from_input(input_ctx, url, timestamp_secs, &FrameOptions::default())?;
+    encode_frame_as_base64(&frame, format)
+}
+
+/// Default cap on the number of keyframes returned for long videos
+const MAX_KEYFRAME_TIMESTAMPS: usize = 10_000;
+
+/// Timestamps (seconds) of every keyframe in the video stream.
+///
+/// Only demuxes packets (no decoding), reading AV_PKT_FLAG_KEY, so it is
+/// cheap even for long files. Stops after `max` keyframes.
+pub fn get_keyframe_timestamps(path: &str, max: Option<usize>) -> Result<Vec<f64>, VideoError> {
+    let max = max.unwrap_or(MAX_KEYFRAME_TIMESTAMPS);
+    let mut input_ctx = open_input(path)?;
+
+    let video_stream = input_ctx
+        .streams()
+        .best(Type::Video)
+        .ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+
+    let video_stream_index = video_stream.index();
+    let time_base = f64::from(video_stream.time_base());
+    let start_pts = stream_start_pts(&video_stream);
+
+    let mut timestamps = Vec::new();
+    for (stream, packet) in input_ctx.packets() {
+        if timestamps.len() >= max {
+            break;
+        }
+        if stream.index() != video_stream_index || !packet.is_key() {
+            continue;
+        }
+        if let Some(pts) = packet.pts().or(packet.dts()) {
+            timestamps.push((pts - start_pts) as f64 * time_base);
+        }
+    }
+
+    // Packets arrive in decode order; keyframe PTS is normally monotonic
+    // already, but sort to be safe with odd muxers
+    timestamps.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    Ok(timestamps)
+}
+
+/// Packets listed by `get_gop_structure` when no limit is given
+const DEFAULT_GOP_FRAMES: usize = 300;
+
+fn picture_type_name(kind: ffmpeg::picture::Type) -> &'static str {
+    use ffmpeg::picture::Type as Picture;
+    match kind {
+        Picture::I => "I",
+        Picture::P => "P",
+        Picture::B => "B",
+        Picture::S => "S",
+        Picture::SI => "SI",
+        Picture::SP => "SP",
+        Picture::BI => "BI",
+        Picture::None => "?",
+    }
+}
+
+/// The first `max_frames` video packets in decode order with their picture
+/// types, for explaining where seeks land. Packets are decoded (not just
+/// demuxed) since only the decoder knows P from B.
+pub fn get_gop_structure(
+    path: &str,
+    max_frames: Option<usize>,
+) -> Result<Vec<GopFrame>, VideoError> {
+    let max_frames = max_frames.unwrap_or(DEFAULT_GOP_FRAMES);
+    let mut input_ctx = open_input(path)?;
+    let video_stream = input_ctx
+        .streams()
+        .best(Type::Video)
+        .ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+
+    let video_stream_index = video_stream.index();
+    let time_base = f64::from(video_stream.time_base());
+    let start_pts = stream_start_pts(&video_stream);
+    let (mut decoder, _) = create_video_decoder(video_stream.parameters(), None)?;
+
+    let mut frames = Vec::new();
+    let mut types: HashMap<i64, &'static str> = HashMap::new();
+    let mut decoded = VideoFrame::empty();
+    for (stream, packet) in input_ctx.packets() {
+        if frames.len() >= max_frames {
+            break;
+        }
+        if stream.index() != video_stream_index {
+            continue;
+        }
+        let pts = packet.pts().or(packet.dts()).unwrap_or(0);
+        frames.push((
+            pts,
+            GopFrame {
+                pts_secs: (pts - start_pts) as f64 * time_base,
+                frame_type: "?".to_string(),
+                is_keyframe: packet.is_key(),
+                size_bytes: packet.size(),
+            },
+        ));
+
+        if decoder.send_packet(&packet).is_ok() {
+            while decoder.receive_frame(&mut decoded).is_ok() {
+                if let Some(frame_pts) = decoded.pts() {
+                    types.insert(frame_pts, picture_type_name(decoded.kind()));
+                }
+            }
+        }
+    }
+    let _ = decoder.send_eof();
+    while decoder.receive_frame(&mut decoded).is_ok() {
+        if let Some(frame_pts) = decoded.pts() {
+            types.insert(frame_pts, picture_type_name(decoded.kind()));
+        }
+    }
+
+    Ok(frames
+        .into_iter()
+        .map(|(pts, mut frame)| {
+            if let Some(kind) = types.get(&pts) {
+                frame.frame_type = kind.to_string();
+            } else if frame.is_keyframe {
+                frame.frame_type = "I".to_string();
+            }
+            frame
+        })
+        .collect())
+}
+
+/// Extract the frame with the given zero-based index (frame-accurate stepping)
+pub fn get_frame_at_index(
+    path: &str,
+    frame_index: u64,
+    format: OutputFormat,
+    options: &FrameOptions,
+) -> Result<String, VideoError> {
+    let frame = apply_frame_options(decode_frame_at_index(path, frame_index, options)?, options)?;
+    encode_frame_as_base64(&frame, format)
+}
+
+/// Decode the frame at `frame_index` by seeking to the preceding keyframe
+/// and counting decoded frames forward, instead of comparing PTS against a
+/// float timestamp.
+fn decode_frame_at_index(
+    path: &str,
+    frame_index: u64,
+    options: &FrameOptions,
+) -> Result<VideoFrame, VideoError> {
+    let info = get_video_info_unsampled(path)?;
+    if frame_index >= info.frame_count {
+        return Err(VideoError {
+            message: format!(
+                "Frame index {} is out of range (video has {} frames)",
+                frame_index, info.frame_count
+            ),
+            code: "FRAME_OUT_OF_RANGE".to_string(),
+        });
+    }
+
+    let mut input_ctx = open_input(path)?;
+    let video_stream = input_ctx
+        .streams()
+        .best(Type::Video)
+        .ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+
+    let video_stream_index = video_stream.index();
+    let time_base = f64::from(video_stream.time_base());
+    let start_pts = stream_start_pts(&video_stream);
+    let parameters = video_stream.parameters();
+
+    // Index of a decoded frame, derived from its timestamp
+    let index_of = |frame: &VideoFrame| -> u64 {
+        let pts = frame.timestamp().or(frame.pts()).unwrap_or(start_pts);
+        (((pts - start_pts) as f64 * time_base) * info.fps).round().max(0.0) as u64
+    };
+
+    let target_us = (frame_index as f64 / info.fps * 1_000_000.0) as i64;
+
+    // First try from the keyframe before the target; if the demuxer lands
+    // past it, count from the very start instead.
+    for seek_us in [target_us, 0] {
+        let (mut decoder, _) =
+            create_tuned_video_decoder(parameters.clone(), options.hwaccel, options.decoder)?;
+        input_ctx.seek(seek_us, ..seek_us + 1)?;
+
+        let mut current: Option<u64> = None;
+        let mut overshot = false;
+        let mut decoded_frame = VideoFrame::empty();
+
+        let mut step = |frame: &VideoFrame| -> Option<bool> {
+            let index = match current {
+                Some(i) => i + 1,
+                None => index_of(frame),
+            };
+            current = Some(index);
+            if index == frame_index {
+                Some(true)
+            } else if index > frame_index {
+                Some(false)
+            } else {
+                None
+            }
+        };
+
+        'packets: for (stream, packet) in input_ctx.packets() {
+            if stream.index() != video_stream_index {
+                continue;
+            }
+            decoder
+                .send_packet(&packet)
+                .map_err(|e| corrupt_video_error(path, &e.to_string()))?;
+
+            while decoder.receive_frame(&mut decoded_frame).is_ok() {
+                match step(&decoded_frame) {
+                    Some(true) => return Ok(decoded_frame),
+                    Some(false) => {
+                        overshot = true;
+                        break 'packets;
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        if !overshot {
+            decoder.send_eof()?;
+            while decoder.receive_frame(&mut decoded_frame).is_ok() {
+                match step(&decoded_frame) {
+                    Some(true) => return Ok(decoded_frame),
+                    Some(false) => break,
+                    None => {}
+                }
+            }
+        }
+
+        if seek_us == 0 {
+            break;
+        }
+    }
+
+    Err(VideoError {
+        message: format!("Could not find frame {}", frame_index),
+        code: "FRAME_NOT_FOUND".to_string(),
+    })
+}
+
+/// Return embedded cover art (an attached-picture stream), if any.
+///
+/// JPEG and PNG art is returned as stored; other codecs are decoded and
+/// re-encoded as JPEG.
+pub fn get_cover_art(path: &str) -> Result<Option<CoverArt>, VideoError> {
+    let mut input_ctx = open_input(path)?;
+
+    let art_stream = input_ctx.streams().find(|stream| {
+        stream
+            .disposition()
+            .contains(ffmpeg::format::stream::Disposition::ATTACHED_PIC)
+    });
+    let Some(art_stream) = art_stream else {
+        return Ok(None);
+    };
+
+    let art_index = art_stream.index();
+    let parameters = art_stream.parameters();
+    let stored_mime = match parameters.id() {
+        ffmpeg::codec::Id::MJPEG => Some("image/jpeg"),
+        ffmpeg::codec::Id::PNG => Some("image/png"),
+        _ => None,
+    };
+
+    // The demuxer queues attached pictures as the first packets of the file
+    let packet = input_ctx
+        .packets()
+        .find(|(stream, _)| stream.index() == art_index)
+        .map(|(_, packet)| packet);
+    let Some(packet) = packet else {
+        return Ok(None);
+    };
+    let Some(bytes) = packet.data() else {
+        return Ok(None);
+    };
+
+    if let Some(mime_type) = stored_mime {
+        return Ok(Some(CoverArt {
+            data: BASE64.encode(bytes),
+            mime_type: mime_type.to_string(),
+        }));
+    }
+
+    // Unusual codec (BMP, WebP, ...): decode and re-encode as JPEG
+    let (mut decoder, _) = create_video_decoder(parameters, None)?;
+    decoder.send_packet(&packet)?;
+    decoder.send_eof()?;
+    let mut frame = VideoFrame::empty();
+    if decoder.receive_frame(&mut frame).is_err() {
+        return Ok(None);
+    }
+
+    Ok(Some(CoverArt {
+        data: encode_frame_as_base64(&frame, OutputFormat::Jpeg { quality: 90 })?,
+        mime_type: "image/jpeg".to_string(),
+    }))
+}
+
+/// Size of the luma thumbnails compared by scene-cut detection
+const SCENE_LUMA_WIDTH: u32 = 64;
+const SCENE_LUMA_HEIGHT: u32 = 36;
+
+/// Downscale a frame to a `width` x `height` 8-bit luma buffer
+fn frame_to_luma(frame: &VideoFrame, width: u32, height: u32) -> Result<Vec<u8>, VideoError> {
+    let downloaded = download_hw_frame(frame)?;
+    let frame = downloaded.as_ref().unwrap_or(frame);
+
+    let mut scaler = ScalingContext::get(
+        frame.format(),
+        frame.width(),
+        frame.height(),
+        Pixel::GRAY8,
+        width,
+        height,
+        Flags::AREA,
+    )
+    .map_err(|e| VideoError {
+        message: format!("Failed to create scaler: {}", e),
+        code: "SCALER_ERROR".to_string(),
+    })?;
+
+    let mut gray_frame = VideoFrame::empty();
+    scaler.run(frame, &mut gray_frame).map_err(|e| VideoError {
+        message: format!("Failed to scale frame: {}", e),
+        code: "SCALE_ERROR".to_string(),
+    })?;
+
+    let data = gray_frame.data(0);
+    let stride = gray_frame.stride(0);
+    let mut luma = Vec::with_capacity((width * height) as usize);
+    for y in 0..height as usize {
+        luma.extend_from_slice(&data[y * stride..y * stride + width as usize]);
+    }
+    Ok(luma)
+}
+
+/// Mean absolute difference of two luma buffers, normalized to 0.0 - 1.0
+fn mean_abs_diff(a: &[u8], b: &[u8]) -> f64 {
+    if a.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+    let total: u64 = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| (*x as i32 - *y as i32).unsigned_abs() as u64)
+        .sum();
+    total as f64 / (a.len() as f64 * 255.0)
+}
+
+/// Find scene changes by comparing downscaled luma of every `stride`th
+/// frame with the previous sample. Returns cuts whose score exceeds
+/// `threshold` (0.0 - 1.0), with scores so the UI can tune sensitivity.
+pub fn detect_scene_cuts(
+    path: &str,
+    threshold: f64,
+    stride: usize,
+) -> Result<Vec<SceneCut>, VideoError> {
+    if stride == 0 {
+        return Err(VideoError {
+            message: "stride must be at least 1".to_string(),
+            code: "INVALID_ARGUMENT".to_string(),
+        });
+    }
+
+    let mut input_ctx = open_input(path)?;
+
+    let video_stream = input_ctx
+        .streams()
+        .best(Type::Video)
+        .ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+
+    let video_stream_index = video_stream.index();
+    let time_base = f64::from(video_stream.time_base());
+    let start_pts = stream_start_pts(&video_stream);
+    let (mut decoder, _) = create_video_decoder(video_stream.parameters(), None)?;
+
+    let mut cuts = Vec::new();
+    let mut previous: Option<Vec<u8>> = None;
+    let mut frame_index = 0usize;
+
+    let mut analyze = |frame: &VideoFrame| -> Result<(), VideoError> {
+        let sampled = frame_index % stride == 0;
+        frame_index += 1;
+        if !sampled {
+            return Ok(());
+        }
+
+        let luma = frame_to_luma(frame, SCENE_LUMA_WIDTH, SCENE_LUMA_HEIGHT)?;
+        if let Some(prev) = &previous {
+            let score = mean_abs_diff(prev, &luma);
+            if score > threshold {
+                let pts = frame.timestamp().or(frame.pts()).unwrap_or(start_pts);
+                cuts.push(SceneCut {
+                    timestamp_secs: (pts - start_pts) as f64 * time_base,
+                    score,
+                });
+            }
+        }
+        previous = Some(luma);
+        Ok(())
+    };
+
+    let mut decoded_frame = VideoFrame::empty();
+    for (stream, packet) in input_ctx.packets() {
+        if stream.index() != video_stream_index {
+            continue;
+        }
+
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| corrupt_video_error(path, &e.to_string()))?;
+
+        while decoder.receive_frame(&mut decoded_frame).is_ok() {
+            analyze(&decoded_frame)?;
+        }
+    }
+
+    decoder.send_eof()?;
+    while decoder.receive_frame(&mut decoded_frame).is_ok() {
+        analyze(&decoded_frame)?;
+    }
+
+    Ok(cuts)
+}
+
+/// Frames sampled per clip for the perceptual fingerprint
+const PHASH_SAMPLES: usize = 8;
+
+/// 64-bit difference hash of a 9x8 luma thumbnail: each bit records
+/// whether a pixel is brighter than its right-hand neighbour
+fn dhash(luma: &[u8]) -> u64 {
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let i = y * 9 + x;
+            hash = (hash << 1) | (luma[i] > luma[i + 1]) as u64;
+        }
+    }
+    hash
+}
+
+/// Perceptual fingerprint of a clip: the dHash of frames sampled at the
+/// same relative positions, as 16 hex digits per frame. Re-encodes and
+/// rescales of the same footage land within a small Hamming distance.
+pub fn compute_video_phash(path: &str) -> Result<String, VideoError> {
+    let info = get_video_info_unsampled(path)?;
+    let count = PHASH_SAMPLES.min(info.frame_count.max(1) as usize);
+
+    let mut fingerprint = String::with_capacity(count * 16);
+    for timestamp in evenly_spaced_midpoints(info.duration_secs.max(0.0), count) {
+        let frame = decode_frame_at_time(path, timestamp, &FrameOptions::default())?;
+        let luma = frame_to_luma(&frame, 9, 8)?;
+        fingerprint.push_str(&format!("{:016x}", dhash(&luma)));
+    }
+    Ok(fingerprint)
+}
+
+/// Total Hamming distance between two fingerprints from `compute_video_phash`
+pub fn phash_distance(a: &str, b: &str) -> Result<u32, VideoError> {
+    let invalid = || VideoError {
+        message: "Fingerprints must be hex strings of the same length".to_string(),
+        code: "INVALID_ARGUMENT".to_string(),
+    };
+    if a.len() != b.len() || a.len() % 16 != 0 || !a.is_ascii() || !b.is_ascii() {
+        return Err(invalid());
+    }
+
+    let mut distance = 0;
+    for offset in (0..a.len()).step_by(16) {
+        let x = u64::from_str_radix(&a[offset..offset + 16], 16).map_err(|_| invalid())?;
+        let y = u64::from_str_radix(&b[offset..offset + 16], 16).map_err(|_| invalid())?;
+        distance += (x ^ y).count_ones();
+    }
+    Ok(distance)
+}
+
+/// Upper bounds for hover-preview loops, to keep outputs small
+const PREVIEW_MAX_FRAMES: usize = 120;
+const PREVIEW_MAX_FPS: f64 = 30.0;
+const PREVIEW_MAX_WIDTH: u32 = 480;
+const PREVIEW_MIN_WIDTH: u32 = 16;
+
+/// Output size for a preview of `width` pixels wide, preserving aspect ratio
+/// (never upscaling, and keeping both sides even)
+fn preview_dimensions(src_width: u32, src_height: u32, width: u32) -> (u32, u32) {
+    let width = width.min(src_width).max(2) & !1;
+    let height = ((src_height as f64 * width as f64 / src_width.max(1) as f64).round() as u32).max(2) & !1;
+    (width, height)
+}
+
+/// Build a looping animated preview of `[start_secs, start_secs + duration_secs]`
+/// sampled at `fps` and scaled to `width` pixels wide. Returns base64.
+///
+/// Frame count, fps and width are clamped so the result stays thumbnail-sized.
+pub fn generate_preview_loop(
+    path: &str,
+    start_secs: f64,
+    duration_secs: f64,
+    fps: f64,
+    width: u32,
+    format: PreviewLoopFormat,
+) -> Result<String, VideoError> {
+    if start_secs < 0.0 || duration_secs <= 0.0 || fps <= 0.0 {
+        return Err(VideoError {
+            message: format!(
+                "Invalid preview range: start {} duration {} fps {}",
+                start_secs, duration_secs, fps
+            ),
+            code: "INVALID_ARGUMENT".to_string(),
+        });
+    }
+
+    let fps = fps.min(PREVIEW_MAX_FPS);
+    let frame_count = ((duration_secs * fps).ceil() as usize).clamp(1, PREVIEW_MAX_FRAMES);
+    let width = width.clamp(PREVIEW_MIN_WIDTH, PREVIEW_MAX_WIDTH);
+
+    let mut input_ctx = open_input(path)?;
+
+    let video_stream = input_ctx
+        .streams()
+        .best(Type::Video)
+        .ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+
+    let video_stream_index = video_stream.index();
+    let time_base = f64::from(video_stream.time_base());
+    let start_pts = stream_start_pts(&video_stream);
+    let (mut decoder, _) = create_video_decoder(video_stream.parameters(), None)?;
+
+    let target_ts = start_pts + (start_secs / time_base).round() as i64;
+    seek_stream_before(&mut input_ctx, video_stream_index, target_ts)
+        .or_else(|_| input_ctx.seek(0, ..).map_err(VideoError::from))?;
+
+    let mut frames: Vec<image::RgbImage> = Vec::with_capacity(frame_count);
+    let mut dimensions = None;
+    let end_secs = start_secs + duration_secs;
+    let frame_secs = |frame: &VideoFrame| {
+        (frame.timestamp().or(frame.pts()).unwrap_or(start_pts) - start_pts) as f64 * time_base
+    };
+
+    // The frame on screen at a sample time is the last one decoded at or
+    // before it, so `held` fills every sample up to the next frame's time.
+    // Samples before the first decoded frame take that frame.
+    let mut fill_until = |held: &VideoFrame,
+                          until: f64,
+                          frames: &mut Vec<image::RgbImage>|
+     -> Result<(), VideoError> {
+        let mut covered = 0;
+        while frames.len() + covered < frame_count
+            && start_secs + (frames.len() + covered) as f64 / fps < until - 1e-6
+        {
+            covered += 1;
+        }
+        if covered == 0 {
+            return Ok(());
+        }
+        let (w, h) = *dimensions
+            .get_or_insert_with(|| preview_dimensions(held.width(), held.height(), width));
+        let image = frame_to_rgb_image_scaled(held, w, h, ScalingAlgorithm::default())?;
+        frames.extend(std::iter::repeat_n(image, covered));
+        Ok(())
+    };
+
+    // Returns true once every sample in the range is filled
+    let mut held: Option<(f64, VideoFrame)> = None;
+    let mut step = |frame: &mut VideoFrame| -> Result<bool, VideoError> {
+        let secs = frame_secs(frame);
+        let previous = held.as_ref().map_or(&*frame, |(_, held)| held);
+        fill_until(previous, secs, &mut frames)?;
+        if secs > end_secs || frames.len() >= frame_count {
+            return Ok(true);
+        }
+        // Take the decoded buffers instead of copying them
+        held = Some((secs, std::mem::replace(frame, VideoFrame::empty())));
+        Ok(false)
+    };
+
+    let mut decoded_frame = VideoFrame::empty();
+    let mut done = false;
+    for (stream, packet) in input_ctx.packets() {
+        if stream.index() != video_stream_index {
+            continue;
+        }
+
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| corrupt_video_error(path, &e.to_string()))?;
+
+        while decoder.receive_frame(&mut decoded_frame).is_ok() {
+            if step(&mut decoded_frame)? {
+                done = true;
+                break;
+            }
+        }
+        if done {
+            break;
+        }
+    }
+
+    if !done {
+        decoder.send_eof()?;
+        while decoder.receive_frame(&mut decoded_frame).is_ok() {
+            if step(&mut decoded_frame)? {
+                done = true;
+                break;
+            }
+        }
+    }
+    // The source ended inside the range: show its last frame for one sample
+    if let (false, Some((secs, frame))) = (done, &held) {
+        fill_until(frame, secs + 1.0 / fps, &mut frames)?;
+    }
+
+    if frames.is_empty() {
+        return Err(VideoError {
+            message: format!("No frames found after {} s", start_secs),
+            code: "NO_FRAMES".to_string(),
+        });
+    }
+
+    let delay_ms = (1000.0 / fps).round() as u32;
+    let bytes = match format {
+        PreviewLoopFormat::Gif => encode_gif_loop(frames, delay_ms)?,
+        PreviewLoopFormat::WebP => encode_webp_loop(&frames, delay_ms)?,
+    };
+
+    Ok(BASE64.encode(bytes))
+}
+
+/// Encode frames as an infinitely looping GIF
+fn encode_gif_loop(frames: Vec<image::RgbImage>, delay_ms: u32) -> Result<Vec<u8>, VideoError> {
+    use image::codecs::gif::{GifEncoder, Repeat};
+
+    let encode_error = |e: image::ImageError| VideoError {
+        message: format!("Failed to encode GIF: {}", e),
+        code: "GIF_ENCODE_ERROR".to_string(),
+    };
+
+    let mut buffer = Vec::new();
+    {
+        let mut encoder = GifEncoder::new_with_speed(&mut buffer, 10);
+        encoder.set_repeat(Repeat::Infinite).map_err(encode_error)?;
+        let delay = image::Delay::from_numer_denom_ms(delay_ms, 1);
+        encoder
+            .encode_frames(frames.into_iter().map(|rgb| {
+                image::Frame::from_parts(image::DynamicImage::ImageRgb8(rgb).into_rgba8(), 0, 0, delay)
+            }))
+            .map_err(encode_error)?;
+    }
+
+    Ok(buffer)
+}
+
+/// Encode frames as an infinitely looping animated WebP
+fn encode_webp_loop(frames: &[image::RgbImage], delay_ms: u32) -> Result<Vec<u8>, VideoError> {
+    let (width, height) = frames[0].dimensions();
+    let config = webp::WebPConfig::new().map_err(|_| VideoError {
+        message: "Failed to create WebP encoder config".to_string(),
+        code: "WEBP_ENCODE_ERROR".to_string(),
+    })?;
+
+    let mut encoder = webp::AnimEncoder::new(width, height, &config);
+    encoder.set_loop_count(0);
+    for (i, frame) in frames.iter().enumerate() {
+        let timestamp_ms = (i as u32 * delay_ms) as i32;
+        encoder.add_frame(webp::AnimFrame::from_rgb(frame.as_raw(), width, height, timestamp_ms));
+    }
+
+    Ok(encoder.encode().to_vec())
+}
+
+/// Generate multiple thumbnail frames at regular intervals
+pub fn generate_thumbnails(path: &str, interval_secs: f64) -> Result<Vec<String>, VideoError> {
+    generate_thumbnails_with_options(
+        path,
+        interval_secs,
+        60,
+        None,
+        &AtomicBool::new(false),
+        |_, _, _, _| {},
+    )
+}
+
+/// Generate thumbnails with custom options
+///
+/// See `generate_timed_thumbnails`; this variant returns only the images.
+pub fn generate_thumbnails_with_options<F: FnMut(usize, usize, f64, &str)>(
+    path: &str,
+    interval_secs: f64,
+    quality: u8,
+    max_thumbnails: Option<usize>,
+    cancelled: &AtomicBool,
+    on_frame: F,
+) -> Result<Vec<String>, VideoError> {
+    let thumbnails =
+        generate_timed_thumbnails(path, interval_secs, quality, max_thumbnails, cancelled, on_frame)?;
+    Ok(thumbnails.into_iter().map(|t| t.image_base64).collect())
+}
+
+/// Generate thumbnails every `interval_secs`, each paired with the exact
+/// source timestamp (frames that fail to decode are skipped, not shifted)
+///
+/// Frames are extracted in parallel (up to one worker per CPU) and returned
+/// in timestamp order. Checks `cancelled` between frames and returns what it
+/// has so far when set. `on_frame(index, count, timestamp, thumbnail)` is
+/// called as each frame completes, which may be out of order.
+pub fn generate_timed_thumbnails<F: FnMut(usize, usize, f64, &str)>(
+    path: &str,
+    interval_secs: f64,
+    quality: u8,
+    max_thumbnails: Option<usize>,
+    cancelled: &AtomicBool,
+    on_frame: F,
+) -> Result<Vec<TimedThumbnail>, VideoError> {
+    let info = get_video_info_unsampled(path)?;
+
+    if info.duration_secs <= 0.0 {
+        return Err(VideoError {
+            message: "Cannot generate thumbnails for video with zero duration".to_string(),
+            code: "ZERO_DURATION".to_string(),
+        });
+    }
+
+    // Calculate how many thumbnails to generate
+    let mut count = (info.duration_secs / interval_secs).ceil() as usize;
+    if count == 0 {
+        count = 1;
+    }
+
+    // Apply max limit if specified
+    if let Some(max) = max_thumbnails {
+        count = count.min(max);
+    }
+
+    // Cap at reasonable maximum
+    count = count.min(100);
+
+    let timestamps: Vec<f64> = (0..count)
+        .map(|i| i as f64 * interval_secs)
+        .take_while(|&timestamp| timestamp < info.duration_secs)
+        .collect();
+
+    let thumbnails = extract_timed_thumbnails(path, &timestamps, quality, cancelled, on_frame);
+    if cancelled.load(Ordering::SeqCst) {
+        return Ok(thumbnails);
+    }
+
+    if thumbnails.is_empty() {
+        return Err(VideoError {
+            message: "Failed to generate any thumbnails".to_string(),
+            code: "NO_THUMBNAILS".to_string(),
+        });
+    }
+
+    Ok(thumbnails)
+}
+
+/// Extract a thumbnail at each timestamp in parallel (up to one worker per
+/// CPU), returned in timestamp order. Frames that fail to decode are
+/// skipped; stops early when `cancelled` is set.
+fn extract_timed_thumbnails<F: FnMut(usize, usize, f64, &str)>(
+    path: &str,
+    timestamps: &[f64],
+    quality: u8,
+    cancelled: &AtomicBool,
+    mut on_frame: F,
+) -> Vec<TimedThumbnail> {
+    // Each extraction opens its own decoder, so frames decode independently.
+    // Workers pull the next index; results are reassembled in order here,
+    // and on_frame runs on this thread as each one arrives.
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(timestamps.len())
+        .max(1);
+    let next_index = AtomicUsize::new(0);
+    let mut slots: Vec<Option<TimedThumbnail>> = vec![None; timestamps.len()];
+
+    std::thread::scope(|scope| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let next_index = &next_index;
+            scope.spawn(move || loop {
+                if cancelled.load(Ordering::SeqCst) {
+                    break;
+                }
+                let i = next_index.fetch_add(1, Ordering::SeqCst);
+                let Some(&timestamp) = timestamps.get(i) else {
+                    break;
+                };
+                let result = cached_thumbnail(path, timestamp, quality);
+                if tx.send((i, timestamp, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        for (i, timestamp, result) in rx {
+            match result {
+                Ok(frame) => {
+                    on_frame(i, timestamps.len(), timestamp, &frame);
+                    slots[i] = Some(TimedThumbnail {
+                        timestamp_secs: timestamp,
+                        image_base64: frame,
+                    });
+                }
+                Err(e) => {
+                    // Log error but continue with other frames
+                    eprintln!("Warning: Failed to extract frame at {}: {}", timestamp, e);
+                }
+            }
+        }
+    });
+
+    slots.into_iter().flatten().collect()
+}
+
+/// Upper bound on `generate_thumbnails_count`, to keep IPC payloads sane
+const MAX_THUMBNAIL_COUNT: usize = 500;
+
+/// Midpoints of `count` equal segments of `[0, duration_secs)`
+fn evenly_spaced_midpoints(duration_secs: f64, count: usize) -> Vec<f64> {
+    let segment = duration_secs / count as f64;
+    (0..count).map(|i| (i as f64 + 0.5) * segment).collect()
+}
+
+/// Generate exactly `count` thumbnails sampled at the midpoint of evenly
+/// sized segments (never the very first or last frame). Clips shorter than
+/// `count` frames return one thumbnail per frame.
+pub fn generate_thumbnails_count(
+    path: &str,
+    count: usize,
+    quality: u8,
+) -> Result<Vec<String>, VideoError> {
+    if count == 0 || count > MAX_THUMBNAIL_COUNT {
+        return Err(VideoError {
+            message: format!("count must be between 1 and {}", MAX_THUMBNAIL_COUNT),
+            code: "INVALID_ARGUMENT".to_string(),
+        });
+    }
+
+    let info = get_video_info_unsampled(path)?;
+    if info.duration_secs <= 0.0 {
+        return Err(VideoError {
+            message: "Cannot generate thumbnails for video with zero duration".to_string(),
+            code: "ZERO_DURATION".to_string(),
+        });
+    }
+
+    let count = count.min(info.frame_count.max(1) as usize);
+
+    evenly_spaced_midpoints(info.duration_secs, count)
+        .into_iter()
+        .map(|timestamp| cached_thumbnail(path, timestamp, quality))
+        .collect()
+}
+
+/// Generate a single thumbnail at a specific percentage through the video
+pub fn get_thumbnail_at_percent(path: &str, percent: f64) -> Result<String, VideoError> {
+    let info = get_video_info_unsampled(path)?;
+    let timestamp = info.duration_secs * (percent / 100.0).clamp(0.0, 1.0);
+    cached_thumbnail(path, timestamp, 70)
+}
+
+/// Extract the first frame of a video (useful for poster/thumbnail)
+pub fn get_first_frame(path: &str) -> Result<String, VideoError> {
+    get_frame_at_time_with_quality(path, 0.0, 85)
+}
+
+/// A decoded frame and its time in seconds
+type TimedFrame = (VideoFrame, f64);
+
+/// Decode the frames either side of `timestamp_secs`: the last one at or
+/// before it and the first one after it
+fn decode_frames_around(
+    path: &str,
+    timestamp_secs: f64,
+    options: &FrameOptions,
+) -> Result<(Option<TimedFrame>, Option<TimedFrame>), VideoError> {
+    let mut input_ctx = open_input(path)?;
+    let video_stream = input_ctx
+        .streams()
+        .best(Type::Video)
+        .ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+
+    let video_stream_index = video_stream.index();
+    let time_base = video_stream.time_base();
+    let start_pts = stream_start_pts(&video_stream);
+    let (mut decoder, _) =
+        create_tuned_video_decoder(video_stream.parameters(), options.hwaccel, options.decoder)?;
+
+    let timestamp_secs = clamp_to_stream(&input_ctx, &video_stream, timestamp_secs);
+    let target_ts = start_pts + (timestamp_secs / f64::from(time_base)).floor() as i64;
+    seek_stream_before(&mut input_ctx, video_stream_index, target_ts)
+        .or_else(|_| input_ctx.seek(0, ..).map_err(VideoError::from))?;
+
+    let frame_secs = |frame: &VideoFrame| {
+        frame
+            .timestamp()
+            .or(frame.pts())
+            .map_or(timestamp_secs, |ts| (ts - start_pts) as f64 * f64::from(time_base))
+    };
+
+    let mut before = None;
+    let mut decoded_frame = VideoFrame::empty();
+    let mut packets = input_ctx.packets();
+    loop {
+        let at_eof = match packets.next() {
+            Some((stream, packet)) => {
+                if stream.index() != video_stream_index {
+                    continue;
+                }
+                decoder
+                    .send_packet(&packet)
+                    .map_err(|e| corrupt_video_error(path, &e.to_string()))?;
+                false
+            }
+            None => {
+                decoder.send_eof()?;
+                true
+            }
+        };
+        while decoder.receive_frame(&mut decoded_frame).is_ok() {
+            let secs = frame_secs(&decoded_frame);
+            if secs > timestamp_secs {
+                return Ok((before, Some((decoded_frame, secs))));
+            }
+            before = Some((decoded_frame.clone(), secs));
+        }
+        if at_eof {
+            return Ok((before, None));
+        }
+    }
+}
+
+/// Per-channel linear blend: `weight` 0 gives `a`, 1 gives `b`
+fn blend_rgb(a: &image::RgbImage, b: &image::RgbImage, weight: f64) -> image::RgbImage {
+    let mut out = a.clone();
+    for (dst, &src) in out.iter_mut().zip(b.iter()) {
+        *dst = (*dst as f64 + (src as f64 - *dst as f64) * weight).round() as u8;
+    }
+    out
+}
+
+/// The image shown at `timestamp_secs` when blending the neighbouring
+/// decoded frames by how far the time falls between them. This is a
+/// cross-fade, not motion interpolation: moving objects appear doubled.
+fn interpolated_rgb(
+    path: &str,
+    timestamp_secs: f64,
+    options: &FrameOptions,
+) -> Result<image::RgbImage, VideoError> {
+    let (before, after) = decode_frames_around(path, timestamp_secs, options)?;
+    let rgb = |frame: VideoFrame| frame_to_rgb_image(&apply_frame_options(frame, options)?);
+
+    match (before, after) {
+        (Some((a, a_secs)), Some((b, b_secs))) => {
+            let weight = ((timestamp_secs - a_secs) / (b_secs - a_secs)).clamp(0.0, 1.0);
+            let (a, b) = (rgb(a)?, rgb(b)?);
+            if a.dimensions() != b.dimensions() {
+                // Resolution changed between the frames; show the nearer one
+                return Ok(if weight < 0.5 { a } else { b });
+            }
+            Ok(blend_rgb(&a, &b, weight))
+        }
+        (Some((frame, _)), None) | (None, Some((frame, _))) => rgb(frame),
+        (None, None) => Err(VideoError {
+            message: format!("Could not find frame at timestamp {}", timestamp_secs),
+            code: "FRAME_NOT_FOUND".to_string(),
+        }),
+    }
+}
+
+/// Extract a still at a sub-frame time by blending the two nearest decoded
+/// frames, weighted by the time's position between them. Smooths scrub
+/// previews of very low-fps sources; it is a blend, not true motion
+/// interpolation. Times on or outside the decoded frames return that frame.
+pub fn get_interpolated_frame(
+    path: &str,
+    timestamp_secs: f64,
+    format: OutputFormat,
+    options: &FrameOptions,
+) -> Result<String, VideoError> {
+    let img = interpolated_rgb(path, timestamp_secs, options)?;
+    Ok(BASE64.encode(encode_rgb_image(&img, format)?))
+}
+
+/// Decode the first keyframe of the video stream, reading from the start
+/// with no seek and no search for a target timestamp
+fn decode_first_keyframe(path: &str, options: &FrameOptions) -> Result<VideoFrame, VideoError> {
+    let mut input_ctx = open_input(path)?;
+    let video_stream = input_ctx
+        .streams()
+        .best(Type::Video)
+        .ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+    let video_stream_index = video_stream.index();
+    let (mut decoder, _) =
+        create_tuned_video_decoder(video_stream.parameters(), options.hwaccel, options.decoder)?;
+
+    let mut decoded_frame = VideoFrame::empty();
+    let mut seen_keyframe = false;
+    for (stream, packet) in input_ctx.packets() {
+        if stream.index() != video_stream_index {
+            continue;
+        }
+        // Leading frames of an open GOP can't decode cleanly; skip to the keyframe
+        seen_keyframe |= packet.is_key();
+        if !seen_keyframe {
+            continue;
+        }
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| corrupt_video_error(path, &e.to_string()))?;
+        if decoder.receive_frame(&mut decoded_frame).is_ok() {
+            return Ok(decoded_frame);
+        }
+    }
+
+    decoder.send_eof()?;
+    if decoder.receive_frame(&mut decoded_frame).is_ok() {
+        return Ok(decoded_frame);
+    }
+
+    Err(VideoError {
+        message: format!("No decodable keyframe in '{}'", path),
+        code: "FRAME_NOT_FOUND".to_string(),
+    })
+}
+
+/// Fast poster for media-bin grids: the first keyframe, returned as soon as
+/// it decodes. Unlike `get_first_frame` this skips seeking and the
+/// closest-frame search, so it may pick a frame just after a clip's nominal
+/// start when the stream opens on non-key frames.
+pub fn get_fast_poster(
+    path: &str,
+    format: OutputFormat,
+    options: &FrameOptions,
+) -> Result<String, VideoError> {
+    let frame = apply_frame_options(decode_first_keyframe(path, options)?, options)?;
+    encode_frame_as_base64(&frame, format)
+}
+
+/// Candidate frames scored when picking a poster
+const POSTER_SAMPLES: usize = 9;
+
+/// Luma size poster candidates are scored at
+const POSTER_LUMA_WIDTH: u32 = 160;
+const POSTER_LUMA_HEIGHT: u32 = 90;
+
+/// Frames darker than this mean luma are never chosen as posters
+const POSTER_MIN_MEAN_LUMA: f64 = 24.0;
+
+/// Score a luma buffer as a poster candidate: sharpness (variance of the
+/// Laplacian) plus contrast, penalising very dark or blown-out frames.
+/// Returns None for near-black frames.
+fn poster_score(luma: &[u8], width: usize, height: usize) -> Option<f64> {
+    let n = luma.len() as f64;
+    let mean = luma.iter().map(|&v| v as f64).sum::<f64>() / n;
+    if mean < POSTER_MIN_MEAN_LUMA {
+        return None;
+    }
+    let contrast = (luma.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>() / n).sqrt();
+
+    let mut laplacians = Vec::with_capacity(width * height);
+    for y in 1..height.saturating_sub(1) {
+        for x in 1..width.saturating_sub(1) {
+            let at = |dx: isize, dy: isize| {
+                luma[(y as isize + dy) as usize * width + (x as isize + dx) as usize] as f64
+            };
+            laplacians.push(at(-1, 0) + at(1, 0) + at(0, -1) + at(0, 1) - 4.0 * at(0, 0));
+        }
+    }
+    let sharpness = if laplacians.is_empty() {
+        0.0
+    } else {
+        let lap_mean = laplacians.iter().sum::<f64>() / laplacians.len() as f64;
+        let variance = laplacians.iter().map(|v| (v - lap_mean).powi(2)).sum::<f64>()
+            / laplacians.len() as f64;
+        variance.sqrt()
+    };
+
+    Some(sharpness + contrast - (mean - 128.0).abs() * 0.25)
+}
+
+/// Pick the most representative poster frame: sample across the clip,
+/// skip near-black frames and keep the sharpest, best-exposed one.
+/// Falls back to the middle of the clip when every sample is dark.
+pub fn get_smart_poster_frame(path: &str) -> Result<String, VideoError> {
+    let info = get_video_info_unsampled(path)?;
+    let count = POSTER_SAMPLES.min(info.frame_count.max(1) as usize);
+    let timestamps = evenly_spaced_midpoints(info.duration_secs.max(0.0), count);
+
+    let mut best: Option<(f64, VideoFrame)> = None;
+    for &timestamp in &timestamps {
+        let frame = decode_frame_at_time(path, timestamp, &FrameOptions::default())?;
+        let luma = frame_to_luma(&frame, POSTER_LUMA_WIDTH, POSTER_LUMA_HEIGHT)?;
+        let Some(score) = poster_score(&luma, POSTER_LUMA_WIDTH as usize, POSTER_LUMA_HEIGHT as usize) else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+            best = Some((score, frame));
+        }
+    }
+
+    match best {
+        Some((_, frame)) => encode_frame_as_base64(&frame, OutputFormat::default()),
+        None => get_frame_at_time_with_quality(path, info.duration_secs / 2.0, 85),
+    }
+}
+
+/// Luma difference treated as a cut when building smart filmstrips
+const FILMSTRIP_CUT_THRESHOLD: f64 = 0.3;
+
+/// Frames per second analysed for cuts when building smart filmstrips
+const FILMSTRIP_ANALYSIS_FPS: f64 = 4.0;
+
+/// How far into a scene a filmstrip sample is taken, skipping dissolves
+const FILMSTRIP_SCENE_OFFSET_SECS: f64 = 0.5;
+
+/// Sample times for a scene-aware strip: the `count - 1` strongest cuts
+/// split the clip into `count` scenes, each sampled just after it starts.
+/// Uniform midpoints when fewer than `count` scenes were found.
+fn smart_filmstrip_timestamps(duration_secs: f64, cuts: &[SceneCut], count: usize) -> Vec<f64> {
+    let mut cuts: Vec<&SceneCut> = cuts
+        .iter()
+        .filter(|cut| cut.timestamp_secs > 0.0 && cut.timestamp_secs < duration_secs)
+        .collect();
+    if cuts.len() + 1 < count {
+        return evenly_spaced_midpoints(duration_secs, count);
+    }
+
+    cuts.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut bounds: Vec<f64> = std::iter::once(0.0)
+        .chain(cuts.iter().take(count.saturating_sub(1)).map(|cut| cut.timestamp_secs))
+        .collect();
+    bounds.sort_by(f64::total_cmp);
+    bounds.push(duration_secs);
+
+    bounds
+        .windows(2)
+        .map(|scene| scene[0] + ((scene[1] - scene[0]) / 2.0).min(FILMSTRIP_SCENE_OFFSET_SECS))
+        .collect()
+}
+
+/// Generate `count` thumbnails biased toward scene boundaries, so the strip
+/// shows each shot once instead of repeating static ones. Runs a quick
+/// scene-cut pass first and falls back to uniform spacing when there are
+/// fewer cuts than thumbnails.
+pub fn generate_smart_filmstrip(
+    path: &str,
+    count: usize,
+    quality: u8,
+) -> Result<Vec<TimedThumbnail>, VideoError> {
+    if count == 0 || count > MAX_THUMBNAIL_COUNT {
+        return Err(VideoError {
+            message: format!("count must be between 1 and {}", MAX_THUMBNAIL_COUNT),
+            code: "INVALID_ARGUMENT".to_string(),
+        });
+    }
+
+    let info = get_video_info_unsampled(path)?;
+    if info.duration_secs <= 0.0 {
+        return Err(VideoError {
+            message: "Cannot generate thumbnails for video with zero duration".to_string(),
+            code: "ZERO_DURATION".to_string(),
+        });
+    }
+    let count = count.min(info.frame_count.max(1) as usize);
+
+    let stride = (info.fps / FILMSTRIP_ANALYSIS_FPS).round().max(1.0) as usize;
+    let cuts = detect_scene_cuts(path, FILMSTRIP_CUT_THRESHOLD, stride)?;
+    let timestamps = smart_filmstrip_timestamps(info.duration_secs, &cuts, count);
+
+    let cancelled = AtomicBool::new(false);
+    let thumbnails = extract_timed_thumbnails(path, &timestamps, quality, &cancelled, |_, _, _, _| {});
+    if thumbnails.is_empty() {
+        return Err(VideoError {
+            message: "Failed to generate any thumbnails".to_string(),
+            code: "NO_THUMBNAILS".to_string(),
+        });
+    }
+    Ok(thumbnails)
+}
+
+/// A thumbnail and the source time it was taken from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimedThumbnail {
+    pub timestamp_secs: f64,
+    /// Base64 JPEG
+    pub image_base64: String,
+}
+
+/// Progress payload emitted per thumbnail so the strip can fill incrementally
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThumbnailsProgress {
+    pub job_id: Option<String>,
+    /// Position of this thumbnail in the strip
+    pub index: usize,
+    /// Number of thumbnails requested
+    pub total: usize,
+    pub timestamp_secs: f64,
+    /// Base64 JPEG
+    pub thumbnail: String,
+}
+
+/// Progress payload emitted while exporting a frame sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractFramesProgress {
+    pub job_id: String,
+    pub frames_written: usize,
+    pub timestamp_secs: f64,
+    pub end_secs: f64,
+}
+
+/// Decode `[start_secs, end_secs]` and write every `step`th frame to
+/// `output_dir` as `frame_000001.png`, `frame_000002.png`, ...
+///
+/// Stops early (keeping what was written) when `cancelled` is set.
+/// Returns the number of frames written.
+pub fn extract_frames_to_dir<F: FnMut(usize, f64)>(
+    path: &str,
+    start_secs: f64,
+    end_secs: f64,
+    output_dir: &str,
+    step: usize,
+    cancelled: &AtomicBool,
+    mut on_progress: F,
+) -> Result<usize, VideoError> {
+    if step == 0 {
+        return Err(VideoError {
+            message: "step must be at least 1".to_string(),
+            code: "INVALID_ARGUMENT".to_string(),
+        });
+    }
+    if start_secs < 0.0 || end_secs <= start_secs {
+        return Err(VideoError {
+            message: format!("Invalid range: {} to {}", start_secs, end_secs),
+            code: "INVALID_ARGUMENT".to_string(),
+        });
+    }
+
+    let out_dir = Path::new(output_dir);
+    fs::create_dir_all(out_dir)?;
+
+    let mut input_ctx = open_input(path)?;
+
+    let video_stream = input_ctx
+        .streams()
+        .best(Type::Video)
+        .ok_or_else(|| VideoError {
+            message: "No video stream found".to_string(),
+            code: "NO_VIDEO_STREAM".to_string(),
+        })?;
+
+    let video_stream_index = video_stream.index();
+    let time_base = f64::from(video_stream.time_base());
+    let start_pts = stream_start_pts(&video_stream);
+
+    let codec_ctx = ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())?;
+    let mut decoder = codec_ctx.decoder().video()?;
+
+    // Seek to the keyframe at or before the range start
+    let target_ts = start_pts + (start_secs / time_base).round() as i64;
+    seek_stream_before(&mut input_ctx, video_stream_index, target_ts)
+        .or_else(|_| input_ctx.seek(0, ..).map_err(VideoError::from))?;
+
+    let frame_secs = |frame: &VideoFrame| {
+        (frame.timestamp().or(frame.pts()).unwrap_or(start_pts) - start_pts) as f64 * time_base
+    };
+
+    let mut in_range_index = 0usize;
+    let mut written = 0usize;
+    let mut done = false;
+
+    let mut handle_frame = |frame: &VideoFrame, written: &mut usize| -> Result<bool, VideoError> {
+        let secs = frame_secs(frame);
+        if secs < start_secs {
+            return Ok(false);
+        }
+        if secs > end_secs {
+            return Ok(true);
+        }
+
+        if in_range_index % step == 0 {
+            let file = out_dir.join(format!("frame_{:06}.png", *written + 1));
+            write_frame_to_file(frame, &file, OutputFormat::Png)?;
+            *written += 1;
+            on_progress(*written, secs);
+        }
+        in_range_index += 1;
+        Ok(false)
+    };
+
+    for (stream, packet) in input_ctx.packets() {
+        if cancelled.load(Ordering::SeqCst) {
+            return Ok(written);
+        }
+        if stream.index() != video_stream_index {
+            continue;
+        }
+
+        decoder.send_packet(&packet)?;
+
+        let mut decoded_frame = VideoFrame::empty();
+        while decoder.receive_frame(&mut decoded_frame).is_ok() {
+            if handle_frame(&decoded_frame, &mut written)? {
+                done = true;
+                break;
+            }
+        }
+        if done {
+            break;
+        }
+    }
+
+    if !done && !cancelled.load(Ordering::SeqCst) {
+        decoder.send_eof()?;
+        let mut decoded_frame = VideoFrame::empty();
+        while decoder.receive_frame(&mut decoded_frame).is_ok() {
+            if handle_frame(&decoded_frame, &mut written)? {
+                break;
+            }
+        }
+    }
+
+    Ok(written)
+}
+
+// ============================================================================
+// Tauri Commands
+// ============================================================================
+
+/// Tauri command to get video information
+#[tauri::command]
+pub async fn cmd_get_video_info(
+    path: String,
+    assumed_fps: Option<f64>,
+) -> Result<VideoInfo, VideoError> {
+    // VFR detection reads packets ahead
+    tokio::task::spawn_blocking(move || get_video_info_with_fps(&path, assumed_fps)).await?
+}
+
+/// Tauri command to list all streams (audio/subtitle tracks included)
+#[tauri::command]
+pub async fn cmd_probe_streams(path: String) -> Result<Vec<StreamInfo>, VideoError> {
+    tokio::task::spawn_blocking(move || probe_streams(&path)).await?
+}
+
+/// Tauri command to register a numbered image sequence as a clip. Accepts a
+/// pattern (`frame%04d.png`) or any frame of the sequence; returns the pattern
+/// to pass as `path` to the other video commands.
+#[tauri::command]
+pub async fn cmd_register_image_sequence(
+    path: String,
+    fps: Option<f64>,
+) -> Result<ImageSequence, VideoError> {
+    tokio::task::spawn_blocking(move || register_image_sequence(&path, fps)).await?
+}
+
+/// Tauri command to open a video and get a handle
+#[tauri::command]
+pub async fn cmd_open_video(
+    path: String,
+    hwaccel: Option<HwAccel>,
+    options: Option<OpenOptions>,
+) -> Result<String, VideoError> {
+    // Opening decodes the first frame to check integrity
+    tokio::task::spawn_blocking(move || open_video(&path, hwaccel, options.unwrap_or_default()))
+        .await?
+}
+
+/// Tauri command to extract a frame using an open handle's decoder settings
+#[tauri::command]
+pub async fn cmd_get_frame_from_handle(
+    handle_id: String,
+    timestamp_secs: f64,
+    format: Option<OutputFormat>,
+) -> Result<String, VideoError> {
+    tokio::task::spawn_blocking(move || {
+        get_frame_from_handle(&handle_id, timestamp_secs, format.unwrap_or_default())
+    })
+    .await?
+}
+
+/// Tauri command to get the info stored on an open handle
+#[tauri::command]
+pub async fn cmd_get_video_handle_info(handle_id: String) -> Result<VideoInfo, VideoError> {
+    get_video_handle(&handle_id).map(|handle| handle.info.clone())
+}
+
+/// Tauri command to list open video handles (for debugging leaks)
+#[tauri::command]
+pub async fn cmd_list_open_videos() -> Result<Vec<OpenVideoInfo>, VideoError> {
+    list_open_videos()
+}
+
+/// Tauri command to close handles idle longer than `max_idle_secs`
+#[tauri::command]
+pub async fn cmd_prune_video_handles(max_idle_secs: f64) -> Result<usize, VideoError> {
+    prune_video_handles(max_idle_secs)
+}
+
+/// Tauri command to check whether a video is truncated or corrupt
+#[tauri::command]
+pub async fn cmd_check_video_integrity(path: String) -> Result<(), VideoError> {
+    tokio::task::spawn_blocking(move || check_video_integrity(&path)).await?
+}
+
+/// Tauri command to close a video handle
+#[tauri::command]
+pub async fn cmd_close_video(handle_id: String) -> Result<(), VideoError> {
+    close_video(&handle_id)
+}
+
+/// Tauri command to get a frame at a specific timestamp
+#[tauri::command]
+pub async fn cmd_get_frame_at_time(
+    path: String,
+    timestamp_secs: f64,
+    hwaccel: Option<HwAccel>,
+    format: Option<OutputFormat>,
+    tonemap: Option<bool>,
+    lut_path: Option<String>,
+    scaling: Option<ScalingAlgorithm>,
+) -> Result<String, VideoError> {
+    let options = FrameOptions {
+        hwaccel,
+        tonemap: tonemap.unwrap_or(false),
+        lut_path,
+        scaling: scaling.unwrap_or_default(),
+        ..Default::default()
+    };
+    // Run in blocking task since FFmpeg operations are CPU-intensive
+    tokio::task::spawn_blocking(move || {
+        get_frame_at_time_with_options(&path, timestamp_secs, format.unwrap_or_default(), &options)
+    })
+    .await?
+}
+
+/// Tauri command to extract a frame along with its dimensions
+#[tauri::command]
+pub async fn cmd_get_frame_with_dimensions(
+    path: String,
+    timestamp_secs: f64,
+    hwaccel: Option<HwAccel>,
+    format: Option<OutputFormat>,
+    tonemap: Option<bool>,
+    lut_path: Option<String>,
+    scaling: Option<ScalingAlgorithm>,
+) -> Result<FrameWithDimensions, VideoError> {
+    let options = FrameOptions {
+        hwaccel,
+        tonemap: tonemap.unwrap_or(false),
+        lut_path,
+        scaling: scaling.unwrap_or_default(),
+        ..Default::default()
+    };
+    tokio::task::spawn_blocking(move || {
+        get_frame_with_dimensions(&path, timestamp_secs, format.unwrap_or_default(), &options)
+    })
+    .await?
+}
+
+/// Tauri command to get a frame as a ready-to-use `data:` URL
+#[tauri::command]
+pub async fn cmd_get_frame_data_url(
+    path: String,
+    timestamp_secs: f64,
+    hwaccel: Option<HwAccel>,
+    format: Option<OutputFormat>,
+    tonemap: Option<bool>,
+    lut_path: Option<String>,
+    scaling: Option<ScalingAlgorithm>,
+) -> Result<String, VideoError> {
+    let options = FrameOptions {
+        hwaccel,
+        tonemap: tonemap.unwrap_or(false),
+        lut_path,
+        scaling: scaling.unwrap_or_default(),
+        ..Default::default()
+    };
+    tokio::task::spawn_blocking(move || {
+        get_frame_data_url(&path, timestamp_secs, format.unwrap_or_default(), &options)
+    })
+    .await?
+}
+
+/// Tauri command to extract the keyframe at or before a timestamp
+#[tauri::command]
+pub async fn cmd_get_nearest_keyframe_frame(
+    path: String,
+    timestamp_secs: f64,
+    hwaccel: Option<HwAccel>,
+    format: Option<OutputFormat>,
+) -> Result<KeyframeFrame, VideoError> {
+    let options = FrameOptions {
+        hwaccel,
+        ..Default::default()
+    };
+    tokio::task::spawn_blocking(move || {
+        get_nearest_keyframe_frame(&path, timestamp_secs, format.unwrap_or_default(), &options)
+    })
+    .await?
+}
+
+/// Tauri command to preview a frame of cloud-hosted media via range requests
+#[tauri::command]
+pub async fn cmd_get_frame_from_url(
+    url: String,
+    timestamp_secs: f64,
+    format: Option<OutputFormat>,
+) -> Result<String, VideoError> {
+    tokio::task::spawn_blocking(move || {
+        get_frame_from_url(&url, timestamp_secs, format.unwrap_or_default())
+    })
+    .await?
+}
+
+/// Tauri command to get a frame with custom quality
+#[tauri::command]
+pub async fn cmd_get_frame_at_time_with_quality(
+    path: String,
+    timestamp_secs: f64,
+    quality: u8,
+    hwaccel: Option<HwAccel>,
+    tonemap: Option<bool>,
+    lut_path: Option<String>,
+) -> Result<String, VideoError> {
+    let options = FrameOptions {
+        hwaccel,
+        tonemap: tonemap.unwrap_or(false),
+        lut_path,
+        ..Default::default()
+    };
+    tokio::task::spawn_blocking(move || {
+        get_frame_at_time_with_options(&path, timestamp_secs, OutputFormat::Jpeg { quality }, &options)
+    })
+    .await?
+}
+
+/// Tauri command to get the frame at an exact frame index
+#[tauri::command]
+pub async fn cmd_get_frame_at_index(
+    path: String,
+    frame_index: u64,
+    quality: Option<u8>,
+    format: Option<OutputFormat>,
+) -> Result<String, VideoError> {
+    // An explicit format wins over the legacy JPEG quality parameter
+    let format = format.unwrap_or(OutputFormat::Jpeg {
+        quality: quality.unwrap_or(85),
+    });
+    tokio::task::spawn_blocking(move || {
+        get_frame_at_index(&path, frame_index, format, &FrameOptions::default())
+    })
+    .await?
+}
+
+/// Tauri command to generate thumbnails at regular intervals
+#[tauri::command]
+pub async fn cmd_generate_thumbnails(
+    path: String,
+    interval_secs: f64,
+) -> Result<Vec<String>, VideoError> {
+    tokio::task::spawn_blocking(move || generate_thumbnails(&path, interval_secs)).await?
+}
+
+/// Run a thumbnail strip job, emitting "thumbnails-progress" per frame and
+/// registering `job_id` (when given) for `cmd_cancel_thumbnail_job`
+fn run_thumbnail_job(
+    app: &AppHandle,
+    path: &str,
+    interval_secs: f64,
+    quality: u8,
+    max_thumbnails: Option<usize>,
+    job_id: Option<String>,
+) -> Result<Vec<TimedThumbnail>, VideoError> {
+    let cancelled = match &job_id {
+        Some(job_id) => register_decode_job(job_id),
+        None => Arc::new(AtomicBool::new(false)),
+    };
+    let result = generate_timed_thumbnails(
+        path,
+        interval_secs,
+        quality,
+        max_thumbnails,
+        &cancelled,
+        |index, total, timestamp_secs, thumbnail| {
+            let _ = app.emit(
+                "thumbnails-progress",
+                ThumbnailsProgress {
+                    job_id: job_id.clone(),
+                    index,
+                    total,
+                    timestamp_secs,
+                    thumbnail: thumbnail.to_string(),
+                },
+            );
+        },
+    );
+    if let Some(job_id) = &job_id {
+        finish_decode_job(job_id);
+    }
+    result
+}
+
+/// Tauri command to generate thumbnails with options. Pass `job_id` to
+/// receive "thumbnails-progress" events and allow `cmd_cancel_thumbnail_job`.
+#[tauri::command]
+pub async fn cmd_generate_thumbnails_with_options(
+    app: AppHandle,
+    path: String,
+    interval_secs: f64,
+    quality: u8,
+    max_thumbnails: Option<usize>,
+    job_id: Option<String>,
+) -> Result<Vec<String>, VideoError> {
+    tokio::task::spawn_blocking(move || {
+        let thumbnails = run_thumbnail_job(&app, &path, interval_secs, quality, max_thumbnails, job_id)?;
+        Ok(thumbnails.into_iter().map(|t| t.image_base64).collect())
+    })
+    .await?
+}
+
+/// Tauri command like `cmd_generate_thumbnails_with_options`, but pairing
+/// each thumbnail with its exact source timestamp
+#[tauri::command]
+pub async fn cmd_generate_thumbnails_with_timestamps(
+    app: AppHandle,
+    path: String,
+    interval_secs: f64,
+    quality: Option<u8>,
+    max_thumbnails: Option<usize>,
+    job_id: Option<String>,
+) -> Result<Vec<TimedThumbnail>, VideoError> {
+    tokio::task::spawn_blocking(move || {
+        run_thumbnail_job(&app, &path, interval_secs, quality.unwrap_or(70), max_thumbnails, job_id)
+    })
+    .await?
+}
+
+/// Tauri command to generate exactly `count` evenly spaced thumbnails
+#[tauri::command]
+pub async fn cmd_generate_thumbnails_count(
+    path: String,
+    count: usize,
+    quality: Option<u8>,
+) -> Result<Vec<String>, VideoError> {
+    tokio::task::spawn_blocking(move || {
+        generate_thumbnails_count(&path, count, quality.unwrap_or(60))
+    })
+    .await?
+}
+
+/// Tauri command to stop a running thumbnail strip job early
+#[tauri::command]
+pub async fn cmd_cancel_thumbnail_job(job_id: String) -> Result<bool, VideoError> {
+    Ok(cancel_decode_job(&job_id))
+}
+
+/// Tauri command to get the first frame of a video
+#[tauri::command]
+pub async fn cmd_get_first_frame(path: String) -> Result<String, VideoError> {
+    tokio::task::spawn_blocking(move || get_first_frame(&path)).await?
+}
+
+/// Tauri command to get a still blended between the two nearest frames
+#[tauri::command]
+pub async fn cmd_get_interpolated_frame(
+    path: String,
+    timestamp_secs: f64,
+    hwaccel: Option<HwAccel>,
+    format: Option<OutputFormat>,
+) -> Result<String, VideoError> {
+    let options = FrameOptions {
+        hwaccel,
+        ..Default::default()
+    };
+    tokio::task::spawn_blocking(move || {
+        get_interpolated_frame(&path, timestamp_secs, format.unwrap_or_default(), &options)
+    })
+    .await?
+}
+
+/// Tauri command for the quickest possible poster (first keyframe)
+#[tauri::command]
+pub async fn cmd_get_fast_poster(
+    path: String,
+    hwaccel: Option<HwAccel>,
+    format: Option<OutputFormat>,
+) -> Result<String, VideoError> {
+    let options = FrameOptions {
+        hwaccel,
+        ..Default::default()
+    };
+    tokio::task::spawn_blocking(move || {
+        get_fast_poster(&path, format.unwrap_or_default(), &options)
+    })
+    .await?
+}
+
+/// Tauri command to check a file's codec is supported before import
+#[tauri::command]
+pub async fn cmd_can_decode(path: String) -> Result<DecodeSupport, VideoError> {
+    Ok(tokio::task::spawn_blocking(move || can_decode(&path)).await?)
+}
+
+/// Tauri command to pick a representative (non-black, sharp) poster frame
+#[tauri::command]
+pub async fn cmd_get_smart_poster_frame(path: String) -> Result<String, VideoError> {
+    tokio::task::spawn_blocking(move || get_smart_poster_frame(&path)).await?
+}
+
+/// Tauri command to get a thumbnail at a percentage through the video
+#[tauri::command]
+pub async fn cmd_get_thumbnail_at_percent(path: String, percent: f64) -> Result<String, VideoError> {
+    tokio::task::spawn_blocking(move || get_thumbnail_at_percent(&path, percent)).await?
+}
+
+/// Tauri command to list keyframe timestamps for scrubbing/snapping
+#[tauri::command]
+pub async fn cmd_get_keyframe_timestamps(
+    path: String,
+    max: Option<usize>,
+) -> Result<Vec<f64>, VideoError> {
+    tokio::task::spawn_blocking(move || get_keyframe_timestamps(&path, max)).await?
+}
+
+/// Tauri command to list the GOP layout (picture types) for diagnostics
+#[tauri::command]
+pub async fn cmd_get_gop_structure(
+    path: String,
+    max_frames: Option<usize>,
+) -> Result<Vec<GopFrame>, VideoError> {
+    tokio::task::spawn_blocking(move || get_gop_structure(&path, max_frames)).await?
+}
+
+/// Tauri command to get embedded cover art for the audio bin
+#[tauri::command]
+pub async fn cmd_get_cover_art(path: String) -> Result<Option<CoverArt>, VideoError> {
+    tokio::task::spawn_blocking(move || get_cover_art(&path)).await?
+}
+
+/// Tauri command to find scene changes for "split at scene changes"
+#[tauri::command]
+pub async fn cmd_detect_scene_cuts(
+    path: String,
+    threshold: f64,
+    stride: Option<usize>,
+) -> Result<Vec<SceneCut>, VideoError> {
+    tokio::task::spawn_blocking(move || detect_scene_cuts(&path, threshold, stride.unwrap_or(1)))
+        .await?
+}
+
+/// Tauri command for a scene-aware strip of `count` thumbnails
+#[tauri::command]
+pub async fn cmd_generate_smart_filmstrip(
+    path: String,
+    count: usize,
+    quality: Option<u8>,
+) -> Result<Vec<TimedThumbnail>, VideoError> {
+    tokio::task::spawn_blocking(move || generate_smart_filmstrip(&path, count, quality.unwrap_or(70)))
+        .await?
+}
+
+/// Tauri command to fingerprint a clip for near-duplicate detection
+#[tauri::command]
+pub async fn cmd_compute_video_phash(path: String) -> Result<String, VideoError> {
+    tokio::task::spawn_blocking(move || compute_video_phash(&path)).await?
+}
+
+/// Tauri command to compare two clip fingerprints (0 = identical)
+#[tauri::command]
+pub fn cmd_phash_distance(a: String, b: String) -> Result<u32, VideoError> {
+    phash_distance(&a, &b)
+}
+
+/// Tauri command to build an animated hover preview for a clip range
+#[tauri::command]
+pub async fn cmd_generate_preview_loop(
+    path: String,
+    start_secs: f64,
+    duration_secs: f64,
+    fps: f64,
+    width: u32,
+    format: Option<PreviewLoopFormat>,
+) -> Result<String, VideoError> {
+    tokio::task::spawn_blocking(move || {
+        generate_preview_loop(
+            &path,
+            start_secs,
+            duration_secs,
+            fps,
+            width,
+            format.unwrap_or_default(),
+        )
+    })
+    .await?
+}
+
+/// Tauri command to write a single frame to disk (for the thumbnail cache)
+#[tauri::command]
+pub async fn cmd_extract_frame_to_file(
+    path: String,
+    timestamp_secs: f64,
+    out_path: String,
+    format: Option<OutputFormat>,
+    hwaccel: Option<HwAccel>,
+    tonemap: Option<bool>,
+    lut_path: Option<String>,
+    scaling: Option<ScalingAlgorithm>,
+) -> Result<String, VideoError> {
+    let options = FrameOptions {
+        hwaccel,
+        tonemap: tonemap.unwrap_or(false),
+        lut_path,
+        scaling: scaling.unwrap_or_default(),
+        ..Default::default()
+    };
+    tokio::task::spawn_blocking(move || {
+        extract_frame_to_file(&path, timestamp_secs, &out_path, format.unwrap_or_default(), &options)
+    })
+    .await?
+}
+
+/// Tauri command to export a frame range as a numbered PNG sequence.
+/// Emits "extract-frames-progress" events and can be stopped with
+/// `cmd_cancel_extract_frames`.
+#[tauri::command]
+pub async fn cmd_extract_frames_to_dir(
+    app: AppHandle,
+    path: String,
+    start_secs: f64,
+    end_secs: f64,
+    output_dir: String,
+    step: usize,
+    job_id: String,
+) -> Result<usize, VideoError> {
+    tokio::task::spawn_blocking(move || {
+        let cancelled = register_decode_job(&job_id);
+        let result = extract_frames_to_dir(
+            &path,
+            start_secs,
+            end_secs,
+            &output_dir,
+            step,
+            &cancelled,
+            |frames_written, timestamp_secs| {
+                let _ = app.emit(
+                    "extract-frames-progress",
+                    ExtractFramesProgress {
+                        job_id: job_id.clone(),
+                        frames_written,
+                        timestamp_secs,
+                        end_secs,
+                    },
+                );
+            },
+        );
+        finish_decode_job(&job_id);
+        result
+    })
+    .await?
+}
+
+/// Tauri command to cancel a running frame sequence export
+#[tauri::command]
+pub async fn cmd_cancel_extract_frames(job_id: String) -> Result<bool, VideoError> {
+    Ok(cancel_decode_job(&job_id))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn drain_packets(
+        encoder: &mut ffmpeg::codec::encoder::video::Encoder,
+        octx: &mut ffmpeg::format::context::Output,
+        stream_index: usize,
+        encoder_tb: ffmpeg::Rational,
+        stream_tb: ffmpeg::Rational,
+    ) {
+        let mut packet = ffmpeg::Packet::empty();
+        while encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(stream_index);
+            packet.rescale_ts(encoder_tb, stream_tb);
+            packet.write_interleaved(octx).unwrap();
+        }
+    }
+
+    /// Encode a small MPEG-4 clip in a temp file. Frame `i` has timestamp
+    /// `pts[i]` in units of 1/`time_base_den` seconds and a distinct luma level.
+    pub(crate) fn write_test_clip(name: &str, time_base_den: i32, pts: &[i64]) -> PathBuf {
+        write_rotated_test_clip(name, time_base_den, pts, 0)
+    }
+
+    /// `write_test_clip` with a display matrix rotating it `rotation` degrees
+    /// clockwise, like portrait phone footage
+    fn write_rotated_test_clip(name: &str, time_base_den: i32, pts: &[i64], rotation: i32) -> PathBuf {
+        ffmpeg::init().unwrap();
+        let path = std::env::temp_dir().join(format!("{}_{}.mp4", name, uuid::Uuid::new_v4()));
+        let (width, height) = (64, 48);
+
+        let mut octx = ffmpeg::format::output(&path).unwrap();
+        let codec = ffmpeg::encoder::find(ffmpeg::codec::Id::MPEG4).unwrap();
+        let global_header = octx
+            .format()
+            .flags()
+            .contains(ffmpeg::format::Flags::GLOBAL_HEADER);
+
+        let mut stream = octx.add_stream(codec).unwrap();
+        let mut encoder = ffmpeg::codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .video()
+            .unwrap();
+        let encoder_tb = ffmpeg::Rational::new(1, time_base_den);
+        encoder.set_width(width);
+        encoder.set_height(height);
+        encoder.set_format(Pixel::YUV420P);
+        encoder.set_time_base(encoder_tb);
+        encoder.set_gop(12);
+        if global_header {
+            encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
+        let mut encoder = encoder.open_as(codec).unwrap();
+        stream.set_parameters(&encoder);
+        stream.set_time_base(encoder_tb);
+        if rotation != 0 {
+            // SAFETY: codecpar is owned by the stream; the new side data is
+            // sized for the 3x3 i32 display matrix
+            unsafe {
+                let par = (*stream.as_mut_ptr()).codecpar;
+                let side_data = ffmpeg::ffi::av_packet_side_data_new(
+                    &mut (*par).coded_side_data,
+                    &mut (*par).nb_coded_side_data,
+                    ffmpeg::ffi::AVPacketSideDataType::AV_PKT_DATA_DISPLAYMATRIX,
+                    9 * std::mem::size_of::<i32>(),
+                    0,
+                );
+                assert!(!side_data.is_null());
+                ffmpeg::ffi::av_display_rotation_set((*side_data).data as *mut i32, -rotation as f64);
+            }
+        }
+        let stream_index = stream.index();
+
+        octx.write_header().unwrap();
+        let stream_tb = octx.stream(stream_index).unwrap().time_base();
+
+        for (i, &frame_pts) in pts.iter().enumerate() {
+            let mut frame = VideoFrame::new(Pixel::YUV420P, width, height);
+            let level = (16 + (i * 37) % 220) as u8;
+            frame.data_mut(0).fill(level);
+            frame.data_mut(1).fill(128);
+            frame.data_mut(2).fill(128);
+            frame.set_pts(Some(frame_pts));
+            encoder.send_frame(&frame).unwrap();
+            drain_packets(&mut encoder, &mut octx, stream_index, encoder_tb, stream_tb);
+        }
+        encoder.send_eof().unwrap();
+        drain_packets(&mut encoder, &mut octx, stream_index, encoder_tb, stream_tb);
+        octx.write_trailer().unwrap();
+
+        path
+    }
+
+    /// Frame timestamp in seconds
+    fn frame_secs(path: &Path, frame: &VideoFrame) -> f64 {
+        let input_ctx = input(&path).unwrap();
+        let stream = input_ctx.streams().best(Type::Video).unwrap();
+        let pts = frame.timestamp().or(frame.pts()).unwrap();
+        (pts - stream_start_pts(&stream)) as f64 * f64::from(stream.time_base())
+    }
+
+    #[test]
+    fn test_output_format_defaults_to_jpeg_85() {
+        assert_eq!(OutputFormat::default(), OutputFormat::Jpeg { quality: 85 });
+
+        let webp: OutputFormat =
+            serde_json::from_str(r#"{"type":"webp","quality":75,"lossless":false}"#).unwrap();
+        assert_eq!(webp, OutputFormat::WebP { quality: 75, lossless: false });
+    }
+
+    #[test]
+    fn test_tonemap_pixel_keeps_sdr_range() {
+        // Black stays black
+        assert_eq!(tonemap_pixel([0.0, 0.0, 0.0], HdrTransfer::Pq), [0, 0, 0]);
+
+        // A 10,000 nit PQ highlight compresses below clipping without going grey
+        let peak = tonemap_pixel([1.0, 1.0, 1.0], HdrTransfer::Pq);
+        assert!(peak.iter().all(|&c| c > 200 && c < 255), "{:?}", peak);
+
+        // Reference white (~0.58 PQ) lands mid-high, not washed out or crushed
+        let white = tonemap_pixel([0.58, 0.58, 0.58], HdrTransfer::Pq);
+        assert!(white.iter().all(|&c| c > 150 && c < 230), "{:?}", white);
+    }
+
+    #[test]
+    fn test_mean_abs_diff_is_normalized() {
+        assert_eq!(mean_abs_diff(&[10, 20, 30], &[10, 20, 30]), 0.0);
+        assert_eq!(mean_abs_diff(&[0, 0], &[255, 255]), 1.0);
+        assert!((mean_abs_diff(&[0, 255], &[255, 255]) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_dhash_and_distance() {
+        // Brightness falling left to right sets every bit
+        let falling: Vec<u8> = (0..8).flat_map(|_| (0..9).rev().map(|x| x * 20)).collect();
+        assert_eq!(dhash(&falling), u64::MAX);
+        let flat = vec![100u8; 72];
+        assert_eq!(dhash(&flat), 0);
+
+        let a = format!("{:016x}{:016x}", u64::MAX, 0u64);
+        let b = format!("{:016x}{:016x}", 0u64, 0b1011u64);
+        assert_eq!(phash_distance(&a, &a).unwrap(), 0);
+        assert_eq!(phash_distance(&a, &b).unwrap(), 64 + 3);
+        assert!(phash_distance(&a, "abc").is_err());
+    }
+
+    #[test]
+    fn test_smart_filmstrip_samples_strongest_scenes() {
+        let cut = |timestamp_secs, score| SceneCut { timestamp_secs, score };
+        let cuts = [cut(2.0, 0.4), cut(5.0, 0.9), cut(8.0, 0.35)];
+
+        // Three scenes from the two strongest cuts, each sampled 0.5 s in
+        assert_eq!(smart_filmstrip_timestamps(10.0, &cuts, 3), vec![0.5, 2.5, 5.5]);
+
+        // Too few cuts: uniform midpoints
+        assert_eq!(
+            smart_filmstrip_timestamps(10.0, &cuts, 5),
+            evenly_spaced_midpoints(10.0, 5)
+        );
+    }
+
+    #[test]
+    fn test_poster_score_skips_black_and_prefers_detail() {
+        assert_eq!(poster_score(&[4u8; 16 * 9], 16, 9), None);
+
+        let flat = vec![128u8; 16 * 9];
+        let checker: Vec<u8> = (0..16 * 9).map(|i| if (i % 16 + i / 16) % 2 == 0 { 64 } else { 192 }).collect();
+        assert!(poster_score(&checker, 16, 9).unwrap() > poster_score(&flat, 16, 9).unwrap());
+    }
+
+    #[test]
+    fn test_image_sequence_detection() {
+        assert_eq!(parse_sequence_pattern("a/frame%04d.png"), Some(("a/frame", 4, ".png")));
+        assert_eq!(parse_sequence_pattern("a/frame%d.exr"), Some(("a/frame", 0, ".exr")));
+        assert_eq!(parse_sequence_pattern("a/frame.png"), None);
+
+        let dir = std::env::temp_dir().join(format!("seq_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        // 0003 is missing, so the contiguous run is 0001-0002
+        for n in [1, 2, 4] {
+            fs::write(dir.join(format!("frame{:04}.png", n)), b"").unwrap();
+        }
+
+        let frame = dir.join("frame0001.png").to_string_lossy().to_string();
+        let pattern = pattern_from_frame_path(&frame).unwrap();
+        assert!(pattern.ends_with("frame%04d.png"));
+        assert_eq!(scan_sequence(&pattern), Some((1, 2)));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_evenly_spaced_midpoints() {
+        assert_eq!(evenly_spaced_midpoints(10.0, 4), vec![1.25, 3.75, 6.25, 8.75]);
+        assert_eq!(evenly_spaced_midpoints(2.0, 1), vec![1.0]);
+    }
+
+    #[test]
+    fn test_preview_dimensions_preserve_aspect() {
+        assert_eq!(preview_dimensions(1920, 1080, 320), (320, 180));
+        // Never upscale past the source
+        assert_eq!(preview_dimensions(200, 100, 480), (200, 100));
+        // Odd results are rounded down to even
+        assert_eq!(preview_dimensions(1000, 333, 101), (100, 32));
+    }
+
+    #[test]
+    fn test_preview_loop_holds_frames_of_slow_sources() {
+        use image::AnimationDecoder;
+
+        // 5 fps source sampled at 10 fps: each source frame covers two samples
+        let pts: Vec<i64> = (0..20).collect();
+        let path = write_test_clip("preview_loop", 5, &pts);
+        let result = generate_preview_loop(path.to_str().unwrap(), 1.0, 1.0, 10.0, 64, PreviewLoopFormat::Gif);
+        let _ = std::fs::remove_file(&path);
+
+        let gif = BASE64.decode(result.unwrap()).unwrap();
+        let frames = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(gif))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 10);
+        let level = |i: usize| frames[i].buffer().get_pixel(0, 0)[0];
+        assert_eq!(level(0), level(1));
+        assert_ne!(level(1), level(2));
+    }
+
+    #[test]
+    fn test_handle_reads_match_fresh_decodes() {
+        let pts: Vec<i64> = (0..50).collect();
+        let path = write_test_clip("handle_reads", 25, &pts);
+        let path_str = path.to_str().unwrap();
+        let handle_id = open_video(path_str, None, OpenOptions::default()).unwrap();
+
+        // Forward steps, a repeat, a jump back and a jump past the window
+        let mut mismatches = Vec::new();
+        for secs in [0.0, 0.04, 0.08, 0.08, 0.5, 0.2, 1.9, 0.0] {
+            let via_handle = get_frame_from_handle(&handle_id, secs, OutputFormat::Png).unwrap();
+            let fresh = get_frame_at_time_with_options(path_str, secs, OutputFormat::Png, &FrameOptions::default())
+                .unwrap();
+            if via_handle != fresh {
+                mismatches.push(secs);
+            }
+        }
+        close_video(&handle_id).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(mismatches.is_empty(), "differs at {:?}", mismatches);
+    }
+
+    #[test]
+    fn test_frame_data_url_matches_format() {
+        let pts: Vec<i64> = (0..5).collect();
+        let path = write_test_clip("data_url", 25, &pts);
+        let path_str = path.to_str().unwrap();
+        let options = FrameOptions::default();
+
+        let png = get_frame_data_url(path_str, 0.0, OutputFormat::Png, &options).unwrap();
+        assert!(png.starts_with("data:image/png;base64,iVBOR"));
+        let webp = OutputFormat::WebP {
+            quality: 80,
+            lossless: false,
+        };
+        let webp = get_frame_data_url(path_str, 0.0, webp, &options).unwrap();
+        assert!(webp.starts_with("data:image/webp;base64,UklGR"));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_scaling_option_converts_to_rgb() {
+        let pts: Vec<i64> = (0..5).collect();
+        let path = write_test_clip("scaling", 25, &pts);
+        let path_str = path.to_str().unwrap();
+        let frame = decode_frame_at_time(path_str, 0.0, &FrameOptions::default()).unwrap();
+        let source_format = frame.format();
+
+        let options = FrameOptions {
+            scaling: ScalingAlgorithm::Lanczos,
+            ..Default::default()
+        };
+        let lanczos = apply_frame_options(frame.clone(), &options).unwrap();
+        assert_eq!(lanczos.format(), Pixel::RGB24);
+        assert_eq!((lanczos.width(), lanczos.height()), (frame.width(), frame.height()));
+
+        // The default leaves conversion to the encoder
+        let untouched = apply_frame_options(frame, &FrameOptions::default()).unwrap();
+        assert_eq!(untouched.format(), source_format);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_fast_poster_matches_first_frame() {
+        let pts: Vec<i64> = (0..50).collect();
+        let path = write_test_clip("fast_poster", 25, &pts);
+        let path_str = path.to_str().unwrap();
+        let options = FrameOptions::default();
+
+        let fast = decode_first_keyframe(path_str, &options).unwrap();
+        assert_eq!(frame_secs(&path, &fast), 0.0);
+        assert_eq!(
+            get_fast_poster(path_str, OutputFormat::Png, &options).unwrap(),
+            get_frame_at_time_with_options(path_str, 0.0, OutputFormat::Png, &options).unwrap()
+        );
+
+        let _ = fs::remove_file(&path);
+    }
+
+    /// Timing comparison; run with `cargo test bench_fast_poster -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_fast_poster_vs_first_frame() {
+        const RUNS: u32 = 20;
+        let pts: Vec<i64> = (0..250).collect();
+        let path = write_test_clip("bench_poster", 25, &pts);
+        let path_str = path.to_str().unwrap();
+        let options = FrameOptions::default();
+        let format = OutputFormat::default();
+
+        let started = Instant::now();
+        for _ in 0..RUNS {
+            get_fast_poster(path_str, format, &options).unwrap();
+        }
+        let fast = started.elapsed() / RUNS;
+
+        let started = Instant::now();
+        for _ in 0..RUNS {
+            get_first_frame(path_str).unwrap();
+        }
+        let first = started.elapsed() / RUNS;
+        let _ = std::fs::remove_file(&path);
+
+        eprintln!("get_fast_poster: {:?}/call, get_first_frame: {:?}/call", fast, first);
+    }
+
+    #[test]
+    fn test_vfr_detection() {
+        // Constant 25 fps
+        let pts: Vec<i64> = (0..50).collect();
+        let path = write_test_clip("cfr", 25, &pts);
+        let info = get_video_info(path.to_str().unwrap()).unwrap();
+        assert!(!info.is_vfr);
+        assert!((info.min_fps.unwrap() - 25.0).abs() < 0.5);
+        assert!((info.max_fps.unwrap() - 25.0).abs() < 0.5);
+        let _ = fs::remove_file(&path);
+
+        // Screen-recording style: 30 fps bursts, then 10 fps while idle
+        let mut pts: Vec<i64> = (0..30).map(|i| i * 33).collect();
+        pts.extend((1..=20).map(|i| 29 * 33 + i * 100));
+        let path = write_test_clip("vfr", 1000, &pts);
+        let info = get_video_info(path.to_str().unwrap()).unwrap();
+        assert!(info.is_vfr);
+        assert!((info.min_fps.unwrap() - 10.0).abs() < 0.5);
+        assert!((info.max_fps.unwrap() - 30.3).abs() < 0.5);
+        let _ = fs::remove_file(&path);
+
+        // Coarse time bases round a constant rate to alternating durations
+        let rounded: Vec<i64> = (0..30)
+            .map(|i| (i as f64 * 1000.0 / 30.0).round() as i64)
+            .collect();
+        let sample = frame_rate_sample(rounded, ffmpeg::Rational::new(1, 1000)).unwrap();
+        assert!(!sample.variable);
+    }
+
+    #[test]
+    fn test_interpolated_frame_blends_neighbours() {
+        // Each frame is a flat grey, brighter than the one before
+        let pts: Vec<i64> = (0..10).collect();
+        let path = write_test_clip("interpolate", 25, &pts);
+        let path_str = path.to_str().unwrap();
+        let options = FrameOptions::default();
+        let pixel = |img: &image::RgbImage| img.get_pixel(32, 24)[0] as f64;
+        let decoded = |secs: f64| {
+            let frame = decode_frame_at_time(path_str, secs, &options).unwrap();
+            pixel(&frame_to_rgb_image(&frame).unwrap())
+        };
+
+        let (first, second) = (decoded(0.04), decoded(0.08));
+        let quarter = pixel(&interpolated_rgb(path_str, 0.05, &options).unwrap());
+        let expected = first + (second - first) * 0.25;
+        assert!((quarter - expected).abs() <= 2.0, "expected {} got {}", expected, quarter);
+
+        // Exactly on a frame there is nothing to blend
+        assert_eq!(pixel(&interpolated_rgb(path_str, 0.04, &options).unwrap()), first);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_extract_frames_counts_from_stream_start() {
+        // Timestamps start at 1 s, so 0.0-0.5 s is the first 13 frames
+        let pts: Vec<i64> = (25..75).collect();
+        let path = write_test_clip("extract_offset", 25, &pts);
+        let out_dir = std::env::temp_dir().join(format!("extract-{}", uuid::Uuid::new_v4()));
+        let written = extract_frames_to_dir(
+            path.to_str().unwrap(),
+            0.0,
+            0.5,
+            out_dir.to_str().unwrap(),
+            1,
+            &AtomicBool::new(false),
+            |_, _| {},
+        );
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir_all(&out_dir);
+
+        assert_eq!(written.unwrap(), 13);
+    }
+
+    #[test]
+    fn test_seek_lands_within_one_frame_of_target() {
+        // 25 fps, 3 seconds, keyframe every 12 frames
+        let pts: Vec<i64> = (0..75).collect();
+        let path = write_test_clip("seek", 25, &pts);
+        let path_str = path.to_str().unwrap();
+
+        for target in [0.0, 0.52, 1.23, 1.96, 2.5] {
+            let frame = decode_frame_at_time(path_str, target, &FrameOptions::default()).unwrap();
+            let secs = frame_secs(&path, &frame);
+            assert!(
+                secs >= target - 1e-6 && secs - target < 1.0 / 25.0 + 1e-6,
+                "requested {} got {}",
+                target,
+                secs
+            );
+        }
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_out_of_range_timestamps_clamp_to_boundary_frames() {
+        // 25 fps, 3 seconds
+        let pts: Vec<i64> = (0..75).collect();
+        let path = write_test_clip("clamp", 25, &pts);
+        let path_str = path.to_str().unwrap();
+
+        let past_end = decode_frame_at_time(path_str, 60.0, &FrameOptions::default()).unwrap();
+        assert!((frame_secs(&path, &past_end) - 74.0 / 25.0).abs() < 1e-6);
+
+        let negative = decode_frame_at_time(path_str, -5.0, &FrameOptions::default()).unwrap();
+        assert!(frame_secs(&path, &negative).abs() < 1e-6);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_gop_structure_reports_picture_types() {
+        // Keyframe every 12 frames, no B-frames
+        let pts: Vec<i64> = (0..30).collect();
+        let path = write_test_clip("gop", 25, &pts);
+
+        let gop = get_gop_structure(path.to_str().unwrap(), Some(20)).unwrap();
+        assert_eq!(gop.len(), 20);
+        assert!(gop[0].is_keyframe && gop[0].frame_type == "I");
+        assert!(!gop[1].is_keyframe && gop[1].frame_type == "P");
+        assert!(gop[12].is_keyframe && gop[12].frame_type == "I");
+        assert!(gop.iter().all(|f| f.size_bytes > 0));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_nearest_keyframe_frame_stops_at_keyframe() {
+        // 25 fps with a keyframe every 12 frames
+        let pts: Vec<i64> = (0..50).collect();
+        let path = write_test_clip("keyframe_before", 25, &pts);
+
+        let (_, secs) = decode_keyframe_before(path.to_str().unwrap(), 1.0, &FrameOptions::default()).unwrap();
+        assert!((secs - 24.0 / 25.0).abs() < 1e-6);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_rotated_clip_reports_display_dimensions() {
+        assert_eq!(display_dimensions(1920, 1080, 180), (1920, 1080));
+
+        let pts: Vec<i64> = (0..5).collect();
+        let path = write_rotated_test_clip("rotated", 25, &pts, 90);
+        let info = get_video_info(path.to_str().unwrap()).unwrap();
+
+        assert_eq!(info.rotation, 90);
+        assert_eq!((info.width, info.height), (64, 48));
+        assert_eq!((info.display_width, info.display_height), (48, 64));
+
+        let _ = fs::remove_file(&path);
+    }
 
     #[test]
     fn test_video_error_display() {